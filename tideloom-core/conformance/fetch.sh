@@ -0,0 +1,13 @@
+#!/bin/sh
+# Replaces the bundled examples with those of a Serverless Workflow specification release (default: v1.0.0).
+set -eu
+
+tag="${1:-v1.0.0}"
+dir="$(cd "$(dirname "$0")" && pwd)/examples"
+checkout="$(mktemp -d)"
+trap 'rm -rf "$checkout"' EXIT
+
+git clone --quiet --depth 1 --branch "$tag" https://github.com/serverlessworkflow/specification "$checkout"
+rm -f "$dir"/*.yaml
+cp "$checkout"/examples/*.yaml "$dir"/
+echo "copied $(ls "$dir" | wc -l | tr -d ' ') examples of $tag into $dir"
@@ -0,0 +1,24 @@
+//! Runs Serverless Workflow examples against the engine and prints the conformance report.
+//!
+//! ```sh
+//! cargo run --example conformance [-- <dir>]
+//! ```
+//!
+//! Without a directory, runs the specification examples bundled in `conformance/examples`; see the README there
+//! to refresh them from a specification release.
+
+use std::path::PathBuf;
+
+use tideloom_core::conformance::{load_examples, run_examples};
+use tideloom_core::runtime::WorkflowContext;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let dir = match std::env::args_os().nth(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("conformance/examples"),
+    };
+    let report = run_examples(&WorkflowContext::default(), load_examples(&dir)?).await;
+    print!("{report}");
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Programmatic construction of workflows, for hosts that embed the engine without going through YAML.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use serverless_workflow_builders::services::task::GenericTaskDefinitionBuilder;
+use serverless_workflow_core::models::task::{CallTaskDefinition, TaskDefinition};
+use serverless_workflow_core::models::workflow::{WorkflowDefinition, WorkflowDefinitionMetadata};
+
+use crate::Workflow;
+use crate::nodes::workflow::WorkflowNode;
+use crate::runtime::StepResult;
+
+/// Builds a [`Workflow`] task by task.
+///
+/// Tasks are either given as definitions (see [`HttpCall`]) or configured through the
+/// `serverless_workflow_builders` task builder with [`WorkflowBuilder::task`].
+#[derive(Debug, Clone)]
+pub struct WorkflowBuilder {
+    definition: WorkflowDefinition,
+}
+
+impl WorkflowBuilder {
+    /// Starts a workflow in the `default` namespace.
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            definition: WorkflowDefinition::new(WorkflowDefinitionMetadata::new(
+                "default", name, version, None, None, None,
+            )),
+        }
+    }
+
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.definition.document.namespace = namespace.to_string();
+        self
+    }
+
+    /// Appends a task to the top-level `do` block.
+    pub fn do_task(mut self, name: &str, task: impl Into<TaskDefinition>) -> Self {
+        self.definition.do_.add(name.to_string(), task.into());
+        self
+    }
+
+    /// Appends a task configured through the generic task builder.
+    pub fn task<F>(self, name: &str, setup: F) -> Self
+    where
+        F: FnOnce(&mut GenericTaskDefinitionBuilder),
+    {
+        let mut builder = GenericTaskDefinitionBuilder::new();
+        setup(&mut builder);
+        self.do_task(name, builder.build())
+    }
+
+    /// Returns the workflow, failing if any of its tasks cannot be executed by this engine.
+    pub fn build(self) -> StepResult<Workflow> {
+        WorkflowNode::try_from_definition(&self.definition)?;
+        Ok(Workflow::from_definition(self.definition))
+    }
+}
+
+/// A `call: http` task.
+#[derive(Debug, Clone)]
+pub struct HttpCall {
+    with: HashMap<String, Value>,
+}
+
+impl HttpCall {
+    pub fn new(method: &str, endpoint: &str) -> Self {
+        let with = HashMap::from([
+            ("method".to_string(), Value::from(method)),
+            ("endpoint".to_string(), Value::from(endpoint)),
+        ]);
+        Self { with }
+    }
+
+    pub fn get(endpoint: &str) -> Self {
+        Self::new("get", endpoint)
+    }
+
+    pub fn post(endpoint: &str) -> Self {
+        Self::new("post", endpoint)
+    }
+
+    pub fn put(endpoint: &str) -> Self {
+        Self::new("put", endpoint)
+    }
+
+    pub fn delete(endpoint: &str) -> Self {
+        Self::new("delete", endpoint)
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.entry("headers").insert(name.to_string(), Value::from(value));
+        self
+    }
+
+    pub fn query(mut self, name: &str, value: &str) -> Self {
+        self.entry("query").insert(name.to_string(), Value::from(value));
+        self
+    }
+
+    pub fn body(mut self, body: Value) -> Self {
+        self.with.insert("body".to_string(), body);
+        self
+    }
+
+    /// Shape of the task output: `raw`, `content` (the default) or `response`.
+    pub fn output(mut self, output: &str) -> Self {
+        self.with.insert("output".to_string(), Value::from(output));
+        self
+    }
+
+    fn entry(&mut self, field: &str) -> &mut Map<String, Value> {
+        let value = self
+            .with
+            .entry(field.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!("`{field}` is only ever set to an object"),
+        }
+    }
+}
+
+impl From<HttpCall> for TaskDefinition {
+    fn from(call: HttpCall) -> Self {
+        TaskDefinition::Call(CallTaskDefinition::new("http", Some(call.with), None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::nodes::http::HTTPNode;
+    use crate::runtime::WorkflowContext;
+
+    #[tokio::test]
+    async fn builds_a_runnable_workflow() {
+        let workflow = WorkflowBuilder::new("greet", "1.0.0")
+            .namespace("test")
+            .task("echo", |task| {
+                task.run().shell().with_command("echo").with_argument("${ .name }");
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(workflow.definition().document.namespace, "test");
+        assert_eq!(workflow.definition().document.dsl, "1.0.0");
+        let output = workflow.run(&WorkflowContext::default(), json!({ "name": "ada" })).await.unwrap();
+        assert_eq!(output, json!("ada"));
+    }
+
+    #[test]
+    fn http_calls_become_call_tasks() {
+        let workflow = WorkflowBuilder::new("pets", "1.0.0")
+            .do_task(
+                "getPet",
+                HttpCall::get("https://petstore.example/pets/{petId}").header("accept", "application/json"),
+            )
+            .build()
+            .unwrap();
+
+        let task = workflow.definition().do_.entries[0].get("getPet").unwrap();
+        let node = HTTPNode::try_from_task(task).unwrap();
+        assert_eq!(node.method, reqwest::Method::GET);
+        assert_eq!(node.headers["accept"], "application/json");
+
+        let err = WorkflowBuilder::new("broken", "1.0.0")
+            .do_task("call", HttpCall::new("not a method", "https://example.com"))
+            .build()
+            .unwrap_err();
+        assert!(err.contains("invalid http method"), "{err}");
+    }
+}
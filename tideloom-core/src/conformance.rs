@@ -0,0 +1,219 @@
+//! Conformance harness: runs Serverless Workflow examples, such as the feature examples of the specification
+//! repository, against the engine and reports which DSL features work.
+//!
+//! The specification examples are bundled in `conformance/examples` of this crate, with a script to refresh them
+//! from a specification release; `cargo run --example conformance` prints their report.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+use serde_json::Value;
+use serverless_workflow_core::models::map::Map;
+use serverless_workflow_core::models::task::TaskDefinition;
+
+use crate::Workflow;
+use crate::nodes::doing::task_kind;
+use crate::runtime::WorkflowContext;
+
+/// How well the engine covers one DSL feature across the examples that use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    /// Every example using the feature ran.
+    Supported,
+    /// Some examples using the feature ran and some did not.
+    Partial,
+    /// No example using the feature ran.
+    Unsupported,
+}
+
+/// Outcome of one example.
+#[derive(Debug, Clone)]
+pub struct ExampleResult {
+    pub name: String,
+    /// Task kinds the example uses, e.g. `switch` or `http`; empty if the document could not be loaded.
+    pub features: BTreeSet<String>,
+    /// Why the example did not run: a load error, unsupported tasks, or the error of the run.
+    pub error: Option<String>,
+}
+
+/// Results of a [`run_examples`] pass.
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    pub examples: Vec<ExampleResult>,
+}
+
+impl ConformanceReport {
+    /// Support of every feature used by at least one example.
+    pub fn features(&self) -> BTreeMap<String, Support> {
+        let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for example in &self.examples {
+            for feature in &example.features {
+                let (passed, total) = counts.entry(feature).or_default();
+                *passed += usize::from(example.error.is_none());
+                *total += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .map(|(feature, (passed, total))| {
+                let support = match passed {
+                    0 => Support::Unsupported,
+                    passed if passed == total => Support::Supported,
+                    _ => Support::Partial,
+                };
+                (feature.to_string(), support)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (feature, support) in self.features() {
+            let support = match support {
+                Support::Supported => "supported",
+                Support::Partial => "partially supported",
+                Support::Unsupported => "unsupported",
+            };
+            writeln!(f, "{feature}: {support}")?;
+        }
+        for example in &self.examples {
+            if let Some(error) = &example.error {
+                writeln!(f, "{} failed: {error}", example.name)?;
+            }
+        }
+        let passed = self.examples.iter().filter(|example| example.error.is_none()).count();
+        writeln!(f, "{passed} of {} examples passed", self.examples.len())
+    }
+}
+
+/// Reads every `.yaml`, `.yml` and `.json` file of `dir` as a named example, in file name order.
+pub fn load_examples(dir: &Path) -> std::io::Result<Vec<(String, String)>> {
+    let mut examples = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml" | "json")) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            examples.push((name, std::fs::read_to_string(&path)?));
+        }
+    }
+    examples.sort();
+    Ok(examples)
+}
+
+/// Runs each `(name, document)` example with `ctx` and an empty object as input.
+///
+/// An example fails if its document cannot be loaded, if [`Workflow::check_support`] reports tasks `ctx` cannot
+/// execute, or if its run returns an error.
+pub async fn run_examples(
+    ctx: &WorkflowContext,
+    examples: impl IntoIterator<Item = (String, String)>,
+) -> ConformanceReport {
+    let mut report = ConformanceReport::default();
+    for (name, document) in examples {
+        let (features, error) = match Workflow::try_from_yaml(&document) {
+            Err(e) => (BTreeSet::new(), Some(e.to_string())),
+            Ok(workflow) => {
+                let mut features = BTreeSet::new();
+                collect_features(&workflow.definition().do_, &mut features);
+                let unsupported = workflow.check_support(ctx);
+                let error = if unsupported.is_empty() {
+                    workflow.run(ctx, Value::Object(Default::default())).await.err().map(String::from)
+                } else {
+                    Some(unsupported.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))
+                };
+                (features, error)
+            }
+        };
+        report.examples.push(ExampleResult { name, features, error });
+    }
+    report
+}
+
+fn collect_features(tasks: &Map<String, TaskDefinition>, features: &mut BTreeSet<String>) {
+    for (_, task) in tasks.entries.iter().flat_map(|entry| entry.iter()) {
+        features.insert(task_kind(task));
+        match task {
+            TaskDefinition::Do(def) => collect_features(&def.do_, features),
+            TaskDefinition::For(def) => collect_features(&def.do_, features),
+            TaskDefinition::Fork(def) => collect_features(&def.fork.branches, features),
+            TaskDefinition::Try(def) => {
+                collect_features(&def.try_, features);
+                if let Some(handler) = &def.catch.do_ {
+                    collect_features(handler, features);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::messaging::InMemoryEventBus;
+
+    #[tokio::test]
+    async fn features_are_graded_across_examples() {
+        let switch = r#"
+document: { dsl: '1.0.0', namespace: test, name: switch, version: '0.1.0' }
+do:
+  - route:
+      switch:
+        - otherwise:
+            then: end
+"#;
+        let grpc = r#"
+document: { dsl: '1.0.0', namespace: test, name: grpc, version: '0.1.0' }
+do:
+  - route:
+      switch:
+        - otherwise:
+            then: continue
+  - greet:
+      call: grpc
+      with: { service: { name: Greeter, host: localhost }, method: SayHello }
+"#;
+        let examples = [("switch.yaml", switch), ("grpc.yaml", grpc), ("broken.yaml", "do: 1")];
+        let examples = examples.map(|(name, document)| (name.to_string(), document.to_string()));
+        let report = run_examples(&WorkflowContext::default(), examples).await;
+
+        let features = report.features();
+        assert_eq!(features["switch"], Support::Partial);
+        assert_eq!(features["grpc"], Support::Unsupported);
+        assert!(report.examples[0].error.is_none(), "{:?}", report.examples[0].error);
+        assert!(report.examples[2].error.is_some());
+        assert!(report.to_string().contains("grpc: unsupported"), "{report}");
+        assert!(report.to_string().ends_with("1 of 3 examples passed\n"), "{report}");
+    }
+
+    #[tokio::test]
+    async fn runs_bundled_spec_examples() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("conformance/examples");
+        let examples = load_examples(&dir).unwrap();
+        assert!(examples.iter().any(|(name, _)| name == "do-single.yaml"));
+
+        // Examples that may call HTTP services are left to `cargo run --example conformance`. Each offline example is
+        // paired with whether it is expected to pass against a context that only has an in-memory event sink.
+        let offline = [
+            ("call-grpc.yaml", false),
+            ("emit.yaml", true),
+            ("listen-to-any.yaml", false),
+            ("raise-inline.yaml", false),
+            ("run-subflow.yaml", false),
+        ];
+        let examples = examples.into_iter().filter(|(name, _)| offline.iter().any(|(offline, _)| name == offline));
+        let ctx = WorkflowContext::default().with_event_sink(Arc::new(InMemoryEventBus::default()));
+        let report = run_examples(&ctx, examples).await;
+
+        assert_eq!(report.examples.len(), offline.len());
+        assert!(report.examples.iter().all(|example| !example.features.is_empty()), "{report}");
+        for (name, passes) in offline {
+            let example = report.examples.iter().find(|example| example.name == name).unwrap();
+            assert_eq!(example.error.is_none(), passes, "{name}: {:?}", example.error);
+        }
+    }
+}
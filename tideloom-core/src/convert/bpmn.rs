@@ -0,0 +1,569 @@
+//! Converter from a pragmatic BPMN 2.0 subset to Serverless Workflow 1.0 definitions.
+//!
+//! Supported elements: start/end events, service, script and plain tasks, intermediate timer catch
+//! events with a `timeDuration`, exclusive gateways (conditions are copied verbatim into `when`) and
+//! parallel gateways whose branches are linear and join on a single parallel gateway.
+//! Flow nodes are emitted as tasks named after their BPMN id, linked with explicit `then` directives.
+//! Everything else is reported as a [`ConversionIssue`] whose path is the element id.
+
+use std::collections::{HashMap, HashSet};
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use serverless_workflow_core::models::duration::OneOfDurationOrIso8601Expression;
+use serverless_workflow_core::models::map::Map;
+use serverless_workflow_core::models::task::{
+    BranchingDefinition, CallTaskDefinition, DoTaskDefinition, ForkTaskDefinition, ProcessTypeDefinition,
+    RunTaskDefinition, ScriptProcessDefinition, SetTaskDefinition, SwitchCaseDefinition, SwitchTaskDefinition,
+    TaskDefinition, TaskDefinitionFields, WaitTaskDefinition,
+};
+use serverless_workflow_core::models::workflow::{DEFAULT_NAMESPACE, WorkflowDefinition, WorkflowDefinitionMetadata};
+
+use crate::convert::{Conversion, ConversionIssue, kebab_case};
+use crate::nodes::doing::task_fields_mut;
+
+/// A BPMN flow node of the converted process.
+#[derive(Debug, Clone, Default)]
+struct FlowNode {
+    kind: String,
+    name: Option<String>,
+    implementation: Option<String>,
+    script_format: Option<String>,
+    script: Option<String>,
+    timer: Option<Timer>,
+    default_flow: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Timer {
+    Duration(String),
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone)]
+struct SequenceFlow {
+    id: String,
+    source: String,
+    target: String,
+    condition: Option<String>,
+}
+
+/// Converts the first `process` of a BPMN 2.0 XML document.
+pub fn convert_str(xml: &str) -> Result<Conversion, String> {
+    let mut converter = Converter::parse(xml)?;
+    let definition = converter.workflow()?;
+    Ok(Conversion {
+        definition,
+        issues: converter.issues,
+    })
+}
+
+#[derive(Default)]
+struct Converter {
+    process_id: String,
+    process_name: Option<String>,
+    /// Flow node ids in document order.
+    order: Vec<String>,
+    nodes: HashMap<String, FlowNode>,
+    flows: Vec<SequenceFlow>,
+    issues: Vec<ConversionIssue>,
+}
+
+impl Converter {
+    fn parse(xml: &str) -> Result<Self, String> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut converter = Self::default();
+        let mut processes = 0;
+        let mut in_process = false;
+        // Depth inside an element whose content is skipped, such as a sub-process.
+        let mut skip_depth = 0usize;
+        let mut current: Option<String> = None;
+        let mut text_target: Option<&'static str> = None;
+
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| format!("invalid BPMN document at {}: {e}", reader.buffer_position()))?;
+            let (element, empty) = match &event {
+                Event::Start(e) => (Some(e), false),
+                Event::Empty(e) => (Some(e), true),
+                _ => (None, false),
+            };
+
+            if let Some(element) = element {
+                if skip_depth > 0 {
+                    if !empty {
+                        skip_depth += 1;
+                    }
+                    continue;
+                }
+                let local = local_name(element);
+                let id = attribute(element, "id")?;
+                match local.as_str() {
+                    "process" => {
+                        processes += 1;
+                        if processes > 1 {
+                            converter.issue(id.unwrap_or_default(), "only the first process is converted");
+                            skip_depth = usize::from(!empty);
+                            continue;
+                        }
+                        converter.process_id = id.unwrap_or_else(|| "process".to_string());
+                        converter.process_name = attribute(element, "name")?;
+                        in_process = !empty;
+                    }
+                    _ if !in_process => {}
+                    "sequenceFlow" => {
+                        let id = id.unwrap_or_default();
+                        let (Some(source), Some(target)) =
+                            (attribute(element, "sourceRef")?, attribute(element, "targetRef")?)
+                        else {
+                            return Err(format!("sequence flow '{id}' requires a sourceRef and a targetRef"));
+                        };
+                        converter.flows.push(SequenceFlow {
+                            id: id.clone(),
+                            source,
+                            target,
+                            condition: None,
+                        });
+                        if !empty {
+                            current = Some(id);
+                        }
+                    }
+                    "conditionExpression" => text_target = Some("condition"),
+                    "script" => text_target = Some("script"),
+                    "timeDuration" => text_target = Some("timeDuration"),
+                    "timeDate" | "timeCycle" => {
+                        if let Some(node) = current.as_ref().and_then(|id| converter.nodes.get_mut(id)) {
+                            node.timer = Some(Timer::Unsupported(local.clone()));
+                        }
+                    }
+                    "subProcess" | "adHocSubProcess" | "transaction" => {
+                        converter.issue(id.unwrap_or_default(), format!("{local} is not supported and was dropped"));
+                        skip_depth = usize::from(!empty);
+                    }
+                    _ if id.is_some() && is_flow_node(&local) => {
+                        let id = id.unwrap_or_default();
+                        converter.order.push(id.clone());
+                        converter.nodes.insert(
+                            id.clone(),
+                            FlowNode {
+                                kind: local.clone(),
+                                name: attribute(element, "name")?,
+                                implementation: attribute(element, "implementation")?
+                                    .or(attribute(element, "operationRef")?),
+                                script_format: attribute(element, "scriptFormat")?,
+                                default_flow: attribute(element, "default")?,
+                                ..Default::default()
+                            },
+                        );
+                        if !empty {
+                            current = Some(id);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            match event {
+                Event::End(_) if skip_depth > 0 => skip_depth -= 1,
+                Event::End(e) => match String::from_utf8_lossy(e.local_name().as_ref()).as_ref() {
+                    "process" => in_process = false,
+                    "conditionExpression" | "script" | "timeDuration" => text_target = None,
+                    local if local == "sequenceFlow" || is_flow_node(local) => current = None,
+                    _ => {}
+                },
+                Event::Text(_) | Event::CData(_) if skip_depth == 0 => {
+                    let text = match &event {
+                        Event::Text(t) => t.unescape().map_err(|e| format!("invalid BPMN text: {e}"))?.into_owned(),
+                        Event::CData(c) => String::from_utf8_lossy(c.as_ref()).into_owned(),
+                        _ => unreachable!(),
+                    };
+                    let Some(id) = current.as_ref() else { continue };
+                    match text_target {
+                        Some("condition") => {
+                            if let Some(flow) = converter.flows.iter_mut().find(|f| &f.id == id) {
+                                flow.condition = Some(text.trim().to_string());
+                            }
+                        }
+                        Some("script") => {
+                            if let Some(node) = converter.nodes.get_mut(id) {
+                                node.script = Some(text.trim().to_string());
+                            }
+                        }
+                        Some("timeDuration") => {
+                            if let Some(node) = converter.nodes.get_mut(id) {
+                                node.timer = Some(Timer::Duration(text.trim().to_string()));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if processes == 0 {
+            return Err("BPMN document contains no process".into());
+        }
+        Ok(converter)
+    }
+
+    fn issue(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ConversionIssue {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn outgoing(&self, id: &str) -> Vec<SequenceFlow> {
+        self.flows.iter().filter(|f| f.source == id).cloned().collect()
+    }
+
+    fn kind(&self, id: &str) -> &str {
+        self.nodes.get(id).map(|n| n.kind.as_str()).unwrap_or_default()
+    }
+
+    /// Resolves the `then` target of a flow into `id`, passing through end events and joining gateways.
+    fn target(&self, id: &str) -> Option<String> {
+        let mut id = id.to_string();
+        let mut seen = HashSet::new();
+        loop {
+            if self.kind(&id) == "endEvent" {
+                return Some("end".to_string());
+            }
+            let outgoing = self.outgoing(&id);
+            if self.kind(&id) == "exclusiveGateway" && outgoing.len() == 1 && seen.insert(id.clone()) {
+                id = outgoing[0].target.clone();
+                continue;
+            }
+            return Some(id);
+        }
+    }
+
+    fn workflow(&mut self) -> Result<WorkflowDefinition, String> {
+        let mut definition = WorkflowDefinition::new(WorkflowDefinitionMetadata::new(
+            DEFAULT_NAMESPACE,
+            &kebab_case(&self.process_id),
+            "0.1.0",
+            self.process_name.clone(),
+            None,
+            None,
+        ));
+
+        let starts: Vec<String> = self.order.iter().filter(|id| self.kind(id) == "startEvent").cloned().collect();
+        let start = match starts.as_slice() {
+            [start] => start.clone(),
+            [] => return Err(format!("process '{}' has no start event", self.process_id)),
+            [start, ..] => {
+                self.issue(self.process_id.clone(), "only the first start event is converted");
+                start.clone()
+            }
+        };
+
+        // Walk the graph from the start event; nodes consumed by parallel branches are not emitted at the top level.
+        let mut visited = HashSet::new();
+        let mut consumed = HashSet::new();
+        let mut pending: Vec<String> = self.outgoing(&start).into_iter().map(|f| f.target).collect();
+        pending.reverse();
+        let mut tasks = Vec::new();
+        while let Some(id) = pending.pop() {
+            let Some(id) = self.target(&id) else { continue };
+            if id == "end" || !visited.insert(id.clone()) || consumed.contains(&id) {
+                continue;
+            }
+            let Some((task, next)) = self.node_task(&id, &mut consumed)? else {
+                continue;
+            };
+            tasks.push((id, task));
+            pending.extend(next.into_iter().rev());
+        }
+
+        for id in &self.order.clone() {
+            let kind = self.kind(id).to_string();
+            if !visited.contains(id) && !consumed.contains(id) && !matches!(kind.as_str(), "startEvent" | "endEvent")
+                && !(kind == "exclusiveGateway" && self.outgoing(id).len() == 1)
+            {
+                self.issue(id.clone(), "unreachable from the start event; dropped");
+            }
+        }
+
+        definition.do_ = Map::from(tasks);
+        Ok(definition)
+    }
+
+    /// Translates a top-level flow node, returning its task and the nodes to visit next.
+    fn node_task(
+        &mut self,
+        id: &str,
+        consumed: &mut HashSet<String>,
+    ) -> Result<Option<(TaskDefinition, Vec<String>)>, String> {
+        let outgoing = self.outgoing(id);
+        let kind = self.kind(id).to_string();
+        match kind.as_str() {
+            "exclusiveGateway" => {
+                let default_flow = self.nodes[id].default_flow.clone();
+                let mut cases = Vec::new();
+                let mut default = None;
+                for flow in &outgoing {
+                    let case = SwitchCaseDefinition {
+                        when: None,
+                        then: self.target(&flow.target),
+                    };
+                    if default_flow.as_deref() == Some(flow.id.as_str()) || flow.condition.is_none() {
+                        if default.is_some() {
+                            self.issue(flow.id.clone(), "more than one unconditional flow; extra flows dropped");
+                            continue;
+                        }
+                        default = Some(case);
+                        continue;
+                    }
+                    cases.push((flow.id.clone(), SwitchCaseDefinition {
+                        when: flow.condition.clone(),
+                        ..case
+                    }));
+                }
+                if let Some(default) = default {
+                    cases.push(("default".to_string(), default));
+                }
+                let next = outgoing.into_iter().map(|f| f.target).collect();
+                let task = TaskDefinition::Switch(SwitchTaskDefinition {
+                    switch: Map::from(cases),
+                    common: TaskDefinitionFields::new(),
+                });
+                Ok(Some((task, next)))
+            }
+            "parallelGateway" if outgoing.len() > 1 => {
+                let Some((fork, join)) = self.fork(id, &outgoing, consumed)? else {
+                    return Ok(None);
+                };
+                let mut task = TaskDefinition::Fork(fork);
+                let after = self.outgoing(&join);
+                task_fields_mut(&mut task).then = after.first().and_then(|f| self.target(&f.target));
+                Ok(Some((task, after.into_iter().map(|f| f.target).collect())))
+            }
+            _ => {
+                let Some(mut task) = self.activity(id) else {
+                    return Ok(None);
+                };
+                if outgoing.len() > 1 {
+                    self.issue(id, "implicit parallel split on an activity; only the first flow is followed");
+                }
+                if outgoing.iter().any(|f| f.condition.is_some()) {
+                    self.issue(id, "conditional flows outside a gateway are not supported; condition dropped");
+                }
+                task_fields_mut(&mut task).then = outgoing.first().and_then(|f| self.target(&f.target));
+                Ok(Some((task, outgoing.into_iter().take(1).map(|f| f.target).collect())))
+            }
+        }
+    }
+
+    /// Builds a fork from a parallel split whose branches are linear and meet at one parallel join.
+    fn fork(
+        &mut self,
+        split: &str,
+        outgoing: &[SequenceFlow],
+        consumed: &mut HashSet<String>,
+    ) -> Result<Option<(ForkTaskDefinition, String)>, String> {
+        let mut branches = Vec::new();
+        let mut join: Option<String> = None;
+        for flow in outgoing {
+            let mut branch = Vec::new();
+            let mut id = flow.target.clone();
+            loop {
+                if self.kind(&id) == "parallelGateway" {
+                    break;
+                }
+                let next = self.outgoing(&id);
+                if next.len() != 1 || self.kind(&id).ends_with("Gateway") || self.kind(&id) == "endEvent" {
+                    self.issue(split, "parallel branches must be linear and end in a parallel join; split dropped");
+                    return Ok(None);
+                }
+                if let Some(task) = self.activity(&id) {
+                    branch.push((id.clone(), task));
+                }
+                consumed.insert(id.clone());
+                id = next[0].target.clone();
+            }
+            match &join {
+                Some(existing) if existing != &id => {
+                    self.issue(split, "parallel branches join on different gateways; split dropped");
+                    return Ok(None);
+                }
+                _ => join = Some(id),
+            }
+            let task = match branch.len() {
+                1 => branch.pop().map(|(_, task)| task).expect("one task"),
+                _ => TaskDefinition::Do(DoTaskDefinition::new(Map::from(branch))),
+            };
+            branches.push((flow.id.clone(), task));
+        }
+        let join = join.ok_or_else(|| format!("parallel gateway '{split}' has no branches"))?;
+        consumed.insert(join.clone());
+        Ok(Some((
+            ForkTaskDefinition::new(BranchingDefinition::new(Map::from(branches), false)),
+            join,
+        )))
+    }
+
+    /// Translates an activity or intermediate event, without its outgoing flow.
+    fn activity(&mut self, id: &str) -> Option<TaskDefinition> {
+        let node = self.nodes.get(id)?.clone();
+        match node.kind.as_str() {
+            "serviceTask" | "sendTask" => {
+                let function = node
+                    .implementation
+                    .filter(|i| !i.starts_with("##"))
+                    .unwrap_or_else(|| kebab_case(node.name.as_deref().unwrap_or(id)));
+                self.issue(id, format!("service task calls function '{function}'; declare it under `use.functions`"));
+                Some(TaskDefinition::Call(CallTaskDefinition::new(&function, None, None)))
+            }
+            "scriptTask" => {
+                let language = node.script_format.unwrap_or_else(|| "javascript".to_string());
+                let language = language.rsplit('/').next().unwrap_or_default().to_string();
+                let script = ScriptProcessDefinition {
+                    language,
+                    code: node.script,
+                    source: None,
+                    arguments: None,
+                    environment: None,
+                };
+                Some(TaskDefinition::Run(RunTaskDefinition::new(ProcessTypeDefinition::using_script(
+                    script, None,
+                ))))
+            }
+            "task" => Some(TaskDefinition::Set(SetTaskDefinition {
+                set: HashMap::new(),
+                common: TaskDefinitionFields::new(),
+            })),
+            "intermediateCatchEvent" => match node.timer {
+                Some(Timer::Duration(duration)) => Some(TaskDefinition::Wait(WaitTaskDefinition::new(
+                    OneOfDurationOrIso8601Expression::Iso8601Expression(duration),
+                ))),
+                Some(Timer::Unsupported(kind)) => {
+                    self.issue(id, format!("timer `{kind}` is not supported; only `timeDuration` converts to a wait"));
+                    None
+                }
+                None => {
+                    self.issue(id, "only timer catch events are supported");
+                    None
+                }
+            },
+            "parallelGateway" => None,
+            other => {
+                self.issue(id, format!("`{other}` is not supported and was dropped"));
+                None
+            }
+        }
+    }
+}
+
+fn local_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).into_owned()
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>, String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name.as_bytes())
+        .map(|a| a.unescape_value().map(|v| v.into_owned()))
+        .transpose()
+        .map_err(|e| format!("invalid BPMN attribute '{name}': {e}"))
+}
+
+fn is_flow_node(local: &str) -> bool {
+    local.ends_with("Task")
+        || local.ends_with("Event")
+        || local.ends_with("Gateway")
+        || matches!(local, "task" | "callActivity")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDER_PROCESS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<bpmn:definitions xmlns:bpmn="http://www.omg.org/spec/BPMN/20100524/MODEL">
+  <bpmn:process id="orderProcess" name="Order Process">
+    <bpmn:startEvent id="start" />
+    <bpmn:sequenceFlow id="f1" sourceRef="start" targetRef="check" />
+    <bpmn:exclusiveGateway id="check" default="f3" />
+    <bpmn:sequenceFlow id="f2" sourceRef="check" targetRef="split">
+      <bpmn:conditionExpression>${ .approved }</bpmn:conditionExpression>
+    </bpmn:sequenceFlow>
+    <bpmn:sequenceFlow id="f3" sourceRef="check" targetRef="reject" />
+    <bpmn:serviceTask id="reject" implementation="rejectOrder" />
+    <bpmn:sequenceFlow id="f4" sourceRef="reject" targetRef="end" />
+    <bpmn:parallelGateway id="split" />
+    <bpmn:sequenceFlow id="f5" sourceRef="split" targetRef="charge" />
+    <bpmn:sequenceFlow id="f6" sourceRef="split" targetRef="pause" />
+    <bpmn:serviceTask id="charge" name="Charge Card" />
+    <bpmn:intermediateCatchEvent id="pause">
+      <bpmn:timerEventDefinition><bpmn:timeDuration>PT5M</bpmn:timeDuration></bpmn:timerEventDefinition>
+    </bpmn:intermediateCatchEvent>
+    <bpmn:sequenceFlow id="f7" sourceRef="charge" targetRef="join" />
+    <bpmn:sequenceFlow id="f8" sourceRef="pause" targetRef="join" />
+    <bpmn:parallelGateway id="join" />
+    <bpmn:sequenceFlow id="f9" sourceRef="join" targetRef="notify" />
+    <bpmn:scriptTask id="notify" scriptFormat="python"><bpmn:script><![CDATA[print("done")]]></bpmn:script></bpmn:scriptTask>
+    <bpmn:sequenceFlow id="f10" sourceRef="notify" targetRef="end" />
+    <bpmn:userTask id="review" />
+    <bpmn:endEvent id="end" />
+  </bpmn:process>
+</bpmn:definitions>"#;
+
+    fn names(definition: &WorkflowDefinition) -> Vec<String> {
+        definition.do_.entries.iter().flat_map(|e| e.keys().cloned()).collect()
+    }
+
+    #[test]
+    fn convert_gateways_tasks_and_timers() {
+        let conversion = convert_str(ORDER_PROCESS).unwrap();
+        let definition = &conversion.definition;
+
+        assert_eq!(definition.document.name, "order-process");
+        assert_eq!(definition.document.title.as_deref(), Some("Order Process"));
+        assert_eq!(names(definition), ["check", "split", "notify", "reject"]);
+
+        let tasks: HashMap<_, _> = definition.do_.entries.iter().flatten().map(|(k, v)| (k.as_str(), v)).collect();
+        let TaskDefinition::Switch(switch) = tasks["check"] else {
+            panic!("expected a switch task");
+        };
+        let approved = switch.switch.entries[0].get("f2").unwrap();
+        assert_eq!(approved.when.as_deref(), Some("${ .approved }"));
+        assert_eq!(approved.then.as_deref(), Some("split"));
+        assert_eq!(switch.switch.entries[1]["default"].then.as_deref(), Some("reject"));
+
+        let TaskDefinition::Fork(fork) = tasks["split"] else {
+            panic!("expected a fork task");
+        };
+        assert_eq!(fork.common.then.as_deref(), Some("notify"));
+        let branches: HashMap<_, _> =
+            fork.fork.branches.entries.iter().flatten().map(|(k, v)| (k.as_str(), v)).collect();
+        assert!(matches!(branches["f5"], TaskDefinition::Call(call) if call.call == "charge-card"));
+        assert!(matches!(branches["f6"], TaskDefinition::Wait(_)));
+
+        let TaskDefinition::Run(run) = tasks["notify"] else {
+            panic!("expected a run task");
+        };
+        let script = run.run.script.as_ref().unwrap();
+        assert_eq!(script.language, "python");
+        assert_eq!(script.code.as_deref(), Some(r#"print("done")"#));
+        assert_eq!(run.common.then.as_deref(), Some("end"));
+    }
+
+    #[test]
+    fn convert_reports_unsupported_elements() {
+        let conversion = convert_str(ORDER_PROCESS).unwrap();
+        let paths: Vec<_> = conversion.issues.iter().map(|i| i.path.as_str()).collect();
+        // Service tasks need their functions declared; the user task is unreachable.
+        assert_eq!(paths, ["charge", "reject", "review"]);
+
+        let err = convert_str("<definitions/>").unwrap_err();
+        assert!(err.contains("no process"), "{err}");
+    }
+}
@@ -0,0 +1,47 @@
+//! Best-effort converters from other workflow formats into Serverless Workflow 1.0 definitions.
+
+pub mod bpmn;
+pub mod v08;
+
+use serverless_workflow_core::models::workflow::WorkflowDefinition;
+
+/// A construct of the source document that could not be translated faithfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionIssue {
+    /// Location of the construct in the source document, e.g. `states[2].onErrors`.
+    pub path: String,
+    pub message: String,
+}
+
+/// Result of a conversion: the translated definition plus everything that was dropped or approximated.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub definition: WorkflowDefinition,
+    pub issues: Vec<ConversionIssue>,
+}
+
+impl Conversion {
+    /// Returns whether the source document was translated without any issue.
+    pub fn is_lossless(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Turns identifiers such as `orderWorkflow` or `Order Process` into DSL names (`order-workflow`).
+pub(crate) fn kebab_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && prev_lower {
+                out.push('-');
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            out.push(c.to_ascii_lowercase());
+        } else if !out.ends_with('-') && !out.is_empty() {
+            out.push('-');
+            prev_lower = false;
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
@@ -0,0 +1,743 @@
+//! Converter from Serverless Workflow 0.8 (states-based) documents to 1.0 `do`-based definitions.
+//!
+//! States become tasks named after the state, with `transition`/`end` mapped to explicit `then` directives.
+//! Anything without a 1.0 equivalent is dropped and reported as a [`ConversionIssue`].
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use serverless_workflow_core::models::duration::OneOfDurationOrIso8601Expression;
+use serverless_workflow_core::models::event::{EventConsumptionStrategyDefinition, EventFilterDefinition};
+use serverless_workflow_core::models::input::InputDataModelDefinition;
+use serverless_workflow_core::models::map::Map;
+use serverless_workflow_core::models::output::OutputDataModelDefinition;
+use serverless_workflow_core::models::resource::{
+    EndpointDefinition, ExternalResourceDefinition, OneOfEndpointDefinitionOrUri,
+};
+use serverless_workflow_core::models::schema::{SchemaDefinition, SchemaFormat};
+use serverless_workflow_core::models::task::{
+    BranchingDefinition, CallTaskDefinition, DoTaskDefinition, ForLoopDefinition, ForTaskDefinition,
+    ForkTaskDefinition, ListenTaskDefinition, ListenerDefinition, ProcessTypeDefinition, RunTaskDefinition,
+    SetTaskDefinition, SwitchCaseDefinition, SwitchTaskDefinition, TaskDefinition, TaskDefinitionFields,
+    WaitTaskDefinition, WorkflowProcessDefinition,
+};
+use serverless_workflow_core::models::workflow::{
+    ComponentDefinitionCollection, DEFAULT_NAMESPACE, WorkflowDefinition, WorkflowDefinitionMetadata,
+};
+
+use crate::convert::{Conversion, ConversionIssue, kebab_case};
+use crate::nodes::doing::task_fields_mut;
+
+/// Top-level 0.8 properties that have no 1.0 counterpart handled by the converter.
+const UNSUPPORTED_TOP_LEVEL: [&str; 8] = [
+    "auth",
+    "autoRetries",
+    "constants",
+    "errors",
+    "extensions",
+    "keepActive",
+    "retries",
+    "timeouts",
+];
+
+/// State properties that have no 1.0 counterpart handled by the converter.
+const UNSUPPORTED_STATE: [&str; 4] = ["compensatedBy", "onErrors", "timeouts", "usedForCompensation"];
+
+/// Converts a 0.8 document given as JSON or YAML text.
+pub fn convert_str(text: &str) -> Result<Conversion, String> {
+    let doc: Value = serde_json::from_str(text)
+        .or_else(|_| serde_yaml::from_str(text))
+        .map_err(|e| format!("invalid 0.8 workflow document: {e}"))?;
+    convert(&doc)
+}
+
+/// Converts a parsed 0.8 document.
+pub fn convert(doc: &Value) -> Result<Conversion, String> {
+    let mut converter = Converter::new(doc);
+    let definition = converter.workflow()?;
+    Ok(Conversion {
+        definition,
+        issues: converter.issues,
+    })
+}
+
+struct Converter<'a> {
+    doc: &'a Value,
+    functions: HashMap<&'a str, &'a Value>,
+    events: HashMap<&'a str, &'a Value>,
+    issues: Vec<ConversionIssue>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(doc: &'a Value) -> Self {
+        let index = |key: &str| {
+            doc.get(key)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|item| Some((item.get("name")?.as_str()?, item)))
+                .collect::<HashMap<_, _>>()
+        };
+        Self {
+            doc,
+            functions: index("functions"),
+            events: index("events"),
+            issues: Vec::new(),
+        }
+    }
+
+    fn issue(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ConversionIssue {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn workflow(&mut self) -> Result<WorkflowDefinition, String> {
+        let doc = self.doc;
+        if let Some(version) = str_field(doc, "specVersion")
+            && !version.starts_with("0.8")
+        {
+            self.issue("specVersion", format!("expected a 0.8 document, got specVersion '{version}'"));
+        }
+        let id = str_field(doc, "id")
+            .or_else(|| str_field(doc, "key"))
+            .ok_or_else(|| "0.8 workflow requires an `id`".to_string())?;
+
+        let mut definition = WorkflowDefinition::new(WorkflowDefinitionMetadata::new(
+            DEFAULT_NAMESPACE,
+            &kebab_case(id),
+            &semver(str_field(doc, "version").unwrap_or("0.1.0")),
+            str_field(doc, "name").map(str::to_string),
+            str_field(doc, "description").map(str::to_string),
+            None,
+        ));
+
+        for key in UNSUPPORTED_TOP_LEVEL {
+            if doc.get(key).is_some() {
+                self.issue(key, format!("`{key}` has no 1.0 equivalent in this converter and was dropped"));
+            }
+        }
+        if let Some(secrets) = doc.get("secrets").and_then(Value::as_array) {
+            definition.use_ = Some(ComponentDefinitionCollection {
+                secrets: Some(secrets.iter().filter_map(Value::as_str).map(str::to_string).collect()),
+                ..Default::default()
+            });
+        }
+        if let Some(schema) = doc.get("dataInputSchema") {
+            definition.input = Some(InputDataModelDefinition {
+                schema: Some(self.schema(schema)),
+                from: None,
+            });
+        }
+        if let Some(Value::Object(metadata)) = doc.get("metadata") {
+            definition.metadata = Some(metadata.clone().into_iter().collect());
+        }
+
+        let states = doc
+            .get("states")
+            .and_then(Value::as_array)
+            .ok_or_else(|| "0.8 workflow requires `states`".to_string())?;
+        let start = match doc.get("start") {
+            Some(Value::String(name)) => Some(name.as_str()),
+            Some(start) => str_field(start, "stateName"),
+            None => None,
+        };
+        // The start state runs first in 1.0, so move it to the front; every task carries an explicit `then`.
+        let mut ordered: Vec<(usize, &Value)> = states.iter().enumerate().collect();
+        if let Some(pos) = ordered.iter().position(|(_, s)| str_field(s, "name") == start) {
+            let start = ordered.remove(pos);
+            ordered.insert(0, start);
+        }
+
+        let mut tasks = Vec::new();
+        for (index, state) in ordered {
+            let path = format!("states[{index}]");
+            let name = str_field(state, "name")
+                .ok_or_else(|| format!("{path} requires a `name`"))?
+                .to_string();
+            if let Some(task) = self.state(state, &path)? {
+                tasks.push((name, task));
+            }
+        }
+        definition.do_ = Map::from(tasks);
+        Ok(definition)
+    }
+
+    fn schema(&mut self, schema: &Value) -> SchemaDefinition {
+        let schema = match schema {
+            Value::Object(obj) if obj.contains_key("schema") => &obj["schema"],
+            other => other,
+        };
+        match schema {
+            Value::String(uri) => SchemaDefinition {
+                format: SchemaFormat::JSON.to_string(),
+                resource: Some(ExternalResourceDefinition {
+                    name: None,
+                    endpoint: OneOfEndpointDefinitionOrUri::Uri(uri.clone()),
+                }),
+                document: None,
+            },
+            document => SchemaDefinition {
+                format: SchemaFormat::JSON.to_string(),
+                resource: None,
+                document: Some(document.clone()),
+            },
+        }
+    }
+
+    fn state(&mut self, state: &Value, path: &str) -> Result<Option<TaskDefinition>, String> {
+        let kind = str_field(state, "type").unwrap_or_default();
+        let mut task = match kind {
+            "operation" => {
+                let parallel = str_field(state, "actionMode") == Some("parallel");
+                self.actions(state.get("actions"), parallel, &format!("{path}.actions"))?
+            }
+            "sleep" => {
+                let duration = str_field(state, "duration")
+                    .ok_or_else(|| format!("{path}: sleep state requires a `duration`"))?;
+                TaskDefinition::Wait(WaitTaskDefinition::new(
+                    OneOfDurationOrIso8601Expression::Iso8601Expression(duration.to_string()),
+                ))
+            }
+            "inject" => {
+                let data = match state.get("data") {
+                    Some(Value::Object(data)) => data.clone().into_iter().collect(),
+                    _ => HashMap::new(),
+                };
+                TaskDefinition::Set(SetTaskDefinition {
+                    set: data,
+                    common: TaskDefinitionFields::new(),
+                })
+            }
+            "switch" => self.switch(state, path),
+            "event" => self.event_state(state, path)?,
+            "foreach" => self.foreach(state, path)?,
+            "parallel" => self.parallel(state, path)?,
+            other => {
+                self.issue(path, format!("state type '{other}' cannot be converted and was dropped"));
+                return Ok(None);
+            }
+        };
+
+        for key in UNSUPPORTED_STATE {
+            if state.get(key).is_some() {
+                self.issue(format!("{path}.{key}"), format!("`{key}` has no 1.0 equivalent and was dropped"));
+            }
+        }
+
+        let common = task_fields_mut(&mut task);
+        if kind != "switch" {
+            common.then = self.flow(state, path);
+        }
+        if let Some(filter) = state.get("stateDataFilter") {
+            if let Some(input) = filter.get("input") {
+                common.input = Some(InputDataModelDefinition {
+                    schema: None,
+                    from: Some(input.clone()),
+                });
+            }
+            if let Some(output) = filter.get("output") {
+                if common.output.is_some() {
+                    task = wrap_in_do("action", task);
+                }
+                task_fields_mut(&mut task).output = Some(OutputDataModelDefinition {
+                    schema: None,
+                    as_: Some(output.clone()),
+                });
+            }
+        }
+        if let Some(Value::Object(metadata)) = state.get("metadata") {
+            task_fields_mut(&mut task).metadata = Some(metadata.clone().into_iter().collect());
+        }
+        Ok(Some(task))
+    }
+
+    /// Maps `transition`/`end` to a `then` directive.
+    fn flow(&mut self, node: &Value, path: &str) -> Option<String> {
+        match node.get("transition") {
+            Some(Value::String(next)) => return Some(next.clone()),
+            Some(transition) => {
+                if transition.get("produceEvents").is_some() || transition.get("compensate").is_some() {
+                    self.issue(
+                        format!("{path}.transition"),
+                        "transition `produceEvents`/`compensate` were dropped",
+                    );
+                }
+                if let Some(next) = str_field(transition, "nextState") {
+                    return Some(next.to_string());
+                }
+            }
+            None => {}
+        }
+        match node.get("end") {
+            Some(Value::Bool(true)) => Some("end".to_string()),
+            Some(Value::Object(end)) => {
+                if end.keys().any(|k| k != "terminate") {
+                    self.issue(format!("{path}.end"), "end `produceEvents`/`compensate`/`continueAs` were dropped");
+                }
+                Some("end".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts a list of actions into a single task: the action itself, a `do` sequence or a `fork`.
+    fn actions(&mut self, actions: Option<&Value>, parallel: bool, path: &str) -> Result<TaskDefinition, String> {
+        let actions = actions.and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        let mut tasks = Vec::new();
+        for (index, action) in actions.iter().enumerate() {
+            let action_path = format!("{path}[{index}]");
+            let name = str_field(action, "name")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("action-{index}"));
+            if let Some(task) = self.action(action, &action_path)? {
+                tasks.push((name, task));
+            }
+        }
+
+        if tasks.len() == 1 && !parallel {
+            return Ok(tasks.pop().map(|(_, task)| task).expect("one task"));
+        }
+        if parallel {
+            return Ok(TaskDefinition::Fork(ForkTaskDefinition::new(BranchingDefinition::new(
+                Map::from(tasks),
+                false,
+            ))));
+        }
+        Ok(TaskDefinition::Do(DoTaskDefinition::new(Map::from(tasks))))
+    }
+
+    fn action(&mut self, action: &Value, path: &str) -> Result<Option<TaskDefinition>, String> {
+        let mut task = if let Some(function_ref) = action.get("functionRef") {
+            match self.function_call(function_ref, path) {
+                Some(task) => task,
+                None => return Ok(None),
+            }
+        } else if let Some(subflow) = action.get("subFlowRef") {
+            let (workflow_id, version) = match subflow {
+                Value::String(id) => (id.as_str(), None),
+                other => (str_field(other, "workflowId").unwrap_or_default(), str_field(other, "version")),
+            };
+            if workflow_id.is_empty() {
+                return Err(format!("{path}.subFlowRef requires a `workflowId`"));
+            }
+            TaskDefinition::Run(RunTaskDefinition::new(ProcessTypeDefinition::using_workflow(
+                WorkflowProcessDefinition::new(
+                    DEFAULT_NAMESPACE,
+                    &kebab_case(workflow_id),
+                    &semver(version.unwrap_or("0.1.0")),
+                    None,
+                ),
+                None,
+            )))
+        } else {
+            self.issue(path, "only `functionRef` and `subFlowRef` actions are converted; action dropped");
+            return Ok(None);
+        };
+
+        for key in ["sleep", "retryRef", "nonRetryableErrors", "retryableErrors"] {
+            if action.get(key).is_some() {
+                self.issue(format!("{path}.{key}"), format!("action `{key}` was dropped"));
+            }
+        }
+        let common = task_fields_mut(&mut task);
+        common.if_ = str_field(action, "condition").map(str::to_string);
+        if let Some(filter) = action.get("actionDataFilter") {
+            if let Some(results) = filter.get("results") {
+                common.output = Some(OutputDataModelDefinition {
+                    schema: None,
+                    as_: Some(results.clone()),
+                });
+            }
+            if filter.get("toStateData").is_some() || filter.get("useResults") == Some(&Value::Bool(false)) {
+                self.issue(
+                    format!("{path}.actionDataFilter"),
+                    "`toStateData`/`useResults` merge semantics differ in 1.0; review the task output",
+                );
+            }
+        }
+        Ok(Some(task))
+    }
+
+    fn function_call(&mut self, function_ref: &Value, path: &str) -> Option<TaskDefinition> {
+        let (ref_name, arguments) = match function_ref {
+            Value::String(name) => (name.as_str(), None),
+            other => (str_field(other, "refName").unwrap_or_default(), other.get("arguments")),
+        };
+        let Some(function) = self.functions.get(ref_name).copied() else {
+            self.issue(path, format!("unknown function '{ref_name}'; action dropped"));
+            return None;
+        };
+        let operation = str_field(function, "operation").unwrap_or_default();
+        let kind = str_field(function, "type").unwrap_or("rest");
+
+        let mut with = HashMap::new();
+        let call = match kind {
+            "rest" => {
+                let (uri, operation_id) = operation.split_once('#').unwrap_or((operation, ""));
+                with.insert("document".to_string(), endpoint(uri));
+                with.insert("operationId".to_string(), Value::String(operation_id.to_string()));
+                if let Some(arguments) = arguments {
+                    with.insert("parameters".to_string(), arguments.clone());
+                }
+                "openapi"
+            }
+            "asyncapi" => {
+                let (uri, operation_id) = operation.split_once('#').unwrap_or((operation, ""));
+                with.insert("document".to_string(), endpoint(uri));
+                with.insert("operationRef".to_string(), Value::String(operation_id.to_string()));
+                if let Some(arguments) = arguments {
+                    with.insert("message".to_string(), serde_json::json!({ "payload": arguments }));
+                }
+                "asyncapi"
+            }
+            "rpc" => {
+                let mut parts = operation.split('#');
+                let (proto, service, method) = (parts.next(), parts.next(), parts.next());
+                let (Some(proto), Some(service), Some(method)) = (proto, service, method) else {
+                    self.issue(path, format!("rpc operation '{operation}' is not `file#service#method`"));
+                    return None;
+                };
+                with.insert("proto".to_string(), endpoint(proto));
+                with.insert("service".to_string(), serde_json::json!({ "name": service }));
+                with.insert("method".to_string(), Value::String(method.to_string()));
+                if let Some(arguments) = arguments {
+                    with.insert("arguments".to_string(), arguments.clone());
+                }
+                "grpc"
+            }
+            other => {
+                self.issue(path, format!("function type '{other}' of '{ref_name}' cannot be converted"));
+                return None;
+            }
+        };
+        Some(TaskDefinition::Call(CallTaskDefinition::new(call, Some(with), None)))
+    }
+
+    fn switch(&mut self, state: &Value, path: &str) -> TaskDefinition {
+        let mut cases = Vec::new();
+        if state.get("eventConditions").is_some() {
+            self.issue(format!("{path}.eventConditions"), "event-based switch conditions were dropped");
+        }
+        for (index, condition) in state
+            .get("dataConditions")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let name = str_field(condition, "name")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("case-{index}"));
+            let then = self.flow(condition, &format!("{path}.dataConditions[{index}]"));
+            cases.push((
+                name,
+                SwitchCaseDefinition {
+                    when: str_field(condition, "condition").map(str::to_string),
+                    then,
+                },
+            ));
+        }
+        if let Some(default) = state.get("defaultCondition") {
+            let then = self.flow(default, &format!("{path}.defaultCondition"));
+            cases.push(("default".to_string(), SwitchCaseDefinition { when: None, then }));
+        }
+        TaskDefinition::Switch(SwitchTaskDefinition {
+            switch: Map::from(cases),
+            common: TaskDefinitionFields::new(),
+        })
+    }
+
+    fn event_state(&mut self, state: &Value, path: &str) -> Result<TaskDefinition, String> {
+        let on_events = state.get("onEvents").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        let mut filters = Vec::new();
+        let mut actions = Vec::new();
+        for (index, on_event) in on_events.iter().enumerate() {
+            for event_ref in on_event
+                .get("eventRefs")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                match self.event_filter(event_ref) {
+                    Some(filter) => filters.push(filter),
+                    None => self.issue(
+                        format!("{path}.onEvents[{index}]"),
+                        format!("unknown event '{event_ref}' was dropped"),
+                    ),
+                }
+            }
+            if let Some(list) = on_event.get("actions").and_then(Value::as_array) {
+                actions.extend(list.iter().cloned());
+            }
+        }
+        if on_events.len() > 1 {
+            self.issue(
+                format!("{path}.onEvents"),
+                "actions of all onEvents run sequentially after the listen task",
+            );
+        }
+
+        let exclusive = state.get("exclusive").and_then(Value::as_bool).unwrap_or(true);
+        let to = if exclusive {
+            EventConsumptionStrategyDefinition {
+                any: Some(filters),
+                ..Default::default()
+            }
+        } else {
+            EventConsumptionStrategyDefinition {
+                all: Some(filters),
+                ..Default::default()
+            }
+        };
+        let listen = TaskDefinition::Listen(ListenTaskDefinition::new(ListenerDefinition::new(to)));
+        if actions.is_empty() {
+            return Ok(listen);
+        }
+        let actions = Value::Array(actions);
+        let mode = str_field(state, "actionMode") == Some("parallel");
+        let handle = self.actions(Some(&actions), mode, &format!("{path}.onEvents.actions"))?;
+        Ok(TaskDefinition::Do(DoTaskDefinition::new(Map::from(vec![
+            ("listen".to_string(), listen),
+            ("handle".to_string(), handle),
+        ]))))
+    }
+
+    fn event_filter(&mut self, name: &str) -> Option<EventFilterDefinition> {
+        let event = self.events.get(name).copied()?;
+        let mut with = HashMap::new();
+        for key in ["type", "source"] {
+            if let Some(value) = event.get(key) {
+                with.insert(key.to_string(), value.clone());
+            }
+        }
+        let correlate = event.get("correlation").and_then(Value::as_array).map(|keys| {
+            keys.iter()
+                .filter_map(|key| {
+                    let attribute = str_field(key, "contextAttributeName")?;
+                    Some((
+                        attribute.to_string(),
+                        serverless_workflow_core::models::event::CorrelationKeyDefinition::new(
+                            &format!(".{attribute}"),
+                            str_field(key, "contextAttributeValue").map(str::to_string),
+                        ),
+                    ))
+                })
+                .collect()
+        });
+        Some(EventFilterDefinition {
+            with: Some(with),
+            correlate,
+        })
+    }
+
+    fn foreach(&mut self, state: &Value, path: &str) -> Result<TaskDefinition, String> {
+        let collection = str_field(state, "inputCollection")
+            .ok_or_else(|| format!("{path}: foreach state requires an `inputCollection`"))?;
+        let each = str_field(state, "iterationParam").unwrap_or("item");
+        if str_field(state, "mode") != Some("sequential") {
+            self.issue(
+                format!("{path}.mode"),
+                "0.8 foreach runs iterations in parallel by default; 1.0 `for` runs them sequentially",
+            );
+        }
+        if state.get("outputCollection").is_some() {
+            self.issue(format!("{path}.outputCollection"), "`outputCollection` was dropped");
+        }
+        let body = self.actions(state.get("actions"), false, &format!("{path}.actions"))?;
+        let do_ = match body {
+            TaskDefinition::Do(def) => def.do_,
+            other => Map::from(vec![("action".to_string(), other)]),
+        };
+        Ok(TaskDefinition::For(ForTaskDefinition::new(
+            ForLoopDefinition::new(each, collection, None, None),
+            do_,
+            None,
+        )))
+    }
+
+    fn parallel(&mut self, state: &Value, path: &str) -> Result<TaskDefinition, String> {
+        let mut branches = Vec::new();
+        for (index, branch) in state
+            .get("branches")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let name = str_field(branch, "name")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("branch-{index}"));
+            let task = self.actions(branch.get("actions"), false, &format!("{path}.branches[{index}].actions"))?;
+            branches.push((name, task));
+        }
+        let compete = str_field(state, "completionType") == Some("atLeast");
+        if compete && state.get("numCompleted").and_then(Value::as_u64).unwrap_or(1) != 1 {
+            self.issue(
+                format!("{path}.numCompleted"),
+                "1.0 `compete` finishes on the first branch; `numCompleted` > 1 is not supported",
+            );
+        }
+        Ok(TaskDefinition::Fork(ForkTaskDefinition::new(BranchingDefinition::new(
+            Map::from(branches),
+            compete,
+        ))))
+    }
+}
+
+fn wrap_in_do(name: &str, task: TaskDefinition) -> TaskDefinition {
+    TaskDefinition::Do(DoTaskDefinition::new(Map::from(vec![(name.to_string(), task)])))
+}
+
+fn endpoint(uri: &str) -> Value {
+    serde_json::to_value(ExternalResourceDefinition {
+        name: None,
+        endpoint: OneOfEndpointDefinitionOrUri::Endpoint(EndpointDefinition {
+            uri: uri.to_string(),
+            authentication: None,
+        }),
+    })
+    .unwrap_or(Value::Null)
+}
+
+fn str_field<'v>(value: &'v Value, key: &str) -> Option<&'v str> {
+    value.get(key).and_then(Value::as_str)
+}
+
+/// Pads `1` and `1.0` style versions to semantic versions.
+fn semver(version: &str) -> String {
+    match version.split('.').count() {
+        1 => format!("{version}.0.0"),
+        2 => format!("{version}.0"),
+        _ => version.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task<'d>(definition: &'d WorkflowDefinition, name: &str) -> &'d TaskDefinition {
+        definition
+            .do_
+            .entries
+            .iter()
+            .find_map(|entry| entry.get(name))
+            .unwrap_or_else(|| panic!("missing task '{name}'"))
+    }
+
+    #[test]
+    fn convert_operation_and_switch_states() {
+        let conversion = convert_str(
+            r#"
+id: greetingWorkflow
+version: '1.0'
+specVersion: '0.8'
+name: Greeting Workflow
+start: CheckName
+functions:
+  - name: greetingFunction
+    operation: https://example.com/openapi.json#greet
+states:
+  - name: Greet
+    type: operation
+    actions:
+      - functionRef:
+          refName: greetingFunction
+          arguments:
+            name: ${ .person.name }
+        actionDataFilter:
+          results: ${ .greeting }
+    end: true
+  - name: CheckName
+    type: switch
+    dataConditions:
+      - name: hasName
+        condition: ${ .person.name != null }
+        transition: Greet
+    defaultCondition:
+      end: true
+"#,
+        )
+        .unwrap();
+
+        assert!(conversion.is_lossless(), "{:?}", conversion.issues);
+        let definition = &conversion.definition;
+        assert_eq!(definition.document.name, "greeting-workflow");
+        assert_eq!(definition.document.version, "1.0.0");
+        assert_eq!(definition.document.title.as_deref(), Some("Greeting Workflow"));
+        assert!(definition.do_.entries[0].contains_key("CheckName"));
+
+        let TaskDefinition::Call(call) = task(definition, "Greet") else {
+            panic!("expected a call task");
+        };
+        assert_eq!(call.call, "openapi");
+        assert_eq!(call.common.then.as_deref(), Some("end"));
+        let with = call.with.as_ref().unwrap();
+        assert_eq!(with["operationId"], "greet");
+        assert_eq!(with["parameters"]["name"], "${ .person.name }");
+
+        let TaskDefinition::Switch(switch) = task(definition, "CheckName") else {
+            panic!("expected a switch task");
+        };
+        let has_name = switch.switch.entries[0].get("hasName").unwrap();
+        assert_eq!(has_name.then.as_deref(), Some("Greet"));
+        let default = switch.switch.entries[1].get("default").unwrap();
+        assert_eq!(default.when, None);
+        assert_eq!(default.then.as_deref(), Some("end"));
+    }
+
+    #[test]
+    fn convert_reports_untranslatable_constructs() {
+        let conversion = convert_str(
+            r#"
+id: orders
+version: '2.1.0'
+specVersion: '0.8'
+start: Wait
+retries:
+  - name: default
+    maxAttempts: 3
+states:
+  - name: Wait
+    type: sleep
+    duration: PT5S
+    transition: Notify
+  - name: Notify
+    type: callback
+    end: true
+  - name: Loop
+    type: foreach
+    inputCollection: ${ .orders }
+    actions:
+      - subFlowRef: processOrder
+    onErrors:
+      - errorRef: any
+        end: true
+    end: true
+"#,
+        )
+        .unwrap();
+
+        let paths: Vec<_> = conversion.issues.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, ["retries", "states[1]", "states[2].mode", "states[2].onErrors"]);
+
+        let TaskDefinition::Wait(wait) = task(&conversion.definition, "Wait") else {
+            panic!("expected a wait task");
+        };
+        assert_eq!(wait.common.then.as_deref(), Some("Notify"));
+        let TaskDefinition::For(for_) = task(&conversion.definition, "Loop") else {
+            panic!("expected a for task");
+        };
+        assert_eq!(for_.for_.each, "item");
+        assert!(matches!(for_.do_.entries[0].get("action"), Some(TaskDefinition::Run(_))));
+    }
+
+    #[test]
+    fn kebab_case_names() {
+        assert_eq!(kebab_case("greetingWorkflow"), "greeting-workflow");
+        assert_eq!(kebab_case("Order Processing_v2"), "order-processing-v2");
+    }
+}
@@ -0,0 +1,61 @@
+//! Engine extensions to the DSL. A task declares them under `metadata.tideloom`, so that they never collide
+//! with metadata kept for other tools:
+//!
+//! | Key            | Declared on                            | See                                 |
+//! |----------------|----------------------------------------|-------------------------------------|
+//! | `onError`      | `fork` tasks, or as `fork.onError`     | [`ForkNode`](crate::nodes::forking) |
+//! | `outputBudget` | `fork` tasks                           | [`ForkNode`](crate::nodes::forking) |
+//! | `memoize`      | `call` tasks                           | [`MemoizedNode`](crate::nodes::memoized) |
+//! | `fanOut`       | `run: workflow` tasks                  | [`FanOutNode`](crate::nodes::fanout) |
+//! | `onChildFault` | `run: workflow` tasks                  | [`RunNode`](crate::nodes::run)      |
+//! | `detached`     | `run: workflow` tasks                  | [`RunNode`](crate::nodes::run)      |
+//! | `defaults`     | functions of `use.functions`           | [`FunctionNode`](crate::nodes::function) |
+//!
+//! [`validate`](crate::validation::validate) reports unknown keys and keys declared where they do not apply.
+//! Any other `metadata` is left alone.
+
+use serde_json::Value;
+use serverless_workflow_core::models::task::{TaskDefinition, TaskDefinitionFields};
+
+use crate::nodes::doing::task_fields;
+
+/// The `metadata` key holding a task's engine extensions.
+pub const NAMESPACE: &str = "tideloom";
+
+pub const ON_ERROR: &str = "onError";
+pub const OUTPUT_BUDGET: &str = "outputBudget";
+pub const MEMOIZE: &str = "memoize";
+pub const FAN_OUT: &str = "fanOut";
+pub const ON_CHILD_FAULT: &str = "onChildFault";
+pub const DETACHED: &str = "detached";
+pub const DEFAULTS: &str = "defaults";
+
+/// The engine extension `key` declared by a task, if any.
+pub fn extension<'a>(fields: &'a TaskDefinitionFields, key: &str) -> Option<&'a Value> {
+    fields.metadata.as_ref()?.get(NAMESPACE)?.get(key)
+}
+
+/// Problems with the extensions declared by `task`, a function of `use.functions` if `function`, as pairs of
+/// a JSON pointer relative to `metadata/tideloom` and a message.
+pub(crate) fn check(task: &TaskDefinition, function: bool) -> Vec<(String, String)> {
+    let Some(declared) = task_fields(task).metadata.as_ref().and_then(|m| m.get(NAMESPACE)) else {
+        return Vec::new();
+    };
+    let Value::Object(declared) = declared else {
+        return vec![(String::new(), format!("`metadata.{NAMESPACE}` must be an object"))];
+    };
+    let run_workflow = matches!(task, TaskDefinition::Run(run) if run.run.workflow.is_some());
+    declared
+        .keys()
+        .filter_map(|key| {
+            let (applies, place) = match key.as_str() {
+                ON_ERROR | OUTPUT_BUDGET => (matches!(task, TaskDefinition::Fork(_)), "`fork` tasks"),
+                MEMOIZE => (matches!(task, TaskDefinition::Call(_)), "`call` tasks"),
+                FAN_OUT | ON_CHILD_FAULT | DETACHED => (run_workflow, "`run: workflow` tasks"),
+                DEFAULTS => (function, "functions of `use.functions`"),
+                _ => return Some((format!("/{key}"), format!("unknown engine extension `{key}`"))),
+            };
+            (!applies).then(|| (format!("/{key}"), format!("`{key}` only applies to {place}")))
+        })
+        .collect()
+}
@@ -1,27 +1,349 @@
+pub mod builder;
+pub mod conformance;
+pub mod convert;
+pub mod extensions;
+pub mod messaging;
 pub mod runtime;
+pub mod scheduler;
+#[cfg(feature = "otel")]
+pub mod telemetry;
 pub mod nodes;
+pub mod overrides;
+pub mod validation;
 
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+use serverless_workflow_core::models::map::Map;
+use serverless_workflow_core::models::task::TaskDefinition;
 use serverless_workflow_core::models::workflow::WorkflowDefinition;
 
+use crate::extensions::{NAMESPACE, ON_ERROR};
+use crate::nodes::workflow::WorkflowNode;
+use crate::overrides::Overrides;
+use crate::runtime::registry::TaskRegistry;
+use crate::runtime::schema::{Schema, SchemaError};
+use crate::runtime::{StepResult, Task, WorkflowContext};
+use crate::validation::Diagnostic;
+
 /// Wrapper around `WorkflowDefinition` with convenience constructors.
+#[derive(Debug, Clone)]
 pub struct Workflow {
     workflow_definition: WorkflowDefinition,
 }
 
+/// Why a workflow document could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// One-based line and column of the error, when the parser reported one.
+    pub location: Option<(usize, usize)>,
+    /// Path of the offending value in the document, such as `do[0].getPet.call`, when known.
+    pub path: Option<String>,
+    pub message: String,
+}
+
+impl ParseError {
+    fn from_yaml(error: serde_yaml::Error, path: Option<String>) -> Self {
+        Self {
+            location: error.location().map(|l| (l.line(), l.column())),
+            path,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((line, column)) = self.location {
+            write!(f, "line {line} column {column}: ")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "{path}: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Human-friendly description of a workflow definition for catalogs and dashboards: its `document` title, summary
+/// and tags, and its `metadata`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkflowSummary {
+    /// Registry key, see [`workflow_key`](runtime::workflow_key).
+    pub key: String,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub tags: BTreeMap<String, String>,
+    pub metadata: serde_json::Map<String, Value>,
+}
+
+impl WorkflowSummary {
+    pub fn from_definition(definition: &WorkflowDefinition) -> Self {
+        let document = &definition.document;
+        Self {
+            key: runtime::workflow_key(&document.namespace, &document.name, &document.version),
+            title: document.title.clone(),
+            summary: document.summary.clone(),
+            tags: document.tags.iter().flatten().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            metadata: definition.metadata.iter().flatten().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        }
+    }
+}
+
+/// Why one document of a multi-document YAML stream could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentError {
+    /// Zero-based position of the document in the stream.
+    pub index: usize,
+    pub error: ParseError,
+}
+
+impl std::fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "document {}: {}", self.index, self.error)
+    }
+}
+
 impl Workflow {
     /// Builds a workflow from a YAML string. Panics if input is invalid.
+    #[deprecated(note = "use `Workflow::try_from_yaml`, which returns an error instead of panicking")]
     pub fn from_yaml(yaml: &str) -> Self {
-        let workflow_definition: WorkflowDefinition =
-            serde_yaml::from_str(yaml).expect("invalid workflow yaml");
-        Self {
-            workflow_definition,
+        Self::try_from_yaml(yaml).expect("invalid workflow yaml")
+    }
+
+    /// Loads a workflow from a YAML document, resolving anchors and `<<` merge keys.
+    pub fn try_from_yaml(yaml: &str) -> Result<Self, ParseError> {
+        let value: serde_yaml::Value = serde_yaml::from_str(yaml).map_err(|e| ParseError::from_yaml(e, None))?;
+        Ok(Self {
+            workflow_definition: definition_from_yaml(value)?,
+        })
+    }
+
+    /// Loads a workflow from a JSON document.
+    pub fn try_from_json(json: &str) -> Result<Self, ParseError> {
+        let syntax_error = |e: serde_json::Error| ParseError {
+            location: Some((e.line(), e.column())),
+            path: None,
+            message: e.to_string(),
+        };
+        let value: serde_json::Value = serde_json::from_str(json).map_err(syntax_error)?;
+        let value = serde_yaml::to_value(value).map_err(|e| ParseError::from_yaml(e, None))?;
+        Ok(Self {
+            workflow_definition: definition_from_yaml(value)?,
+        })
+    }
+
+    /// Wraps a definition built or loaded by other means, such as [`builder::WorkflowBuilder`].
+    pub fn from_definition(workflow_definition: WorkflowDefinition) -> Self {
+        Self { workflow_definition }
+    }
+
+    /// Loads every workflow of a `---` separated YAML stream, resolving anchors and `<<` merge keys.
+    ///
+    /// Empty documents are skipped. If any document fails to load, every failure is returned instead.
+    pub fn from_yaml_documents(yaml: &str) -> Result<Vec<Self>, Vec<DocumentError>> {
+        let mut workflows = Vec::new();
+        let mut errors = Vec::new();
+        for (index, document) in serde_yaml::Deserializer::from_str(yaml).enumerate() {
+            let value = match serde_yaml::Value::deserialize(document) {
+                Ok(serde_yaml::Value::Null) => continue,
+                Ok(value) => value,
+                Err(e) => {
+                    errors.push(DocumentError {
+                        index,
+                        error: ParseError::from_yaml(e, None),
+                    });
+                    // The parser cannot resynchronise after a syntax error, so later documents are unreachable.
+                    break;
+                }
+            };
+            match definition_from_yaml(value) {
+                Ok(workflow_definition) => workflows.push(Self { workflow_definition }),
+                Err(error) => errors.push(DocumentError { index, error }),
+            }
         }
+        if errors.is_empty() { Ok(workflows) } else { Err(errors) }
     }
 
     /// Returns the underlying workflow definition.
     pub fn definition(&self) -> &WorkflowDefinition {
         &self.workflow_definition
     }
+
+    /// Describes the workflow for catalogs and dashboards.
+    pub fn summary(&self) -> WorkflowSummary {
+        WorkflowSummary::from_definition(&self.workflow_definition)
+    }
+
+    /// Runs an instance of the workflow to completion and returns its output.
+    ///
+    /// The start payload goes through the document-level `input` block first; an invalid payload fails the
+    /// run before any task executes. The result is the last task's output shaped by the `output` block.
+    pub async fn run(&self, ctx: &WorkflowContext, input: Value) -> StepResult<Value> {
+        WorkflowNode::try_from_definition(&self.workflow_definition)?
+            .execute(ctx, input)
+            .await
+    }
+
+    /// Like [`run`](Self::run), with `overrides` applied to this run only, e.g. to point a task at a staging
+    /// endpoint or mock it out.
+    pub async fn run_with_overrides(
+        &self,
+        ctx: &WorkflowContext,
+        input: Value,
+        overrides: &Overrides,
+    ) -> StepResult<Value> {
+        WorkflowNode::build(&overrides.apply(&self.workflow_definition)?, overrides.mocks())?
+            .execute(ctx, input)
+            .await
+    }
+
+    /// Runs static checks on the definition; an empty list means no problem was found.
+    ///
+    /// See [`validation::validate`] for what is checked.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validation::validate(&self.workflow_definition)
+    }
+
+    /// Runs the static checks of [`validate`](Self::validate), accepting the custom `call` targets of `tasks`.
+    ///
+    /// See [`validation::validate_with`].
+    pub fn validate_with(&self, tasks: &TaskRegistry) -> Vec<Diagnostic> {
+        validation::validate_with(&self.workflow_definition, tasks)
+    }
+
+    /// Checks that `ctx` can execute every task of the workflow, e.g. at deploy time; an empty list means every
+    /// task is covered.
+    ///
+    /// See [`validation::check_support`] for what is checked.
+    pub fn check_support(&self, ctx: &WorkflowContext) -> Vec<Diagnostic> {
+        validation::check_support(&self.workflow_definition, ctx)
+    }
+
+    /// Checks `input` against the workflow's `input.schema`, so callers can reject it before starting an instance.
+    pub fn validate_io(&self, input: &Value) -> Result<(), Vec<SchemaError>> {
+        let Some(definition) = self.workflow_definition.input.as_ref().and_then(|i| i.schema.as_ref()) else {
+            return Ok(());
+        };
+        let schema = Schema::compile(definition).map_err(|message| {
+            vec![SchemaError {
+                instance_path: String::new(),
+                message: format!("workflow input {message}"),
+            }]
+        })?;
+        schema.validate(input)
+    }
+}
+
+fn definition_from_yaml(mut value: serde_yaml::Value) -> Result<WorkflowDefinition, ParseError> {
+    value.apply_merge().map_err(|e| ParseError::from_yaml(e, None))?;
+    normalize_tasks(&mut value);
+    let mut definition: WorkflowDefinition = serde_path_to_error::deserialize(value.clone()).map_err(|e| {
+        let path = e.path().to_string();
+        ParseError::from_yaml(e.into_inner(), Some(path))
+    })?;
+    restore_for_tasks(&mut definition.do_, value.get("do")).map_err(|e| ParseError::from_yaml(e, None))?;
+    Ok(definition)
+}
+
+/// `for` tasks also carry a `do` block, so the model's untagged task enum reads them as `do` tasks.
+/// Re-reads those tasks from the source YAML as `for` tasks.
+fn restore_for_tasks(
+    tasks: &mut Map<String, TaskDefinition>,
+    yaml: Option<&serde_yaml::Value>,
+) -> Result<(), serde_yaml::Error> {
+    let Some(serde_yaml::Value::Sequence(entries)) = yaml else {
+        return Ok(());
+    };
+    for (entry, yaml_entry) in tasks.entries.iter_mut().zip(entries) {
+        for (name, task) in entry.iter_mut() {
+            let Some(yaml_task) = yaml_entry.get(name.as_str()) else {
+                continue;
+            };
+            if matches!(task, TaskDefinition::Do(_)) && yaml_task.get("for").is_some() {
+                *task = TaskDefinition::For(serde_yaml::from_value(yaml_task.clone())?);
+            }
+            match task {
+                TaskDefinition::Do(def) => restore_for_tasks(&mut def.do_, yaml_task.get("do"))?,
+                TaskDefinition::For(def) => restore_for_tasks(&mut def.do_, yaml_task.get("do"))?,
+                TaskDefinition::Fork(def) => {
+                    let branches = yaml_task.get("fork").and_then(|f| f.get("branches"));
+                    restore_for_tasks(&mut def.fork.branches, branches)?;
+                }
+                TaskDefinition::Try(def) => {
+                    restore_for_tasks(&mut def.try_, yaml_task.get("try"))?;
+                    if let Some(handler) = &mut def.catch.do_ {
+                        restore_for_tasks(handler, yaml_task.get("catch").and_then(|c| c.get("do")))?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Maps DSL shapes the model cannot read onto the ones it expects, in the tasks of `do` and of `use.functions`:
+/// - `for.each` is read from an `emit` key and required, while the DSL makes it optional;
+/// - `fork.compete` is required, while the DSL defaults it to `false`;
+/// - `fork.onError` has no field in the model, so it is kept in the task's `metadata.tideloom` instead.
+///
+/// Only task positions are visited, so data such as `set` values or call arguments is never rewritten.
+fn normalize_tasks(value: &mut serde_yaml::Value) {
+    normalize_task_list(value.get_mut("do"));
+    if let Some(serde_yaml::Value::Mapping(functions)) = value.get_mut("use").and_then(|u| u.get_mut("functions")) {
+        functions.values_mut().for_each(normalize_task);
+    }
+}
+
+/// Normalizes the tasks of a task list, a sequence of single-entry mappings from task name to task.
+fn normalize_task_list(tasks: Option<&mut serde_yaml::Value>) {
+    let Some(serde_yaml::Value::Sequence(entries)) = tasks else {
+        return;
+    };
+    for entry in entries {
+        if let serde_yaml::Value::Mapping(entry) = entry {
+            entry.values_mut().for_each(normalize_task);
+        }
+    }
+}
+
+fn normalize_task(task: &mut serde_yaml::Value) {
+    let serde_yaml::Value::Mapping(map) = task else {
+        return;
+    };
+    if let Some(serde_yaml::Value::Mapping(for_)) = map.get_mut("for")
+        && for_.contains_key("in")
+        && !for_.contains_key("emit")
+    {
+        let each = for_.remove("each").unwrap_or_else(|| serde_yaml::Value::from(""));
+        for_.insert(serde_yaml::Value::from("emit"), each);
+    }
+    if let Some(serde_yaml::Value::Mapping(fork)) = map.get_mut("fork")
+        && fork.contains_key("branches")
+    {
+        if !fork.contains_key("compete") {
+            fork.insert(serde_yaml::Value::from("compete"), serde_yaml::Value::from(false));
+        }
+        if let Some(on_error) = fork.remove("onError") {
+            let metadata = map
+                .entry(serde_yaml::Value::from("metadata"))
+                .or_insert_with(|| serde_yaml::Value::Mapping(Default::default()));
+            if let serde_yaml::Value::Mapping(metadata) = metadata
+                && let serde_yaml::Value::Mapping(extensions) = metadata
+                    .entry(serde_yaml::Value::from(NAMESPACE))
+                    .or_insert_with(|| serde_yaml::Value::Mapping(Default::default()))
+            {
+                extensions.insert(serde_yaml::Value::from(ON_ERROR), on_error);
+            }
+        }
+    }
+    normalize_task_list(map.get_mut("do"));
+    normalize_task_list(map.get_mut("try"));
+    normalize_task_list(map.get_mut("catch").and_then(|catch| catch.get_mut("do")));
+    normalize_task_list(map.get_mut("fork").and_then(|fork| fork.get_mut("branches")));
 }
 
 #[cfg(test)]
@@ -29,6 +351,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(deprecated)]
     fn test_from_yaml() {
         let yaml = "
 document:
@@ -49,4 +372,210 @@ do:
             "call-http"
         );
     }
+
+    #[test]
+    fn try_from_yaml_loads_workflows() {
+        let yaml = "
+document:
+  dsl: '1.0.0'
+  namespace: default
+  name: call-http
+  version: '1.0.0'
+do:
+- getPet:
+    call: http
+    with:
+      method: get
+      endpoint: https://petstore.swagger.io/v2/pet/{petId}
+";
+        let workflow = Workflow::try_from_yaml(yaml).unwrap();
+        assert_eq!(workflow.definition().document.name, "call-http");
+        assert!(matches!(workflow.definition().do_.entries[0].get("getPet"), Some(TaskDefinition::Call(_))));
+    }
+
+    #[test]
+    fn only_tasks_are_normalized() {
+        let workflow = Workflow::try_from_yaml(
+            "
+document: { dsl: '1.0.0', namespace: default, name: shapes, version: '1.0.0' }
+do:
+- loop:
+    for: { in: '${ .items }' }
+    do:
+    - race:
+        fork:
+          branches:
+          - payload:
+              set:
+                for: { in: cart }
+                fork: { branches: [], onError: keep }
+          onError: continue
+",
+        )
+        .unwrap();
+        let Some(TaskDefinition::For(for_)) = workflow.definition().do_.entries[0].get("loop") else {
+            panic!("expected a for task");
+        };
+        let Some(TaskDefinition::Fork(fork)) = for_.do_.entries[0].get("race") else {
+            panic!("expected a fork task");
+        };
+        assert!(!fork.fork.compete);
+        assert!(extensions::extension(&fork.common, extensions::ON_ERROR).is_some());
+        let Some(TaskDefinition::Set(set)) = fork.fork.branches.entries[0].get("payload") else {
+            panic!("expected a set task");
+        };
+        assert_eq!(set.set["for"], serde_json::json!({ "in": "cart" }));
+        assert_eq!(set.set["fork"], serde_json::json!({ "branches": [], "onError": "keep" }));
+    }
+
+    #[test]
+    fn registered_workflows_are_described() {
+        let workflow = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: default
+  name: orders
+  version: '1.0.0'
+  title: Orders
+  tags:
+    team: checkout
+metadata:
+  owner: ada
+do: []
+",
+        )
+        .unwrap();
+        let ctx = WorkflowContext::default().with_workflow(workflow.definition().clone());
+
+        let summaries = ctx.registered_workflows();
+
+        assert_eq!(summaries, [workflow.summary()]);
+        assert_eq!(summaries[0].key, "default/orders:1.0.0");
+        assert_eq!(summaries[0].title.as_deref(), Some("Orders"));
+        assert_eq!(summaries[0].summary, None);
+        assert_eq!(summaries[0].tags["team"], "checkout");
+        assert_eq!(summaries[0].metadata["owner"], "ada");
+    }
+
+    #[test]
+    fn try_from_yaml_reports_location_and_path() {
+        let err = Workflow::try_from_yaml("document: [unclosed").unwrap_err();
+        assert!(err.location.is_some(), "{err}");
+
+        let err = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: default
+  name: broken
+  version: 1
+do: []
+",
+        )
+        .unwrap_err();
+        assert_eq!(err.path.as_deref(), Some("document.version"));
+        assert!(err.to_string().starts_with("document.version: "), "{err}");
+    }
+
+    #[test]
+    fn try_from_json_loads_workflows() {
+        let json = r#"{
+  "document": { "dsl": "1.0.0", "namespace": "default", "name": "from-json", "version": "1.0.0" },
+  "do": []
+}"#;
+        let workflow = Workflow::try_from_json(json).unwrap();
+        assert_eq!(workflow.definition().document.name, "from-json");
+
+        let err = Workflow::try_from_json("{\n  \"document\": }").unwrap_err();
+        assert_eq!(err.location, Some((2, 15)));
+    }
+
+    #[test]
+    fn validate_io_checks_workflow_input_schema() {
+        let workflow = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: default
+  name: orders
+  version: '1.0.0'
+input:
+  schema:
+    format: json
+    document:
+      type: object
+      properties:
+        orderId:
+          type: string
+      required: [orderId]
+do:
+- accept:
+    set:
+      accepted: true
+",
+        ).unwrap();
+        assert!(workflow.validate_io(&serde_json::json!({ "orderId": "o-1" })).is_ok());
+        let errors = workflow.validate_io(&serde_json::json!({ "orderId": 1 })).unwrap_err();
+        assert_eq!(errors[0].instance_path, "/orderId");
+    }
+
+    #[test]
+    fn from_yaml_documents_resolves_merge_keys() {
+        let yaml = "
+document:
+  dsl: '1.0.0'
+  namespace: default
+  name: first
+  version: '1.0.0'
+do:
+- hello:
+    set:
+      greeting: hello
+---
+document:
+  dsl: '1.0.0'
+  namespace: default
+  name: second
+  version: '1.0.0'
+do:
+- hello:
+    set: &defaults
+      greeting: hi
+      lang: en
+- bonjour:
+    set:
+      <<: *defaults
+      greeting: bonjour
+---
+";
+        let workflows = Workflow::from_yaml_documents(yaml).unwrap();
+        let names: Vec<_> = workflows.iter().map(|w| w.definition().document.name.as_str()).collect();
+        assert_eq!(names, ["first", "second"]);
+        let Some(TaskDefinition::Set(set)) = workflows[1].definition().do_.entries[1].get("bonjour") else {
+            panic!("expected a set task");
+        };
+        assert_eq!(set.set["greeting"], "bonjour");
+        assert_eq!(set.set["lang"], "en");
+    }
+
+    #[test]
+    fn from_yaml_documents_reports_each_invalid_document() {
+        let yaml = "
+document:
+  dsl: '1.0.0'
+  namespace: default
+  name: ok
+  version: '1.0.0'
+do: []
+---
+document:
+  name: missing-fields
+---
+do: 5
+";
+        let errors = Workflow::from_yaml_documents(yaml).unwrap_err();
+        let indexes: Vec<_> = errors.iter().map(|e| e.index).collect();
+        assert_eq!(indexes, [1, 2]);
+    }
 }
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use serde_json::{Map, Value};
+use tokio::sync::broadcast;
+
+use crate::runtime::StepResult;
+
+/// A message sent or received by `call: asyncapi` tasks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message {
+    pub payload: Value,
+    pub headers: Map<String, Value>,
+}
+
+pub type MessageStream = BoxStream<'static, Message>;
+
+/// Protocol backend of `call: asyncapi` tasks, registered on the context per AsyncAPI server protocol.
+///
+/// `server` is the URL of the AsyncAPI server the operation is bound to and `channel` the resolved channel
+/// address, e.g. an MQTT topic or a Kafka topic name.
+#[async_trait::async_trait]
+pub trait MessageBroker: Send + Sync {
+    async fn publish(&self, server: &str, channel: &str, message: Message) -> StepResult<()>;
+
+    /// Opens a subscription that only sees messages published after it was created.
+    async fn subscribe(&self, server: &str, channel: &str) -> StepResult<MessageStream>;
+}
+
+/// In-process broker with one broadcast channel per channel address; the server is ignored.
+#[derive(Debug, Clone)]
+pub struct InMemoryBroker {
+    capacity: usize,
+    channels: Arc<Mutex<HashMap<String, broadcast::Sender<Message>>>>,
+}
+
+impl InMemoryBroker {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            channels: Default::default(),
+        }
+    }
+
+    fn sender(&self, channel: &str) -> broadcast::Sender<Message> {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels
+            .entry(channel.to_string())
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .clone()
+    }
+}
+
+impl Default for InMemoryBroker {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageBroker for InMemoryBroker {
+    async fn publish(&self, _server: &str, channel: &str, message: Message) -> StepResult<()> {
+        // Nobody subscribed to the channel is fine.
+        let _ = self.sender(channel).send(message);
+        Ok(())
+    }
+
+    async fn subscribe(&self, _server: &str, channel: &str) -> StepResult<MessageStream> {
+        let rx = self.sender(channel).subscribe();
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(message) => return Some((message, rx)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(stream.boxed())
+    }
+}
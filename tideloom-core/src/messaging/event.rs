@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const CLOUD_EVENTS_SPEC_VERSION: &str = "1.0";
+
+/// A CloudEvents 1.0 envelope in its structured JSON form.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloudEvent {
+    pub specversion: String,
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datacontenttype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataschema: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    /// Extension attributes, serialized alongside the context attributes.
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+impl CloudEvent {
+    pub fn new(id: impl Into<String>, source: impl Into<String>, type_: impl Into<String>) -> Self {
+        Self {
+            specversion: CLOUD_EVENTS_SPEC_VERSION.to_string(),
+            id: id.into(),
+            source: source.into(),
+            type_: type_.into(),
+            subject: None,
+            time: None,
+            datacontenttype: None,
+            dataschema: None,
+            data: None,
+            extensions: Map::new(),
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.datacontenttype.get_or_insert_with(|| "application/json".to_string());
+        self.data = Some(data);
+        self
+    }
+
+    /// Returns the event as a JSON object, as exposed to workflow data.
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+
+use crate::messaging::{CloudEvent, EventSink};
+use crate::runtime::observer::{WorkflowEvent, WorkflowObserver};
+
+pub const WORKFLOW_STARTED: &str = "io.serverlessworkflow.workflow.started.v1";
+pub const WORKFLOW_COMPLETED: &str = "io.serverlessworkflow.workflow.completed.v1";
+pub const WORKFLOW_FAULTED: &str = "io.serverlessworkflow.workflow.faulted.v1";
+pub const TASK_STARTED: &str = "io.serverlessworkflow.task.started.v1";
+pub const TASK_COMPLETED: &str = "io.serverlessworkflow.task.completed.v1";
+pub const TASK_FAULTED: &str = "io.serverlessworkflow.task.faulted.v1";
+
+/// Publishes the lifecycle events defined by the Serverless Workflow specification as CloudEvents, in the
+/// order they happen; register it with
+/// [`WorkflowContext::with_observer`](crate::runtime::WorkflowContext::with_observer).
+///
+/// Events carry the workflow key as source and the instance id as subject; workflow started events also carry the
+/// definition's title, summary, tags and metadata. Publishing happens on a
+/// background task, so a slow or failing sink never holds up or fails a workflow.
+pub struct LifecycleEmitter {
+    events: mpsc::UnboundedSender<CloudEvent>,
+    tasks: bool,
+}
+
+impl LifecycleEmitter {
+    /// Publishes to `sink`. Must be called within a Tokio runtime.
+    pub fn new(sink: Arc<dyn EventSink>) -> Self {
+        let (events, mut queue) = mpsc::unbounded_channel::<CloudEvent>();
+        tokio::spawn(async move {
+            while let Some(event) = queue.recv().await {
+                let _ = sink.publish(event).await;
+            }
+        });
+        Self { events, tasks: true }
+    }
+
+    /// Only publishes workflow-level events, leaving out the started/completed/faulted events of every task.
+    pub fn without_task_events(mut self) -> Self {
+        self.tasks = false;
+        self
+    }
+}
+
+impl std::fmt::Debug for LifecycleEmitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LifecycleEmitter").field("tasks", &self.tasks).finish_non_exhaustive()
+    }
+}
+
+impl WorkflowObserver for LifecycleEmitter {
+    fn on_event(&self, event: &WorkflowEvent) {
+        let instance = event.instance();
+        let (type_, mut data) = match event {
+            WorkflowEvent::WorkflowStarted { definition, at, .. } => (
+                WORKFLOW_STARTED,
+                json!({
+                    "startedAt": timestamp(at),
+                    "title": definition.title,
+                    "summary": definition.summary,
+                    "tags": definition.tags,
+                    "metadata": definition.metadata,
+                }),
+            ),
+            WorkflowEvent::WorkflowCompleted { output, at, .. } => (
+                WORKFLOW_COMPLETED,
+                json!({ "completedAt": timestamp(at), "output": output }),
+            ),
+            WorkflowEvent::WorkflowFaulted { error, at, .. } => {
+                (WORKFLOW_FAULTED, json!({ "faultedAt": timestamp(at), "error": error }))
+            }
+            _ if !self.tasks => return,
+            WorkflowEvent::TaskStarted { position, at, .. } => {
+                (TASK_STARTED, json!({ "task": position, "startedAt": timestamp(at) }))
+            }
+            WorkflowEvent::TaskCompleted { position, output, at, .. } => (
+                TASK_COMPLETED,
+                json!({ "task": position, "completedAt": timestamp(at), "output": output }),
+            ),
+            WorkflowEvent::TaskFaulted { position, error, at, .. } => (
+                TASK_FAULTED,
+                json!({ "task": position, "faultedAt": timestamp(at), "error": error }),
+            ),
+        };
+        data["workflow"] = json!(instance.id);
+        data["definition"] = json!(instance.workflow);
+        let source = format!("/tideloom/{}", instance.workflow);
+        let mut event = CloudEvent::new(uuid::Uuid::new_v4().to_string(), source, type_).with_data(data);
+        event.subject = Some(instance.id.clone());
+        event.time = Some(Utc::now().to_rfc3339());
+        // The publishing task only stops with the runtime.
+        let _ = self.events.send(event);
+    }
+}
+
+fn timestamp(at: &DateTime<Utc>) -> Value {
+    Value::String(at.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::messaging::{EventSource, InMemoryEventBus};
+    use crate::runtime::WorkflowContext;
+
+    fn workflow() -> Workflow {
+        Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: lifecycle
+  version: '1.0.0'
+do:
+- greet:
+    run:
+      shell:
+        command: echo
+        arguments: ['hi']
+",
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn lifecycle_events_are_published_in_order() {
+        let bus = Arc::new(InMemoryEventBus::default());
+        let mut events = bus.subscribe().await.unwrap();
+        let ctx = WorkflowContext::default().with_observer(Arc::new(LifecycleEmitter::new(bus.clone())));
+
+        workflow().run(&ctx, json!({})).await.unwrap();
+
+        let mut types = Vec::new();
+        for _ in 0..4 {
+            types.push(events.next().await.unwrap());
+        }
+        let types: Vec<_> = types.iter().map(|event| event.type_.as_str()).collect();
+        assert_eq!(types, [WORKFLOW_STARTED, TASK_STARTED, TASK_COMPLETED, WORKFLOW_COMPLETED]);
+    }
+
+    #[tokio::test]
+    async fn task_events_can_be_left_out() {
+        let bus = Arc::new(InMemoryEventBus::default());
+        let mut events = bus.subscribe().await.unwrap();
+        let emitter = LifecycleEmitter::new(bus.clone()).without_task_events();
+        let ctx = WorkflowContext::default().with_observer(Arc::new(emitter));
+
+        workflow().run(&ctx, json!({})).await.unwrap();
+
+        let started = events.next().await.unwrap();
+        let completed = events.next().await.unwrap();
+        assert_eq!(started.type_, WORKFLOW_STARTED);
+        assert_eq!(started.source, "/tideloom/test/lifecycle:1.0.0");
+        assert_eq!(completed.type_, WORKFLOW_COMPLETED);
+        assert_eq!(completed.subject, started.subject);
+        let data = completed.data.unwrap();
+        assert_eq!(data["output"], json!("hi"));
+        assert_eq!(data["definition"], json!("test/lifecycle:1.0.0"));
+    }
+
+    #[tokio::test]
+    async fn started_events_describe_the_definition() {
+        let bus = Arc::new(InMemoryEventBus::default());
+        let mut events = bus.subscribe().await.unwrap();
+        let ctx = WorkflowContext::default().with_observer(Arc::new(LifecycleEmitter::new(bus.clone())));
+        let workflow = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: described
+  version: '1.0.0'
+  title: Greeter
+  summary: Says hi.
+  tags:
+    team: growth
+metadata:
+  owner: ada
+do:
+- greet:
+    run:
+      shell:
+        command: echo
+        arguments: ['hi']
+",
+        )
+        .unwrap();
+
+        workflow.run(&ctx, json!({})).await.unwrap();
+
+        let data = events.next().await.unwrap().data.unwrap();
+        assert_eq!(data["title"], json!("Greeter"));
+        assert_eq!(data["summary"], json!("Says hi."));
+        assert_eq!(data["tags"], json!({ "team": "growth" }));
+        assert_eq!(data["metadata"], json!({ "owner": "ada" }));
+    }
+}
@@ -0,0 +1,54 @@
+use futures::StreamExt;
+use tokio::sync::broadcast;
+
+use crate::messaging::{CloudEvent, EventSink, EventSource, EventStream};
+use crate::runtime::StepResult;
+
+/// In-process event bus, useful for tests and single-process deployments.
+#[derive(Debug, Clone)]
+pub struct InMemoryEventBus {
+    sender: broadcast::Sender<CloudEvent>,
+}
+
+impl InMemoryEventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Publishes an event to all current subscribers, returning how many received it.
+    pub fn publish(&self, event: CloudEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+}
+
+impl Default for InMemoryEventBus {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+#[async_trait::async_trait]
+impl EventSource for InMemoryEventBus {
+    async fn subscribe(&self) -> StepResult<EventStream> {
+        let rx = self.sender.subscribe();
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => return Some((event, rx)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(stream.boxed())
+    }
+}
+
+#[async_trait::async_trait]
+impl EventSink for InMemoryEventBus {
+    async fn publish(&self, event: CloudEvent) -> StepResult<()> {
+        InMemoryEventBus::publish(self, event);
+        Ok(())
+    }
+}
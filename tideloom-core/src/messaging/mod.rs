@@ -0,0 +1,17 @@
+pub mod broker;
+pub mod event;
+pub mod lifecycle;
+pub mod memory;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod sink;
+pub mod source;
+
+pub use broker::*;
+pub use event::*;
+pub use lifecycle::LifecycleEmitter;
+pub use memory::*;
+#[cfg(feature = "mqtt")]
+pub use mqtt::*;
+pub use sink::*;
+pub use source::*;
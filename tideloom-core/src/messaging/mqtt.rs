@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS};
+
+use crate::messaging::{Message, MessageBroker, MessageStream};
+use crate::runtime::StepResult;
+
+const DEFAULT_PORT: u16 = 1883;
+
+/// MQTT 3.1.1 backend: channels are topics and payloads are sent as JSON.
+///
+/// MQTT 3.1.1 has no message headers, so outgoing headers are dropped and incoming ones are empty.
+/// Each publish or subscription opens its own connection to the server.
+#[derive(Debug, Clone)]
+pub struct MqttBroker {
+    qos: QoS,
+    keep_alive: Duration,
+}
+
+impl MqttBroker {
+    pub fn new() -> Self {
+        Self {
+            qos: QoS::AtLeastOnce,
+            keep_alive: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    fn connect(&self, server: &str) -> StepResult<(AsyncClient, EventLoop)> {
+        let url = reqwest::Url::parse(server).map_err(|e| format!("invalid mqtt server '{server}': {e}"))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| format!("mqtt server '{server}' has no host"))?;
+        let client_id = format!("tideloom-{}", uuid::Uuid::new_v4());
+        let mut options = MqttOptions::new(client_id, host, url.port().unwrap_or(DEFAULT_PORT));
+        options.set_keep_alive(self.keep_alive);
+        if !url.username().is_empty() {
+            options.set_credentials(url.username(), url.password().unwrap_or_default());
+        }
+        Ok(AsyncClient::new(options, 16))
+    }
+}
+
+impl Default for MqttBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageBroker for MqttBroker {
+    async fn publish(&self, server: &str, channel: &str, message: Message) -> StepResult<()> {
+        let (client, mut events) = self.connect(server)?;
+        let payload = serde_json::to_vec(&message.payload).map_err(|e| format!("invalid mqtt payload: {e}"))?;
+        client
+            .publish(channel, self.qos, false, payload)
+            .await
+            .map_err(|e| format!("mqtt publish to '{channel}' failed: {e}"))?;
+        loop {
+            match events.poll().await {
+                Ok(Event::Incoming(Packet::PubAck(_) | Packet::PubComp(_))) => break,
+                Ok(Event::Outgoing(rumqttc::Outgoing::Publish(_))) if self.qos == QoS::AtMostOnce => break,
+                Ok(_) => {}
+                Err(e) => return Err(format!("mqtt publish to '{channel}' on '{server}' failed: {e}").into()),
+            }
+        }
+        // The message is acknowledged; failing to disconnect cleanly loses nothing.
+        let _ = client.disconnect().await;
+        Ok(())
+    }
+
+    async fn subscribe(&self, server: &str, channel: &str) -> StepResult<MessageStream> {
+        let (client, mut events) = self.connect(server)?;
+        client
+            .subscribe(channel, self.qos)
+            .await
+            .map_err(|e| format!("mqtt subscribe to '{channel}' failed: {e}"))?;
+        // Wait for the subscription to be acknowledged so that later publications are seen.
+        loop {
+            match events.poll().await {
+                Ok(Event::Incoming(Packet::SubAck(_))) => break,
+                Ok(_) => {}
+                Err(e) => return Err(format!("mqtt subscribe to '{channel}' on '{server}' failed: {e}").into()),
+            }
+        }
+        let stream = futures::stream::unfold((client, events), |(client, mut events)| async move {
+            loop {
+                match events.poll().await {
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let payload = serde_json::from_slice(&publish.payload).unwrap_or_else(|_| {
+                            serde_json::Value::String(String::from_utf8_lossy(&publish.payload).into_owned())
+                        });
+                        let message = Message {
+                            payload,
+                            headers: Default::default(),
+                        };
+                        return Some((message, (client, events)));
+                    }
+                    Ok(_) => {}
+                    Err(_) => return None,
+                }
+            }
+        });
+        Ok(stream.boxed())
+    }
+}
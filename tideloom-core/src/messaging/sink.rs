@@ -0,0 +1,8 @@
+use crate::messaging::CloudEvent;
+use crate::runtime::StepResult;
+
+/// Destination for events produced by `emit` tasks, e.g. a Kafka or NATS producer.
+#[async_trait::async_trait]
+pub trait EventSink: Send + Sync {
+    async fn publish(&self, event: CloudEvent) -> StepResult<()>;
+}
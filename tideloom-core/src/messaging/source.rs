@@ -0,0 +1,15 @@
+use futures::stream::BoxStream;
+
+use crate::messaging::CloudEvent;
+use crate::runtime::StepResult;
+
+pub type EventStream = BoxStream<'static, CloudEvent>;
+
+/// Source of inbound events consumed by `listen` tasks.
+///
+/// Each call to `subscribe` opens an independent subscription that only sees events published after it
+/// was created.
+#[async_trait::async_trait]
+pub trait EventSource: Send + Sync {
+    async fn subscribe(&self) -> StepResult<EventStream>;
+}
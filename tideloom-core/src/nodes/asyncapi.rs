@@ -1,177 +1,496 @@
-use serde::Deserialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use futures::StreamExt;
 use serde_json::{Map, Value};
-use serverless_workflow_core::models::authentication::AuthenticationPolicyDefinition;
+use serverless_workflow_core::models::duration::OneOfDurationOrIso8601Expression;
 use serverless_workflow_core::models::task::{CallTaskDefinition, TaskDefinition};
-use std::str::FromStr;
 
-use crate::runtime::{Task, StepResult, WorkflowContext};
+use crate::messaging::Message;
+use crate::nodes::openapi::fetch_document;
+use crate::runtime::expr::{resolve_with, value_to_string};
+use crate::runtime::timeout::duration_of;
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext};
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct AsyncApiDocument {
-    #[serde(default)]
-    pub uri: Option<String>,
-    #[serde(default)]
-    pub content: Option<Value>,
+/// Where the AsyncAPI document of a call comes from.
+#[derive(Debug, Clone)]
+pub enum AsyncApiSource {
+    Uri(String),
+    Inline(Value),
 }
 
-impl AsyncApiDocument {
-    fn to_value(&self) -> Value {
-        let mut map = Map::new();
-        if let Some(uri) = &self.uri {
-            map.insert("uri".into(), Value::String(uri.clone()));
-        }
-        if let Some(content) = &self.content {
-            map.insert("content".into(), content.clone());
-        }
-        Value::Object(map)
-    }
+/// Whether the workflow sends or receives the messages of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsyncApiAction {
+    Send,
+    Receive,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct MessageConfig {
-    #[serde(default)]
-    pub payload: Value,
+/// A server an operation's channel is available on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsyncApiServer {
+    pub name: String,
+    /// Server URL with its variables left as `{name}` placeholders, e.g. `mqtt://{host}:1883`.
+    pub url: String,
+    pub protocol: String,
+    /// Defaults of the server variables.
+    pub variables: Map<String, Value>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct AsyncApiConfig {
-    pub document: AsyncApiDocument,
-    #[serde(rename = "operationRef")]
-    pub operation_ref: String,
-    #[serde(default)]
-    pub server: Option<String>,
-    #[serde(default)]
-    pub message: Option<MessageConfig>,
-    #[serde(default)]
-    pub authentication: AuthenticationPolicyDefinition,
+/// An operation resolved from an AsyncAPI document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsyncApiOperation {
+    pub action: AsyncApiAction,
+    /// Address of the channel, with its parameters left as `{name}` placeholders.
+    pub channel: String,
+    pub servers: Vec<AsyncApiServer>,
 }
 
-#[derive(Debug, Clone)]
-pub struct HTTPNode {
-    endpoint: reqwest::Url,
-    method: reqwest::Method,
+/// How many messages a receiving call consumes, from `subscription.consume`: at most `amount`, during at
+/// most `within`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Consume {
+    pub amount: Option<usize>,
+    pub within: Option<Duration>,
 }
 
-impl HTTPNode {
+/// `call: asyncapi` task: resolves `operation` in the referenced document and sends or receives messages on
+/// the bound channel through the context's broker for the server protocol.
+///
+/// Sending publishes `message` and passes the task input through. Receiving consumes messages as bounded by
+/// `subscription.consume` (one by default) and outputs their payloads as an array. Channel parameters are
+/// filled from the task input, like the placeholders of an HTTP endpoint.
+#[derive(Debug)]
+pub struct AsyncApiNode {
+    source: AsyncApiSource,
+    operation: String,
+    server: Option<String>,
+    server_variables: Map<String, Value>,
+    protocol: Option<String>,
+    payload: Value,
+    headers: Value,
+    consume: Consume,
+    document: OnceLock<Value>,
+}
+
+impl AsyncApiNode {
     pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
         match task {
-            TaskDefinition::Call(call) => match call.call.to_lowercase().as_str() {
-                "asyncapi" => Self::try_from_http(call),
-                "http" => Self::try_from_http(call),
-                _ => Err(format!("expected call 'asyncapi', got '{}'", call.call)),
-            },
+            TaskDefinition::Call(call) if call.call.eq_ignore_ascii_case("asyncapi") => Self::try_from_asyncapi(call),
+            TaskDefinition::Call(call) => Err(format!("expected call 'asyncapi', got '{}'", call.call).into()),
             _ => Err("AsyncApiNode expects a `call` task definition".into()),
         }
     }
 
-    pub fn try_from_http(call: &CallTaskDefinition) -> StepResult<Self> {
+    pub fn try_from_asyncapi(call: &CallTaskDefinition) -> StepResult<Self> {
         let with = call
             .with
             .as_ref()
             .ok_or_else(|| "asyncapi call requires a `with` block".to_string())?;
 
-        let mut with_map = Map::new();
-        for (key, value) in with {
-            with_map.insert(key.clone(), value.clone());
+        let source = match with.get("document") {
+            Some(Value::String(uri)) => AsyncApiSource::Uri(uri.clone()),
+            Some(Value::Object(doc)) => match (doc.get("content"), doc.get("endpoint"), doc.get("uri")) {
+                (Some(content), _, _) => AsyncApiSource::Inline(content.clone()),
+                (_, Some(Value::String(uri)), _) | (_, _, Some(Value::String(uri))) => AsyncApiSource::Uri(uri.clone()),
+                (_, Some(Value::Object(endpoint)), _) => AsyncApiSource::Uri(
+                    endpoint
+                        .get("uri")
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| "asyncapi document endpoint requires a `uri`".to_string())?
+                        .to_string(),
+                ),
+                _ => return Err("asyncapi document requires an `endpoint` or inline `content`".into()),
+            },
+            Some(_) => return Err("asyncapi `document` must be a uri or an object".into()),
+            None => return Err("asyncapi call requires a `document`".into()),
+        };
+        // `operationRef` is the name used by documents converted from DSL 0.8.
+        let operation = with
+            .get("operation")
+            .or_else(|| with.get("operationRef"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| "asyncapi call requires an `operation`".to_string())?
+            .to_string();
+        let (server, server_variables) = match with.get("server") {
+            None | Some(Value::Null) => (None, Map::new()),
+            Some(Value::String(name)) => (Some(name.clone()), Map::new()),
+            Some(Value::Object(server)) => (
+                server.get("name").and_then(Value::as_str).map(str::to_string),
+                server.get("variables").and_then(Value::as_object).cloned().unwrap_or_default(),
+            ),
+            Some(_) => return Err("asyncapi `server` must be a name or an object".into()),
+        };
+        if with.contains_key("authentication") {
+            return Err("authentication of asyncapi calls is not supported".into());
         }
+        let message = with.get("message");
 
-        let endpoint_url = with_map.get("endpoint").unwrap().as_str().unwrap();
-        let method = with_map.get("method").unwrap().as_str().unwrap().to_string();
+        Ok(Self {
+            source,
+            operation,
+            server,
+            server_variables,
+            protocol: with.get("protocol").and_then(Value::as_str).map(str::to_lowercase),
+            payload: message.and_then(|m| m.get("payload")).cloned().unwrap_or(Value::Null),
+            headers: message.and_then(|m| m.get("headers")).cloned().unwrap_or(Value::Null),
+            consume: consume(with.get("subscription"))?,
+            document: OnceLock::new(),
+        })
+    }
 
-        let config: HTTPNode = HTTPNode {
-            endpoint: reqwest::Url::parse(endpoint_url).unwrap(),
-            method: reqwest::Method::from_str(&method).unwrap(),
+    async fn document(&self, ctx: &WorkflowContext) -> StepResult<&Value> {
+        if let Some(document) = self.document.get() {
+            return Ok(document);
+        }
+        let document = match &self.source {
+            AsyncApiSource::Inline(content) => content.clone(),
+            AsyncApiSource::Uri(uri) => fetch_document(ctx, uri, None, "asyncapi").await?,
         };
+        Ok(self.document.get_or_init(|| document))
+    }
 
-        Ok(config)
+    /// Picks the server named by the call, or the first one the channel is available on.
+    fn select_server<'a>(&self, operation: &'a AsyncApiOperation) -> StepResult<&'a AsyncApiServer> {
+        match &self.server {
+            Some(name) => operation.servers.iter().find(|s| &s.name == name).ok_or_else(|| {
+                format!("server '{name}' is not available for operation '{}'", self.operation).into()
+            }),
+            None => operation
+                .servers
+                .first()
+                .ok_or_else(|| format!("no server is available for operation '{}'", self.operation).into()),
+        }
     }
 
-    fn build_request(&self, _input: &Value) -> reqwest::Request {
-        
+    /// Fills the server URL variables, preferring the call's values over the document defaults.
+    fn server_url(&self, server: &AsyncApiServer) -> String {
+        let mut url = server.url.clone();
+        for (name, value) in self.server_variables.iter().chain(&server.variables) {
+            url = url.replace(&format!("{{{name}}}"), &value_to_string(value));
+        }
+        url
+    }
+}
 
-        reqwest::Request::new(
-            self.method.clone(),
-            self.endpoint.clone(),
-        )
+fn consume(subscription: Option<&Value>) -> StepResult<Consume> {
+    let once = Consume {
+        amount: Some(1),
+        within: None,
+    };
+    let Some(subscription) = subscription else {
+        return Ok(once);
+    };
+    if subscription.get("filter").is_some() {
+        return Err("asyncapi subscription filters are not supported".into());
+    }
+    let Some(settings) = subscription.get("consume") else {
+        return Ok(once);
+    };
+    for unsupported in ["while", "until"] {
+        if settings.get(unsupported).is_some() {
+            return Err(format!("asyncapi `consume.{unsupported}` is not supported").into());
+        }
+    }
+    let amount = settings
+        .get("amount")
+        .map(|amount| {
+            amount
+                .as_u64()
+                .and_then(|n| usize::try_from(n).ok())
+                .ok_or_else(|| "asyncapi `consume.amount` must be a positive integer".to_string())
+        })
+        .transpose()?;
+    let within = settings
+        .get("for")
+        .map(|within| {
+            let within: OneOfDurationOrIso8601Expression = serde_json::from_value(within.clone())
+                .map_err(|e| format!("invalid asyncapi `consume.for`: {e}"))?;
+            duration_of(&within)
+        })
+        .transpose()?;
+    match (amount, within) {
+        (None, None) => Ok(once),
+        (amount, within) => Ok(Consume { amount, within }),
     }
 }
 
-impl TryFrom<&TaskDefinition> for HTTPNode {
-    type Error = String;
+/// Finds `operation` in an AsyncAPI 3 document by its key, or in an AsyncAPI 2 document by `operationId`.
+pub fn resolve_operation(document: &Value, operation: &str) -> StepResult<AsyncApiOperation> {
+    let version = document.get("asyncapi").and_then(Value::as_str).unwrap_or_default();
+    let all_servers = || -> Vec<String> {
+        document
+            .get("servers")
+            .and_then(Value::as_object)
+            .map(|servers| servers.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+
+    let (action, channel, server_names) = if version.starts_with('3') {
+        let op = document
+            .pointer(&format!("/operations/{}", escape(operation)))
+            .ok_or_else(|| format!("operation '{operation}' not found in asyncapi document"))?;
+        let action = match op.get("action").and_then(Value::as_str) {
+            Some("send") => AsyncApiAction::Send,
+            Some("receive") => AsyncApiAction::Receive,
+            other => return Err(format!("operation '{operation}' has an invalid action {other:?}").into()),
+        };
+        let channel_ref = op
+            .pointer("/channel/$ref")
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("operation '{operation}' has no channel reference"))?;
+        let channel = resolve_ref(document, channel_ref)?;
+        let name = channel_ref.rsplit('/').next().unwrap_or_default();
+        let address = channel.get("address").and_then(Value::as_str).unwrap_or(name);
+        let servers = match channel.get("servers").and_then(Value::as_array) {
+            Some(refs) => refs
+                .iter()
+                .filter_map(|s| s.get("$ref").and_then(Value::as_str))
+                .map(|r| r.rsplit('/').next().unwrap_or_default().to_string())
+                .collect(),
+            None => all_servers(),
+        };
+        (action, address.to_string(), servers)
+    } else if version.starts_with('2') {
+        let channels = document
+            .get("channels")
+            .and_then(Value::as_object)
+            .ok_or_else(|| "asyncapi document has no `channels`".to_string())?;
+        let found = channels.iter().find_map(|(name, item)| {
+            [("publish", AsyncApiAction::Send), ("subscribe", AsyncApiAction::Receive)]
+                .into_iter()
+                .find(|(verb, _)| {
+                    item.pointer(&format!("/{verb}/operationId")).and_then(Value::as_str) == Some(operation)
+                })
+                .map(|(_, action)| (name, item, action))
+        });
+        let (name, item, action) =
+            found.ok_or_else(|| format!("operation '{operation}' not found in asyncapi document"))?;
+        let servers = match item.get("servers").and_then(Value::as_array) {
+            Some(names) => names.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            None => all_servers(),
+        };
+        (action, name.clone(), servers)
+    } else {
+        return Err(format!("unsupported asyncapi version '{version}'").into());
+    };
+
+    let servers = server_names
+        .iter()
+        .map(|name| {
+            let server = document
+                .pointer(&format!("/servers/{}", escape(name)))
+                .ok_or_else(|| format!("server '{name}' not found in asyncapi document"))?;
+            server_of(name, server)
+        })
+        .collect::<StepResult<_>>()?;
+    Ok(AsyncApiOperation { action, channel, servers })
+}
+
+fn server_of(name: &str, server: &Value) -> StepResult<AsyncApiServer> {
+    let protocol = server
+        .get("protocol")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("server '{name}' has no protocol"))?
+        .to_lowercase();
+    // AsyncAPI 3 splits the URL into `host` and `pathname`; AsyncAPI 2 has a single `url`.
+    let url = match (server.get("host").and_then(Value::as_str), server.get("url").and_then(Value::as_str)) {
+        (Some(host), _) => format!(
+            "{protocol}://{host}{}",
+            server.get("pathname").and_then(Value::as_str).unwrap_or_default()
+        ),
+        (None, Some(url)) if url.contains("://") => url.to_string(),
+        (None, Some(url)) => format!("{protocol}://{url}"),
+        (None, None) => return Err(format!("server '{name}' has no host").into()),
+    };
+    let variables = server
+        .get("variables")
+        .and_then(Value::as_object)
+        .map(|variables| {
+            variables
+                .iter()
+                .filter_map(|(k, v)| v.get("default").map(|d| (k.clone(), d.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(AsyncApiServer {
+        name: name.to_string(),
+        url,
+        protocol,
+        variables,
+    })
+}
+
+fn resolve_ref<'a>(document: &'a Value, reference: &str) -> StepResult<&'a Value> {
+    reference
+        .strip_prefix('#')
+        .and_then(|pointer| document.pointer(pointer))
+        .ok_or_else(|| format!("unresolvable asyncapi reference '{reference}'").into())
+}
+
+/// Escapes a key for use in a JSON pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Fills `{name}` channel parameters from the input object.
+fn channel_address(channel: &str, input: &Value) -> String {
+    let mut address = channel.to_string();
+    if let Value::Object(args) = input {
+        for (key, value) in args {
+            address = address.replace(&format!("{{{key}}}"), &value_to_string(value));
+        }
+    }
+    address
+}
+
+impl TryFrom<&TaskDefinition> for AsyncApiNode {
+    type Error = StepError;
 
     fn try_from(task: &TaskDefinition) -> std::result::Result<Self, Self::Error> {
         Self::try_from_task(task)
     }
 }
 
-impl TryFrom<&CallTaskDefinition> for HTTPNode {
-    type Error = String;
+impl TryFrom<&CallTaskDefinition> for AsyncApiNode {
+    type Error = StepError;
 
     fn try_from(call: &CallTaskDefinition) -> std::result::Result<Self, Self::Error> {
-        Self::try_from_http(call)
+        Self::try_from_asyncapi(call)
     }
 }
 
 #[async_trait::async_trait]
-impl Task for HTTPNode {
+impl Task for AsyncApiNode {
     type Input = Value;
     type Output = Value;
 
     async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
-        let req = self.build_request(&input);
-        ctx.http_client.execute(req).await.unwrap();
+        let operation = resolve_operation(self.document(ctx).await?, &self.operation)?;
+        let server = self.select_server(&operation)?;
+        let protocol = self.protocol.as_deref().unwrap_or(&server.protocol);
+        let broker = ctx
+            .brokers
+            .get(protocol)
+            .ok_or_else(|| format!("no message broker is registered for protocol '{protocol}'"))?;
+        let url = self.server_url(server);
+        let channel = channel_address(&operation.channel, &input);
 
-        // TODO: fix me
-        Ok(Value::Null)
+        match operation.action {
+            AsyncApiAction::Send => {
+                let vars = ctx.vars();
+                let headers = match resolve_with(&self.headers, &input, &vars)? {
+                    Value::Object(headers) => headers,
+                    Value::Null => Map::new(),
+                    _ => return Err("asyncapi message `headers` must be an object".into()),
+                };
+                let message = Message {
+                    payload: resolve_with(&self.payload, &input, &vars)?,
+                    headers,
+                };
+                broker.publish(&url, &channel, message).await?;
+                Ok(input)
+            }
+            AsyncApiAction::Receive => {
+                let stream = broker.subscribe(&url, &channel).await?;
+                let stream = stream.take(self.consume.amount.unwrap_or(usize::MAX));
+                let payloads: Vec<Value> = match self.consume.within {
+                    Some(within) => stream.take_until(tokio::time::sleep(within)).map(|m| m.payload).collect().await,
+                    None => stream.map(|m| m.payload).collect().await,
+                };
+                if self.consume.within.is_none() && payloads.len() < self.consume.amount.unwrap_or_default() {
+                    return Err(format!("subscription to '{channel}' closed after {} messages", payloads.len()).into());
+                }
+                Ok(Value::Array(payloads))
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use serde_json::json;
-    use serverless_workflow_core::models::workflow::WorkflowDefinition;
 
     use super::*;
+    use crate::messaging::InMemoryBroker;
+
+    fn orders_v3() -> Value {
+        json!({
+            "asyncapi": "3.0.0",
+            "servers": {
+                "production": { "host": "{region}.broker.example.com:1883", "protocol": "mqtt",
+                                "variables": { "region": { "default": "eu" } } }
+            },
+            "channels": {
+                "orderPlaced": { "address": "orders/{orderId}/placed" }
+            },
+            "operations": {
+                "publishOrder": { "action": "send", "channel": { "$ref": "#/channels/orderPlaced" } },
+                "onOrder": { "action": "receive", "channel": { "$ref": "#/channels/orderPlaced" } }
+            }
+        })
+    }
 
-    fn load_first_task(yaml: &str) -> TaskDefinition {
-        let workflow: WorkflowDefinition = serde_yaml::from_str(yaml).expect("invalid yaml");
-        workflow
-            .do_
-            .entries
-            .first()
-            .and_then(|entry| entry.iter().next())
-            .map(|(_, task)| task.clone())
-            .expect("missing task")
+    fn asyncapi_node(with: Value) -> AsyncApiNode {
+        let call: CallTaskDefinition = serde_json::from_value(json!({ "call": "asyncapi", "with": with })).unwrap();
+        AsyncApiNode::try_from_asyncapi(&call).unwrap()
+    }
+
+    #[test]
+    fn resolves_v3_and_v2_operations() {
+        let operation = resolve_operation(&orders_v3(), "onOrder").unwrap();
+        assert_eq!(operation.action, AsyncApiAction::Receive);
+        assert_eq!(operation.channel, "orders/{orderId}/placed");
+        assert_eq!(operation.servers[0].url, "mqtt://{region}.broker.example.com:1883");
+        assert_eq!(operation.servers[0].variables["region"], json!("eu"));
+
+        let v2 = json!({
+            "asyncapi": "2.6.0",
+            "servers": { "test": { "url": "localhost:9092", "protocol": "kafka" } },
+            "channels": { "user.signedup": { "publish": { "operationId": "signUp" } } }
+        });
+        let operation = resolve_operation(&v2, "signUp").unwrap();
+        assert_eq!(operation.action, AsyncApiAction::Send);
+        assert_eq!(operation.channel, "user.signedup");
+        assert_eq!(operation.servers[0].url, "kafka://localhost:9092");
+        assert!(resolve_operation(&v2, "missing").is_err());
     }
 
     #[tokio::test]
-    async fn http_node_from_task() {
-        let yaml = r#"
-document:
-  dsl: '1.0.1'
-  namespace: test
-  name: http-example
-  version: '0.1.0'
-do:
- - test:
-     call: http
-     with:
-        method: get
-        endpoint: https://httpbin.org/get
-
- "#;
-
-        let task = load_first_task(yaml);
-        let step = HTTPNode::try_from_task(&task).expect("asyncapi node");
-        let c = WorkflowContext::default();
-        let ctx = WorkflowContext::default();
-        let input = json!({});
-
-        let output = step
-            .execute(&ctx, input)
-            .await
-            .expect("step should succeed");
-
-        println!("{:?}", output);
+    async fn sends_and_receives_through_the_protocol_broker() {
+        let document = json!({ "content": orders_v3() });
+        let receive = asyncapi_node(json!({
+            "document": document,
+            "operation": "onOrder",
+            "server": { "name": "production", "variables": { "region": "us" } },
+            "subscription": { "consume": { "amount": 2 } }
+        }));
+        let send = asyncapi_node(json!({
+            "document": document,
+            "operation": "publishOrder",
+            "message": { "payload": { "id": "${ .orderId }" } }
+        }));
+        assert_eq!(
+            receive.server_url(&resolve_operation(&orders_v3(), "onOrder").unwrap().servers[0]),
+            "mqtt://us.broker.example.com:1883"
+        );
+        let ctx = WorkflowContext::default().with_broker("MQTT", Arc::new(InMemoryBroker::default()));
+        let input = json!({ "orderId": "o-1" });
+
+        let received = receive.execute(&ctx, input.clone());
+        let sent = async {
+            // Let the receiver subscribe first.
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            for _ in 0..2 {
+                assert_eq!(send.execute(&ctx, input.clone()).await.unwrap(), input);
+            }
+        };
+        let (received, ()) = tokio::join!(received, sent);
+
+        assert_eq!(received.unwrap(), json!([{ "id": "o-1" }, { "id": "o-1" }]));
+        let err = send.execute(&WorkflowContext::default(), input).await.unwrap_err();
+        assert_eq!(err, "no message broker is registered for protocol 'mqtt'");
     }
 }
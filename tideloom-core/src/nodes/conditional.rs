@@ -0,0 +1,143 @@
+use serde_json::{Map, Value};
+use serverless_workflow_core::models::task::TaskDefinitionFields;
+
+use crate::nodes::doing::BoxedTask;
+use crate::runtime::expr::{holds_with, parse_path};
+use crate::runtime::flags::FlagTarget;
+use crate::runtime::{StepResult, Task, WorkflowContext};
+
+/// Runs a task only when its `if` condition, evaluated against the raw task input, is true; a skipped task
+/// passes its input through.
+///
+/// A condition rooted at `$flags`, such as `${ $flags.newCheckout }`, reads the flag from the context's
+/// flag provider when the task starts. A missing value counts as false.
+pub struct ConditionalNode {
+    condition: String,
+    /// Name of the flag the condition reads, if any.
+    flag: Option<String>,
+    inner: BoxedTask,
+}
+
+impl ConditionalNode {
+    /// Wraps `inner` when its definition declares an `if` condition, and returns it unchanged otherwise.
+    pub fn wrap(inner: BoxedTask, fields: &TaskDefinitionFields) -> StepResult<BoxedTask> {
+        let Some(condition) = &fields.if_ else {
+            return Ok(inner);
+        };
+        let path = parse_path(condition).map_err(|e| format!("if: {e}"))?;
+        let flag = match path.as_slice() {
+            [root, name, ..] if root == "$flags" => Some(name.clone()),
+            [root] if root == "$flags" => return Err("if: `$flags` must name a flag, e.g. `$flags.myFlag`".into()),
+            _ => None,
+        };
+        Ok(Box::new(Self {
+            condition: condition.clone(),
+            flag,
+            inner,
+        }))
+    }
+
+    async fn holds(&self, ctx: &WorkflowContext, input: &Value) -> StepResult<bool> {
+        let mut vars = ctx.vars();
+        if let Some(name) = &self.flag {
+            let provider = ctx
+                .flags
+                .as_ref()
+                .ok_or_else(|| format!("if: flag '{name}' requires a flag provider in the workflow context"))?;
+            let target = FlagTarget {
+                tenant: ctx.tenant.as_deref(),
+                instance: &ctx.instance,
+            };
+            let mut flags = Map::new();
+            if let Some(value) = provider.evaluate(name, target).await? {
+                flags.insert(name.clone(), value);
+            }
+            vars.insert("$flags".to_string(), Value::Object(flags));
+        }
+        holds_with(input, &vars, &self.condition).map_err(|e| format!("if: {e}").into())
+    }
+}
+
+impl std::fmt::Debug for ConditionalNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConditionalNode")
+            .field("condition", &self.condition)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for ConditionalNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        if self.holds(ctx, &input).await? {
+            self.inner.execute(ctx, input).await
+        } else {
+            Ok(input)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::overrides::{Overrides, TaskOverride};
+    use crate::runtime::WorkflowContext;
+    use crate::runtime::flags::InMemoryFlags;
+
+    fn checkout() -> Workflow {
+        Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: checkout
+  version: '1.0.0'
+do:
+- price:
+    if: ${ $flags.newCheckout }
+    call: http
+    with:
+      method: get
+      endpoint: http://pricing.invalid/v2/quote
+"#,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn flags_gate_tasks_per_tenant() {
+        let flags = InMemoryFlags::new()
+            .with_flag("newCheckout", false)
+            .with_tenant_flag("acme", "newCheckout", true);
+        let ctx = WorkflowContext::default().with_flags(Arc::new(flags));
+        let workflow = checkout();
+        let mock = TaskOverride {
+            mock: Some(json!({ "total": 42 })),
+            ..Default::default()
+        };
+        let overrides = Overrides::new().with_task("/do/0/price", mock);
+
+        let output = workflow.run_with_overrides(&ctx, json!({ "cart": 1 }), &overrides).await.unwrap();
+        assert_eq!(output, json!({ "cart": 1 }));
+        let acme = ctx.clone().with_tenant("acme");
+        let output = workflow.run_with_overrides(&acme, json!({ "cart": 1 }), &overrides).await.unwrap();
+        assert_eq!(output, json!({ "total": 42 }));
+    }
+
+    #[tokio::test]
+    async fn conditions_must_be_boolean_and_flags_need_a_provider() {
+        let err = checkout().run(&WorkflowContext::default(), json!({})).await.unwrap_err();
+        assert!(err.contains("flag provider"), "{err}");
+
+        let ctx = WorkflowContext::default().with_flags(Arc::new(InMemoryFlags::new().with_flag("newCheckout", "on")));
+        let err = checkout().run(&ctx, json!({})).await.unwrap_err();
+        assert!(err.contains("expected a boolean"), "{err}");
+    }
+}
@@ -1,54 +1,386 @@
 use std::collections::HashMap;
-use serde_json::{Map, Value};
-use serverless_workflow_core::models::task::{CallTaskDefinition, DoTaskDefinition, TaskDefinition};
-use crate::runtime::{StepResult, Task, WorkflowContext};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 
-#[derive(Debug, Clone)]
+use chrono::Utc;
+use serde_json::Value;
+use serverless_workflow_core::models::map::Map;
+use serverless_workflow_core::models::task::{DoTaskDefinition, TaskDefinition, TaskDefinitionFields};
+use tracing::Instrument;
+
+use crate::nodes::asyncapi::AsyncApiNode;
+use crate::nodes::conditional::ConditionalNode;
+use crate::nodes::http::HTTPNode;
+use crate::nodes::emit::EmitNode;
+use crate::nodes::fanout::FanOutNode;
+use crate::nodes::forking::ForkNode;
+use crate::nodes::function::FunctionCallNode;
+use crate::nodes::limited::LimitedNode;
+use crate::nodes::listen::ListenNode;
+use crate::nodes::looping::ForNode;
+use crate::nodes::memoized::MemoizedNode;
+use crate::nodes::mock::MockNode;
+use crate::nodes::openapi::OpenApiNode;
+use crate::nodes::run::RunNode;
+use crate::nodes::pipeline::PipelineNode;
+use crate::nodes::switch::{FlowDirective, SwitchNode, SwitchOutcome};
+use crate::nodes::timeout::TimeoutNode;
+use crate::nodes::trying::TryNode;
+use crate::runtime::metrics::TASK_DURATION;
+use crate::runtime::observer::WorkflowEvent;
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext, cancelled_error};
+use crate::validation::BUILT_IN_CALLS;
+
+pub type BoxedTask = Box<dyn Task<Input = Value, Output = Value>>;
+
+/// Runs its subtasks sequentially, feeding each task's output into the next one.
+///
+/// After each task, its `then` directive (or the directive picked by a `switch` task) decides which task
+/// runs next. `end` leaves the block and, through [`WorkflowContext::ended`], every block enclosing it, so the
+/// workflow completes with the task's output as transformed by the enclosing tasks.
+///
+/// Each task runs in a `task` span recording its position in the workflow document, such as
+/// `/do/1/pay/try/0/charge`; nested blocks nest their spans.
 pub struct DoNode {
-    pub tasks: HashMap<String, dyn Task<Input=Value, Output=Value>>,
+    pub tasks: Vec<(String, BoxedTask)>,
+    /// JSON pointer to the task list, such as `/do/1/pay/try`.
+    pointer: String,
+    /// Kind of each task, as reported in metrics.
+    kinds: Vec<String>,
+    flow: Vec<Flow>,
+}
+
+/// How the next task is chosen once a task completed.
+#[derive(Debug, Clone)]
+enum Flow {
+    Then(FlowDirective),
+    Switch(SwitchNode),
+}
+
+/// Where a task, or a task list, sits in the workflow document while its node is built, with the stand-ins of
+/// the run's mocked and disabled tasks.
+#[derive(Debug, Clone)]
+pub(crate) struct TaskSite {
+    /// JSON pointer, such as `/do/1/pay` for a task or `/do/1/pay/try` for a task list.
+    pointer: String,
+    /// Stand-ins by task pointer, from [`Overrides`](crate::overrides::Overrides).
+    mocks: Arc<HashMap<String, MockNode>>,
+}
+
+impl TaskSite {
+    pub(crate) fn new(pointer: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            mocks: Arc::default(),
+        }
+    }
+
+    pub(crate) fn with_mocks(mut self, mocks: HashMap<String, MockNode>) -> Self {
+        self.mocks = Arc::new(mocks);
+        self
+    }
+
+    /// Name of the task at this site: the last segment of its pointer.
+    pub(crate) fn name(&self) -> &str {
+        self.pointer.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// The site of `segment` within this one, e.g. `try` or `fork/branches/0/left`.
+    pub(crate) fn join(&self, segment: impl std::fmt::Display) -> Self {
+        Self {
+            pointer: format!("{}/{segment}", self.pointer),
+            mocks: self.mocks.clone(),
+        }
+    }
 }
 
 impl DoNode {
-    pub fn try_from_definition(call: &DoTaskDefinition) -> StepResult<Self> {
+    /// Builds the node from the `do` of a task. Positions are reported relative to the task, as
+    /// `/do/{index}/{name}`.
+    pub fn try_from_definition(def: &DoTaskDefinition) -> StepResult<Self> {
+        Self::try_from_tasks(&def.do_)
+    }
+
+    /// Builds the node from a task list, such as the `do` of a task or of a workflow. Positions are reported
+    /// relative to the list, as `/do/{index}/{name}`.
+    pub fn try_from_tasks(tasks_def: &Map<String, TaskDefinition>) -> StepResult<Self> {
+        Self::build(tasks_def, &TaskSite::new("/do"))
+    }
+
+    /// Builds the node from the task list at `site`.
+    pub(crate) fn build(tasks_def: &Map<String, TaskDefinition>, site: &TaskSite) -> StepResult<Self> {
+        let mut tasks = Vec::new();
+        let mut kinds = Vec::new();
+        let mut flow = Vec::new();
+        let named = tasks_def.entries.iter().flat_map(|entry| entry.iter());
+        for (index, (name, task)) in named.enumerate() {
+            let site = site.join(format!("{index}/{name}"));
+            let (node, then) = match task {
+                TaskDefinition::Switch(switch) => {
+                    let switch = SwitchNode::try_from_switch(switch)?;
+                    (build_task_with(task, &site, || Ok(Box::new(switch.clone())))?, Flow::Switch(switch))
+                }
+                _ => {
+                    let then = match &task_fields(task).then {
+                        Some(then) => then.parse()?,
+                        None => FlowDirective::Continue,
+                    };
+                    (build_task(task, &site)?, Flow::Then(then))
+                }
+            };
+            tasks.push((name.clone(), node));
+            kinds.push(task_kind(task));
+            flow.push(then);
+        }
+        let node = Self {
+            tasks,
+            pointer: site.pointer.clone(),
+            kinds,
+            flow,
+        };
+        for ((name, _), flow) in node.tasks.iter().zip(&node.flow) {
+            let directives: Vec<&FlowDirective> = match flow {
+                Flow::Then(then) => vec![then],
+                Flow::Switch(switch) => switch.directives().collect(),
+            };
+            for directive in directives {
+                if let FlowDirective::Goto(target) = directive
+                    && node.position(target).is_none()
+                {
+                    return Err(format!("task '{name}' flows to unknown task '{target}'").into());
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.tasks.iter().position(|(task, _)| task == name)
+    }
+
+    /// Runs task number `index` in its span, reporting its duration and outcome to metrics and observers.
+    async fn run_task(
+        &self,
+        ctx: &WorkflowContext,
+        index: usize,
+        name: &str,
+        task: &BoxedTask,
+        input: Value,
+    ) -> StepResult<Value> {
+        let position = format!("{}/{index}/{name}", self.pointer);
+        let span = tracing::info_span!(
+            "task",
+            task = %name,
+            position = %position,
+            duration_ms = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        ctx.observe(|| WorkflowEvent::TaskStarted {
+            instance: ctx.instance.clone(),
+            task: name.to_string(),
+            position: position.clone(),
+            input: ctx.redact(&input),
+            at: Utc::now(),
+        });
+        let started = Instant::now();
+        let result = task.execute(ctx, input).instrument(span.clone()).await;
+        let duration = started.elapsed();
+        span.record("duration_ms", duration.as_millis() as u64);
+        if let Some(metrics) = &ctx.metrics {
+            let labels = [("kind", self.kinds[index].clone()), ("task", name.to_string())];
+            metrics.record_histogram(TASK_DURATION, &labels, duration.as_secs_f64());
+        }
+        match &result {
+            Ok(output) => ctx.observe(|| WorkflowEvent::TaskCompleted {
+                instance: ctx.instance.clone(),
+                task: name.to_string(),
+                position,
+                output: ctx.redact(output),
+                at: Utc::now(),
+                duration,
+            }),
+            Err(e) => {
+                let error = ctx.redact_error(e.clone());
+                span.record("error", error.message());
+                ctx.observe(|| WorkflowEvent::TaskFaulted {
+                    instance: ctx.instance.clone(),
+                    task: name.to_string(),
+                    position,
+                    error: error.into(),
+                    at: Utc::now(),
+                    duration,
+                });
+            }
+        }
+        result
+    }
+}
+
+/// Builds the node of the task at `site`, wrapped in the nodes applying its common fields.
+pub(crate) fn build_task(task: &TaskDefinition, site: &TaskSite) -> StepResult<BoxedTask> {
+    build_task_with(task, site, || build_node(task, site))
+}
 
+/// Wraps the node made by `build` in the nodes applying the common fields of the task at `site`; `build` is not
+/// called when the task is mocked.
+fn build_task_with(
+    task: &TaskDefinition,
+    site: &TaskSite,
+    build: impl FnOnce() -> StepResult<BoxedTask>,
+) -> StepResult<BoxedTask> {
+    let fields = task_fields(task);
+    match site.mocks.get(&site.pointer) {
+        Some(MockNode { output: None }) => return Ok(Box::new(MockNode { output: None })),
+        Some(mock) => return ConditionalNode::wrap(PipelineNode::wrap(Box::new(mock.clone()), fields)?, fields),
+        None => {}
     }
+    let node = FanOutNode::wrap(MemoizedNode::wrap(LimitedNode::wrap(build()?, task), task)?, task)?;
+    let node = PipelineNode::wrap(TimeoutNode::wrap(node, site.name(), fields)?, fields)?;
+    ConditionalNode::wrap(node, fields)
+}
 
-    fn build_request(&self, _input: &Value) -> reqwest::Request {
+/// Builds the node running the task at `site` itself.
+fn build_node(task: &TaskDefinition, site: &TaskSite) -> StepResult<BoxedTask> {
+    let node: BoxedTask = match task {
+        TaskDefinition::Call(call) if call.call.eq_ignore_ascii_case("openapi") => {
+            Box::new(OpenApiNode::try_from_task(task)?)
+        }
+        TaskDefinition::Call(call) if call.call.eq_ignore_ascii_case("asyncapi") => {
+            Box::new(AsyncApiNode::try_from_task(task)?)
+        }
+        TaskDefinition::Call(call) if !BUILT_IN_CALLS.contains(&call.call.to_ascii_lowercase().as_str()) => {
+            Box::new(FunctionCallNode::try_from_call(call)?)
+        }
+        TaskDefinition::Call(_) => Box::new(HTTPNode::try_from_task(task)?),
+        TaskDefinition::Do(def) => Box::new(DoNode::build(&def.do_, &site.join("do"))?),
+        TaskDefinition::Emit(emit) => Box::new(EmitNode::try_from_emit(emit)?),
+        TaskDefinition::Fork(def) => Box::new(ForkNode::build(def, site)?),
+        TaskDefinition::For(def) => Box::new(ForNode::build(def, site)?),
+        TaskDefinition::Listen(listen) => Box::new(ListenNode::try_from_listen(listen)?),
+        TaskDefinition::Run(run) => Box::new(RunNode::try_from_run(run)?),
+        TaskDefinition::Switch(switch) => Box::new(SwitchNode::try_from_switch(switch)?),
+        TaskDefinition::Try(def) => Box::new(TryNode::build(def, site)?),
+        _ => return Err("unsupported task type in `do` block".into()),
+    };
+    Ok(node)
+}
 
+/// Returns the kind of a task: the `call` target (`http`, `grpc`, ...) for calls, and the task type otherwise.
+pub fn task_kind(task: &TaskDefinition) -> String {
+    let kind = match task {
+        TaskDefinition::Call(call) => return call.call.to_ascii_lowercase(),
+        TaskDefinition::Do(_) => "do",
+        TaskDefinition::Emit(_) => "emit",
+        TaskDefinition::For(_) => "for",
+        TaskDefinition::Fork(_) => "fork",
+        TaskDefinition::Listen(_) => "listen",
+        TaskDefinition::Raise(_) => "raise",
+        TaskDefinition::Run(_) => "run",
+        TaskDefinition::Set(_) => "set",
+        TaskDefinition::Switch(_) => "switch",
+        TaskDefinition::Try(_) => "try",
+        TaskDefinition::Wait(_) => "wait",
+    };
+    kind.to_string()
+}
 
-        reqwest::Request::new(
-            self.method.clone(),
-            self.endpoint.clone(),
-        )
+/// Returns the fields shared by every task definition (`if`, `input`, `output`, `then`, ...).
+pub fn task_fields(task: &TaskDefinition) -> &TaskDefinitionFields {
+    match task {
+        TaskDefinition::Call(t) => &t.common,
+        TaskDefinition::Do(t) => &t.common,
+        TaskDefinition::Emit(t) => &t.common,
+        TaskDefinition::For(t) => &t.common,
+        TaskDefinition::Fork(t) => &t.common,
+        TaskDefinition::Listen(t) => &t.common,
+        TaskDefinition::Raise(t) => &t.common,
+        TaskDefinition::Run(t) => &t.common,
+        TaskDefinition::Set(t) => &t.common,
+        TaskDefinition::Switch(t) => &t.common,
+        TaskDefinition::Try(t) => &t.common,
+        TaskDefinition::Wait(t) => &t.common,
     }
 }
 
-impl TryFrom<&TaskDefinition> for crate::nodes::asyncapi::HTTPNode {
-    type Error = String;
+/// Returns the fields shared by every task definition, for changing them.
+pub(crate) fn task_fields_mut(task: &mut TaskDefinition) -> &mut TaskDefinitionFields {
+    match task {
+        TaskDefinition::Call(t) => &mut t.common,
+        TaskDefinition::Do(t) => &mut t.common,
+        TaskDefinition::Emit(t) => &mut t.common,
+        TaskDefinition::For(t) => &mut t.common,
+        TaskDefinition::Fork(t) => &mut t.common,
+        TaskDefinition::Listen(t) => &mut t.common,
+        TaskDefinition::Raise(t) => &mut t.common,
+        TaskDefinition::Run(t) => &mut t.common,
+        TaskDefinition::Set(t) => &mut t.common,
+        TaskDefinition::Switch(t) => &mut t.common,
+        TaskDefinition::Try(t) => &mut t.common,
+        TaskDefinition::Wait(t) => &mut t.common,
+    }
+}
 
-    fn try_from(task: &TaskDefinition) -> std::result::Result<Self, Self::Error> {
-        Self::try_from_task(task)
+impl std::fmt::Debug for DoNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DoNode")
+            .field("tasks", &self.tasks.iter().map(|(name, _)| name).collect::<Vec<_>>())
+            .finish()
     }
 }
 
-impl TryFrom<&CallTaskDefinition> for crate::nodes::asyncapi::HTTPNode {
-    type Error = String;
+impl TryFrom<&DoTaskDefinition> for DoNode {
+    type Error = StepError;
 
-    fn try_from(call: &CallTaskDefinition) -> std::result::Result<Self, Self::Error> {
-        Self::try_from_http(call)
+    fn try_from(def: &DoTaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_definition(def)
     }
 }
 
 #[async_trait::async_trait]
-impl Task for crate::nodes::asyncapi::HTTPNode {
+impl Task for DoNode {
     type Input = Value;
     type Output = Value;
 
     async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
-        let req = self.build_request(&input);
-        ctx.http_client.execute(req).await.unwrap();
-
-        // TODO: fix me
-        Ok(Value::Null)
+        let mut output = input;
+        let mut index = 0;
+        while let Some((name, task)) = self.tasks.get(index) {
+            if ctx.cancellation.is_cancelled() {
+                return Err(cancelled_error(&format!("task '{name}'")));
+            }
+            let switched = matches!(self.flow[index], Flow::Switch(_)).then(SwitchOutcome::default);
+            let task_ctx = match &switched {
+                Some(outcome) => &WorkflowContext {
+                    switched: Some(outcome.clone()),
+                    ..ctx.clone()
+                },
+                None => ctx,
+            };
+            output = self
+                .run_task(task_ctx, index, name, task, output)
+                .await
+                .map_err(|e| e.map_message(|e| format!("task '{name}' failed: {e}")))?;
+            // A nested block ended the workflow.
+            if ctx.ended.load(Ordering::Relaxed) {
+                break;
+            }
+            let directive = match (&self.flow[index], switched) {
+                (Flow::Then(then), _) => then.clone(),
+                // A switch that was skipped, or mocked, picked nothing.
+                (Flow::Switch(_), outcome) => outcome.and_then(|o| o.take()).unwrap_or(FlowDirective::Continue),
+            };
+            index = match directive {
+                FlowDirective::Continue => index + 1,
+                FlowDirective::Exit => break,
+                FlowDirective::End => {
+                    ctx.ended.store(true, Ordering::Relaxed);
+                    break;
+                }
+                // Targets are checked when the node is built.
+                FlowDirective::Goto(target) => self.position(&target).unwrap_or(self.tasks.len()),
+            };
+        }
+        Ok(output)
     }
 }
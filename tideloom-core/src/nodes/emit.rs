@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use serverless_workflow_core::models::task::{EmitTaskDefinition, TaskDefinition};
+
+use crate::messaging::{CLOUD_EVENTS_SPEC_VERSION, CloudEvent};
+use crate::runtime::expr::resolve_with;
+use crate::runtime::secrets::redact;
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext};
+
+/// `emit` task: builds a CloudEvent from `emit.event.with` and publishes it to the context's event sink.
+///
+/// The task passes its input through unchanged.
+#[derive(Debug, Clone)]
+pub struct EmitNode {
+    attributes: HashMap<String, Value>,
+}
+
+impl EmitNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Emit(emit) => Self::try_from_emit(emit),
+            _ => Err("EmitNode expects an `emit` task definition".into()),
+        }
+    }
+
+    pub fn try_from_emit(emit: &EmitTaskDefinition) -> StepResult<Self> {
+        let attributes = emit.emit.event.with.clone();
+        for required in ["source", "type"] {
+            if !attributes.contains_key(required) {
+                return Err(format!("emitted event requires a `{required}` attribute").into());
+            }
+        }
+        Ok(Self { attributes })
+    }
+
+    /// Resolves the attribute expressions against the task input and fills in `id` and `time` when missing.
+    ///
+    /// Values of `$secrets` are redacted from the resolved attributes.
+    pub fn build_event(&self, input: &Value, vars: &Map<String, Value>) -> StepResult<CloudEvent> {
+        let no_secrets = Map::new();
+        let secrets = vars.get("$secrets").and_then(Value::as_object).unwrap_or(&no_secrets);
+        let mut attributes = Map::new();
+        for (name, value) in &self.attributes {
+            attributes.insert(name.clone(), redact(&resolve_with(value, input, vars)?, secrets));
+        }
+        attributes
+            .entry("specversion")
+            .or_insert_with(|| Value::String(CLOUD_EVENTS_SPEC_VERSION.to_string()));
+        attributes
+            .entry("id")
+            .or_insert_with(|| Value::String(uuid::Uuid::new_v4().to_string()));
+        attributes
+            .entry("time")
+            .or_insert_with(|| Value::String(chrono::Utc::now().to_rfc3339()));
+        if attributes.contains_key("data") {
+            attributes
+                .entry("datacontenttype")
+                .or_insert_with(|| Value::String("application/json".to_string()));
+        }
+
+        serde_json::from_value(Value::Object(attributes)).map_err(|e| format!("invalid emitted event: {e}").into())
+    }
+}
+
+impl TryFrom<&TaskDefinition> for EmitNode {
+    type Error = StepError;
+
+    fn try_from(task: &TaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_task(task)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for EmitNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let sink = ctx
+            .event_sink
+            .as_ref()
+            .ok_or_else(|| "emit task requires an event sink in the workflow context".to_string())?;
+        sink.publish(self.build_event(&input, &ctx.vars())?).await?;
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+    use crate::messaging::{EventSource, InMemoryEventBus};
+
+    fn emit_node(yaml: &str) -> StepResult<EmitNode> {
+        let task: TaskDefinition = serde_yaml::from_str(yaml).expect("invalid yaml");
+        EmitNode::try_from_task(&task)
+    }
+
+    #[tokio::test]
+    async fn emit_publishes_cloud_event() {
+        let node = emit_node(
+            r#"
+emit:
+  event:
+    with:
+      source: https://petstore.com
+      type: com.petstore.order.placed.v1
+      subject: ${ .order.id }
+      data:
+        client: ${ .client }
+        items: ${ .order.items }
+"#,
+        )
+        .unwrap();
+        let bus = Arc::new(InMemoryEventBus::default());
+        let mut events = bus.subscribe().await.unwrap();
+        let ctx = WorkflowContext::default().with_event_sink(bus.clone());
+        let input = json!({ "client": "alice", "order": { "id": "o-1", "items": [1, 2] } });
+
+        let output = node.execute(&ctx, input.clone()).await.unwrap();
+        let event = events.next().await.unwrap();
+
+        assert_eq!(output, input);
+        assert_eq!(event.specversion, "1.0");
+        assert_eq!(event.type_, "com.petstore.order.placed.v1");
+        assert_eq!(event.subject.as_deref(), Some("o-1"));
+        assert_eq!(event.data, Some(json!({ "client": "alice", "items": [1, 2] })));
+        assert_eq!(event.datacontenttype.as_deref(), Some("application/json"));
+        assert!(!event.id.is_empty());
+        assert!(event.time.is_some());
+    }
+
+    #[test]
+    fn emit_requires_type_and_source() {
+        let err = emit_node("emit:\n  event:\n    with:\n      source: test\n").unwrap_err();
+        assert!(err.contains("type"), "{err}");
+    }
+}
@@ -0,0 +1,228 @@
+use futures::StreamExt;
+use serde_json::{Value, json};
+use serverless_workflow_core::models::task::TaskDefinition;
+
+use crate::extensions::{FAN_OUT, extension};
+use crate::nodes::doing::{BoxedTask, task_fields};
+use crate::nodes::forking::OnError;
+use crate::runtime::expr::{parse_path, select_with};
+use crate::runtime::{StepResult, Task, WorkflowContext};
+
+/// Starts one child instance of a `run: workflow` task per item of the collection selected by
+/// `metadata.tideloom.fanOut.in`, at most `concurrency` at a time, and outputs their outputs in item order.
+///
+/// Each child sees its item as `$item`, or the variable named by `each`, so `run.workflow.input` can pick it,
+/// e.g. `${ $item }`. With `onError: fail`, the default, the first failed child fails the task and cancels
+/// the others. With `onError: continue`, a failed child yields `{ "error": ... }` in its place, unless more than
+/// `maxFailures` children failed.
+pub struct FanOutNode {
+    in_: String,
+    each: String,
+    concurrency: usize,
+    on_error: OnError,
+    max_failures: Option<usize>,
+    child: BoxedTask,
+}
+
+impl FanOutNode {
+    /// Wraps `child` when `task` declares `metadata.tideloom.fanOut`, and returns it unchanged otherwise.
+    pub fn wrap(child: BoxedTask, task: &TaskDefinition) -> StepResult<BoxedTask> {
+        let Some(fan_out) = extension(task_fields(task), FAN_OUT) else {
+            return Ok(child);
+        };
+        if !matches!(task, TaskDefinition::Run(run) if run.run.workflow.is_some()) {
+            return Err("`fanOut` is only supported on `run: workflow` tasks".into());
+        }
+        let in_ = fan_out
+            .get("in")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "`fanOut` requires an `in` path".to_string())?;
+        parse_path(in_).map_err(|e| format!("fanOut.in: {e}"))?;
+        let each = fan_out.get("each").and_then(Value::as_str).unwrap_or("item");
+        let each = format!("${}", each.strip_prefix('$').unwrap_or(each));
+        if !matches!(parse_path(&each).as_deref(), Ok([_])) {
+            return Err(format!("invalid fanOut variable name '{each}'").into());
+        }
+        let count = |field: &str| match fan_out.get(field) {
+            None => Ok(None),
+            Some(value) => value
+                .as_u64()
+                .map(|n| Some(n as usize))
+                .ok_or_else(|| format!("fanOut.{field} must be a non-negative integer")),
+        };
+        let on_error = match fan_out.get("onError").and_then(Value::as_str) {
+            None | Some("fail") => OnError::Fail,
+            Some("continue") => OnError::Continue,
+            Some(other) => return Err(format!("unsupported fanOut onError mode '{other}'").into()),
+        };
+        let max_failures = count("maxFailures")?;
+        if max_failures.is_some() && on_error == OnError::Fail {
+            return Err("fanOut.maxFailures requires `onError: continue`".into());
+        }
+        Ok(Box::new(Self {
+            in_: in_.to_string(),
+            each,
+            concurrency: count("concurrency")?.unwrap_or(usize::MAX).max(1),
+            on_error,
+            max_failures,
+            child,
+        }))
+    }
+}
+
+impl std::fmt::Debug for FanOutNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FanOutNode")
+            .field("in", &self.in_)
+            .field("each", &self.each)
+            .field("concurrency", &self.concurrency)
+            .field("on_error", &self.on_error)
+            .field("max_failures", &self.max_failures)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for FanOutNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let items = match select_with(&input, &ctx.vars(), &self.in_) {
+            Some(Value::Array(items)) => items,
+            Some(Value::Null) | None => Vec::new(),
+            Some(other) => return Err(format!("fanOut.in must select an array, got {other}").into()),
+        };
+        let mut children = futures::stream::iter(items.into_iter().enumerate())
+            .map(|(index, item)| {
+                let mut ctx = ctx.clone();
+                ctx.scope.insert(self.each.clone(), item);
+                let input = input.clone();
+                async move { (index, self.child.execute(&ctx, input).await) }
+            })
+            .buffer_unordered(self.concurrency);
+
+        let mut outputs = Vec::new();
+        let mut failures = 0;
+        // Returning early drops the children still running, which cancels them.
+        while let Some((index, result)) = children.next().await {
+            let output = match (result, self.on_error) {
+                (Ok(output), _) => output,
+                (Err(e), OnError::Fail) => return Err(e.map_message(|e| format!("fan-out child {index} failed: {e}"))),
+                (Err(e), OnError::Continue) => {
+                    failures += 1;
+                    if let Some(max) = self.max_failures
+                        && failures > max
+                    {
+                        return Err(e.map_message(|e| {
+                            format!("fan-out failures exceeded maxFailures ({max}); child {index} failed: {e}")
+                        }));
+                    }
+                    json!({ "error": e.message() })
+                }
+            };
+            outputs.push((index, output));
+        }
+        outputs.sort_by_key(|(index, _)| *index);
+        Ok(Value::Array(outputs.into_iter().map(|(_, output)| output).collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::runtime::WorkflowContext;
+
+    fn context() -> WorkflowContext {
+        let child = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: ship
+  version: '1.0.0'
+do:
+- ship:
+    run:
+      shell:
+        command: sh
+        arguments: ['-c', 'test "$0" != bad && echo "$0"', '${ .id }']
+"#,
+        )
+        .unwrap();
+        WorkflowContext::default().with_workflow(child.definition().clone())
+    }
+
+    fn parent(fan_out: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: ship-all
+  version: '1.0.0'
+do:
+- shipAll:
+    metadata:
+      tideloom:
+        fanOut: {fan_out}
+    run:
+      workflow:
+        namespace: test
+        name: ship
+        version: '1.0.0'
+        input:
+          id: ${{ $order.id }}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn children_run_per_item_and_outputs_keep_item_order() {
+        let input = json!({ "orders": [{ "id": "a" }, { "id": "bad" }, { "id": "c" }] });
+        let collect = parent("{ in: .orders, each: order, concurrency: 2, onError: continue }");
+
+        let output = collect.run(&context(), input.clone()).await.unwrap();
+        assert_eq!(output[0], json!("a"));
+        assert!(output[1]["error"].as_str().unwrap().contains("exited with code 1"), "{output}");
+        assert_eq!(output[2], json!("c"));
+
+        let fail_fast = parent("{ in: .orders, each: order }");
+        let err = fail_fast.run(&context(), input.clone()).await.unwrap_err();
+        assert!(err.contains("fan-out child 1 failed"), "{err}");
+
+        let threshold = parent("{ in: .orders, each: order, onError: continue, maxFailures: 0 }");
+        let err = threshold.run(&context(), input).await.unwrap_err();
+        assert!(err.contains("exceeded maxFailures (0)"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn fan_out_is_limited_to_workflow_runs() {
+        let err = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: misuse
+  version: '1.0.0'
+do:
+- each:
+    metadata:
+      tideloom:
+        fanOut: { in: .items }
+    run:
+      shell:
+        command: 'true'
+"#,
+        )
+        .unwrap()
+        .run(&WorkflowContext::default(), json!({}))
+        .await
+        .unwrap_err();
+        assert!(err.contains("only supported on `run: workflow` tasks"), "{err}");
+        assert!(parent("{ in: .orders, maxFailures: 1 }").run(&context(), json!({})).await.is_err());
+    }
+}
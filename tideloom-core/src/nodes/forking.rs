@@ -0,0 +1,398 @@
+use std::collections::BTreeSet;
+
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use serde_json::{Map, Value, json};
+use serverless_workflow_core::models::task::{ForkTaskDefinition, TaskDefinition};
+
+use crate::extensions::{ON_ERROR, OUTPUT_BUDGET, extension};
+use crate::nodes::doing::{BoxedTask, TaskSite, build_task};
+use crate::runtime::expr::{is_expression, parse_path};
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext};
+
+/// What a fork does when one of its branches fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Cancels the other branches and fails the fork with the branch error.
+    #[default]
+    Fail,
+    /// Lets the other branches complete and reports the failure in place of the branch output.
+    Continue,
+}
+
+/// `fork` task: runs its branches concurrently, each with the fork's input.
+///
+/// With `fork.compete`, the first branch to succeed wins: its output is the fork output and the other
+/// branches are cancelled. The fork fails only when every branch failed.
+///
+/// Otherwise the output maps each branch name to its output. With `fork.onError: continue`, or
+/// `metadata.tideloom.onError: continue`, a failed branch maps
+/// to `{ "error": "<message>" }` instead of failing the fork. A `try` task inside a branch handles that
+/// branch's errors before the fork sees them.
+///
+/// `metadata.tideloom.outputBudget` bounds the bytes of branch outputs, serialized as JSON, that the fork keeps
+/// in memory while its branches complete. A branch output that would cross the budget is written to a file in
+/// the system's temporary directory, and the fork outputs `{ "path", "size", "contentType" }` for that branch,
+/// as for a spooled HTTP body. When the fork has an `output.as`, the spilled outputs it reads are loaded back
+/// for it and every file is deleted; otherwise the files are left to the host.
+pub struct ForkNode {
+    branches: Vec<(String, BoxedTask)>,
+    compete: bool,
+    on_error: OnError,
+    budget: Option<u64>,
+    rehydrate: Rehydrate,
+}
+
+/// Which spilled branch outputs are loaded back before the fork's `output.as` runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rehydrate {
+    /// The fork has no `output.as`: spilled outputs stay files.
+    Nothing,
+    All,
+    Branches(BTreeSet<String>),
+}
+
+impl Rehydrate {
+    /// What `as_`, the fork's `output.as`, reads of the fork output.
+    fn reading(as_: Option<&Value>) -> Self {
+        fn collect(value: &Value, branches: &mut BTreeSet<String>) -> bool {
+            match value {
+                Value::String(s) if is_expression(s) => match parse_path(s).as_deref() {
+                    Ok([]) => false,
+                    Ok([first, ..]) if !first.starts_with('$') => {
+                        branches.insert(first.clone());
+                        true
+                    }
+                    _ => true,
+                },
+                Value::Array(items) => items.iter().all(|item| collect(item, branches)),
+                Value::Object(map) => map.values().all(|value| collect(value, branches)),
+                _ => true,
+            }
+        }
+        let Some(as_) = as_ else {
+            return Self::Nothing;
+        };
+        let mut branches = BTreeSet::new();
+        if collect(as_, &mut branches) { Self::Branches(branches) } else { Self::All }
+    }
+
+    fn wants(&self, branch: &str) -> bool {
+        match self {
+            Self::Nothing => false,
+            Self::All => true,
+            Self::Branches(branches) => branches.contains(branch),
+        }
+    }
+}
+
+impl ForkNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Fork(def) => Self::try_from_fork(def),
+            _ => Err("ForkNode expects a `fork` task definition".into()),
+        }
+    }
+
+    pub fn try_from_fork(def: &ForkTaskDefinition) -> StepResult<Self> {
+        Self::build(def, &TaskSite::new(""))
+    }
+
+    /// Builds the node of the `fork` task at `site`.
+    pub(crate) fn build(def: &ForkTaskDefinition, site: &TaskSite) -> StepResult<Self> {
+        let on_error = match extension(&def.common, ON_ERROR) {
+            None => OnError::Fail,
+            Some(Value::String(mode)) if mode == "fail" => OnError::Fail,
+            Some(Value::String(mode)) if mode == "continue" => OnError::Continue,
+            Some(other) => return Err(format!("unsupported fork onError mode {other}").into()),
+        };
+        let mut branches = Vec::new();
+        for (index, entry) in def.fork.branches.entries.iter().enumerate() {
+            for (name, task) in entry {
+                branches.push((name.clone(), build_task(task, &site.join(format!("fork/branches/{index}/{name}")))?));
+            }
+        }
+        if def.fork.compete && branches.is_empty() {
+            return Err("a competing fork requires at least one branch".into());
+        }
+        let budget = extension(&def.common, OUTPUT_BUDGET)
+            .map(|budget| budget.as_u64().ok_or("fork outputBudget must be a number of bytes"))
+            .transpose()?;
+        Ok(Self {
+            branches,
+            compete: def.fork.compete,
+            on_error,
+            budget,
+            rehydrate: Rehydrate::reading(def.common.output.as_ref().and_then(|output| output.as_.as_ref())),
+        })
+    }
+}
+
+impl std::fmt::Debug for ForkNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ForkNode")
+            .field("branches", &self.branches.iter().map(|(name, _)| name).collect::<Vec<_>>())
+            .field("compete", &self.compete)
+            .field("on_error", &self.on_error)
+            .field("budget", &self.budget)
+            .finish()
+    }
+}
+
+impl TryFrom<&ForkTaskDefinition> for ForkNode {
+    type Error = StepError;
+
+    fn try_from(def: &ForkTaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_fork(def)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for ForkNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let branches = self.branches.iter().map(|(name, task)| {
+            let input = input.clone();
+            async move {
+                let result = task
+                    .execute(ctx, input)
+                    .await
+                    .map_err(|e| e.map_message(|e| format!("fork branch '{name}' failed: {e}")));
+                (name.clone(), result)
+            }
+        });
+        if self.compete {
+            // Dropping the remaining branches once one succeeds cancels them.
+            let ((_, output), _) =
+                futures::future::select_ok(branches.map(|branch| {
+                    Box::pin(async move {
+                        let (name, result) = branch.await;
+                        result.map(|output| (name, output))
+                    })
+                }))
+                .await?;
+            return Ok(output);
+        }
+        // Outputs are taken as branches complete, so that the budget bounds what is held at once. Dropping the
+        // pending branches once one fails cancels them.
+        let mut branches: FuturesUnordered<_> = branches.collect();
+        let mut spilled = Vec::new();
+        let joined = async {
+            let mut outputs = Map::new();
+            let mut kept = 0;
+            while let Some((name, result)) = branches.next().await {
+                let output = match (result, self.on_error) {
+                    (Ok(output), _) => output,
+                    (Err(e), OnError::Fail) => return Err(e),
+                    (Err(e), OnError::Continue) => json!({ "error": e.message() }),
+                };
+                let Some(budget) = self.budget else {
+                    outputs.insert(name, output);
+                    continue;
+                };
+                let bytes = serde_json::to_vec(&output).map_err(|e| format!("fork branch '{name}': {e}"))?;
+                if kept + bytes.len() as u64 <= budget {
+                    kept += bytes.len() as u64;
+                    outputs.insert(name, output);
+                    continue;
+                }
+                drop(output);
+                let path = std::env::temp_dir().join(format!("tideloom-fork-{}.json", uuid::Uuid::new_v4()));
+                tokio::fs::write(&path, &bytes)
+                    .await
+                    .map_err(|e| format!("fork branch '{name}': failed to spill output: {e}"))?;
+                let file = json!({
+                    "path": path.display().to_string(),
+                    "size": bytes.len(),
+                    "contentType": "application/json",
+                });
+                spilled.push((name.clone(), path));
+                outputs.insert(name, file);
+            }
+            if self.rehydrate != Rehydrate::Nothing {
+                for (name, path) in &spilled {
+                    if self.rehydrate.wants(name) {
+                        let bytes = tokio::fs::read(path)
+                            .await
+                            .map_err(|e| format!("fork branch '{name}': failed to reload output: {e}"))?;
+                        let output =
+                            serde_json::from_slice(&bytes).map_err(|e| format!("fork branch '{name}': {e}"))?;
+                        outputs.insert(name.clone(), output);
+                    }
+                }
+            }
+            Ok(outputs)
+        }
+        .await;
+        if joined.is_err() || self.rehydrate != Rehydrate::Nothing {
+            for (_, path) in &spilled {
+                // A file that cannot be deleted is left behind.
+                let _ = tokio::fs::remove_file(path).await;
+            }
+        }
+        joined.map(Value::Object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::nodes::testing::value_context;
+    use crate::runtime::WorkflowContext;
+
+    #[tokio::test]
+    async fn competing_branches_return_the_first_success() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: race
+  version: '1.0.0'
+do:
+  - race:
+      fork:
+        compete: true
+        branches:
+          - slow:
+              run:
+                shell:
+                  command: sh
+                  arguments: ['-c', 'sleep 1; echo slow']
+          - broken:
+              run:
+                shell:
+                  command: 'false'
+          - fast:
+              run:
+                shell:
+                  command: echo
+                  arguments: [fast]
+"#,
+        )
+        .unwrap();
+        let started = std::time::Instant::now();
+
+        let output = workflow.run(&WorkflowContext::default(), json!({})).await.unwrap();
+
+        assert_eq!(output, json!("fast"));
+        assert!(started.elapsed() < std::time::Duration::from_millis(900));
+    }
+
+    fn workflow(on_error: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: fork
+  version: '1.0.0'
+do:
+  - both:
+      fork:
+        {on_error}
+        branches:
+          - fast:
+              run:
+                shell:
+                  command: echo
+                  arguments: ["${{ .name }}"]
+          - broken:
+              run:
+                shell:
+                  command: sh
+                  arguments: ['-c', 'echo boom >&2; exit 1']
+          - guarded:
+              try:
+                - fail:
+                    run:
+                      shell:
+                        command: 'false'
+              catch:
+                do:
+                  - recover:
+                      run:
+                        shell:
+                          command: echo
+                          arguments: [recovered]
+"#
+        ))
+        .unwrap()
+    }
+
+    fn budgeted(output: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: budget
+  version: '1.0.0'
+do:
+  - both:
+      metadata:
+        tideloom:
+          outputBudget: 40
+      {output}
+      fork:
+        branches:
+          - small:
+              call: value
+              with:
+                name: ${{ .name }}
+          - large:
+              call: value
+              with:
+                text: ${{ .text }}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn outputs_beyond_the_budget_are_spilled() {
+        let input = json!({ "name": "ada", "text": "x".repeat(100) });
+
+        let output = budgeted("").run(&value_context(), input.clone()).await.unwrap();
+
+        assert_eq!(output["small"], json!({ "name": "ada" }));
+        assert_eq!(output["large"]["size"], json!(111));
+        let path = output["large"]["path"].as_str().unwrap();
+        let spilled: serde_json::Value = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(spilled, json!({ "text": input["text"] }));
+    }
+
+    #[tokio::test]
+    async fn spilled_outputs_read_by_output_as_are_reloaded() {
+        let input = json!({ "name": "ada", "text": "x".repeat(100) });
+        let workflow = budgeted("output: { as: { name: '${ .small.name }', text: '${ .large.text }' } }");
+
+        let output = workflow.run(&value_context(), input.clone()).await.unwrap();
+
+        assert_eq!(output, json!({ "name": "ada", "text": input["text"] }));
+    }
+
+    #[tokio::test]
+    async fn failing_branch_fails_the_fork_by_default() {
+        let err = workflow("").run(&WorkflowContext::default(), json!({ "name": "ada" })).await.unwrap_err();
+
+        assert!(err.contains("fork branch 'broken' failed: process exited with code 1: boom"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn continue_collects_partial_results_and_errors() {
+        let output = workflow("onError: continue")
+            .run(&WorkflowContext::default(), json!({ "name": "ada" }))
+            .await
+            .unwrap();
+
+        assert_eq!(output["fast"], json!("ada"));
+        assert_eq!(output["guarded"], json!("recovered"));
+        assert_eq!(output["broken"]["error"], json!("fork branch 'broken' failed: process exited with code 1: boom"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::{Map, Value};
+use serverless_workflow_core::models::task::{CallTaskDefinition, TaskDefinition};
+
+use crate::extensions::{DEFAULTS, extension};
+use crate::nodes::doing::{BoxedTask, TaskSite, build_task, task_fields};
+use crate::runtime::expr::resolve_with;
+use crate::runtime::{SharedTask, StepError, StepResult, Task, WorkflowContext};
+
+/// A custom function declared in a workflow's `use.functions`: a task, such as an HTTP or OpenAPI call, run
+/// with the arguments of each call as its input.
+///
+/// Arguments missing from a call take the values of the function's `metadata.tideloom.defaults`, resolved against
+/// the call's arguments when the function runs.
+pub struct FunctionNode {
+    defaults: Map<String, Value>,
+    task: BoxedTask,
+}
+
+impl FunctionNode {
+    pub fn try_from_task(name: &str, task: &TaskDefinition) -> StepResult<Self> {
+        let defaults = match extension(task_fields(task), DEFAULTS) {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(defaults)) => defaults.clone(),
+            Some(_) => return Err(format!("function '{name}': `defaults` must be an object").into()),
+        };
+        Ok(Self {
+            defaults,
+            task: build_task(task, &TaskSite::new(format!("/use/functions/{name}")))
+                .map_err(|e| format!("function '{name}': {e}"))?,
+        })
+    }
+
+    /// Compiles the `use.functions` of a workflow, by name.
+    pub fn registry<'a>(
+        functions: impl IntoIterator<Item = (&'a String, &'a TaskDefinition)>,
+    ) -> StepResult<HashMap<String, SharedTask>> {
+        functions
+            .into_iter()
+            .map(|(name, task)| Ok((name.clone(), Arc::new(Self::try_from_task(name, task)?) as SharedTask)))
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for FunctionNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FunctionNode")
+            .field("defaults", &self.defaults)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for FunctionNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let Value::Object(mut arguments) = input else {
+            return self.task.execute(ctx, input).await;
+        };
+        let args = Value::Object(arguments.clone());
+        for (name, default) in &self.defaults {
+            if !arguments.contains_key(name) {
+                let value = resolve_with(default, &args, &ctx.vars())
+                    .map_err(|e| format!("function default '{name}': {e}"))?;
+                arguments.insert(name.clone(), value);
+            }
+        }
+        self.task.execute(ctx, Value::Object(arguments)).await
+    }
+}
+
+/// `call` task naming a custom function of the running workflow's `use.functions`, or else a task of the
+/// context's [`TaskRegistry`](crate::runtime::registry::TaskRegistry).
+///
+/// The function runs with the call's `with` arguments, resolved against the task input, or with the task input
+/// itself when the call has no arguments.
+#[derive(Debug, Clone)]
+pub struct FunctionCallNode {
+    name: String,
+    arguments: Option<Value>,
+}
+
+impl FunctionCallNode {
+    pub fn try_from_call(call: &CallTaskDefinition) -> StepResult<Self> {
+        Ok(Self {
+            name: call.call.clone(),
+            arguments: call
+                .with
+                .as_ref()
+                .map(|with| serde_json::to_value(with).map_err(|e| format!("invalid call arguments: {e}")))
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<&CallTaskDefinition> for FunctionCallNode {
+    type Error = StepError;
+
+    fn try_from(call: &CallTaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_call(call)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for FunctionCallNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let arguments = match &self.arguments {
+            Some(arguments) => resolve_with(arguments, &input, &ctx.vars())?,
+            None => input,
+        };
+        let result = match (ctx.functions.get(&self.name), ctx.tasks.get(&self.name)) {
+            (Some(function), _) => function.execute(ctx, arguments).await,
+            (None, Some(custom)) => custom.execute(ctx, arguments).await,
+            (None, None) => {
+                return Err(format!(
+                    "function '{}' is neither declared in `use.functions` nor registered in the task registry",
+                    self.name
+                ).into());
+            }
+        };
+        result.map_err(|e| e.map_message(|e| format!("function '{}' failed: {e}", self.name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::nodes::testing::serve;
+    use crate::runtime::WorkflowContext;
+
+    #[tokio::test]
+    async fn calls_run_declared_functions_with_defaults() {
+        let server = serve(vec![
+            ("200 OK", "application/json", r#"{"sent":1}"#.to_string()),
+            ("200 OK", "application/json", r#"{"sent":2}"#.to_string()),
+        ]);
+        let workflow = Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: notify
+  version: '1.0.0'
+use:
+  functions:
+    notify:
+      call: http
+      metadata:
+        tideloom:
+          defaults:
+            channel: email
+      with:
+        method: post
+        endpoint: {}/notify/{{channel}}/{{to}}
+do:
+- welcome:
+    call: notify
+    with:
+      to: ${{ .user }}
+- alert:
+    call: notify
+    with:
+      to: ops
+      channel: sms
+"#,
+            server.url
+        ))
+        .unwrap();
+
+        let output = workflow.run(&WorkflowContext::default(), json!({ "user": "ada" })).await.unwrap();
+        assert_eq!(output, json!({ "sent": 2 }));
+        assert!(server.next_request().starts_with("POST /notify/email/ada "));
+        assert!(server.next_request().starts_with("POST /notify/sms/ops "));
+    }
+}
@@ -0,0 +1,748 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::header::HeaderMap;
+use serde_json::{Map, Value};
+use serverless_workflow_core::models::task::{CallTaskDefinition, TaskDefinition};
+use tokio::io::AsyncWriteExt;
+
+use crate::runtime::auth::AuthenticationRef;
+use crate::runtime::expr::{resolve_with, value_to_string};
+use crate::runtime::http_client::HttpBodyLimits;
+use crate::runtime::interceptor::HttpResponse;
+use crate::runtime::retry::RetryHint;
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext};
+
+/// Shape of the value produced by an HTTP call, as selected by `with.output`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HttpOutputFormat {
+    /// Base64 encoded response body.
+    Raw,
+    /// Response body decoded according to its content type: JSON as a value, text as a string, anything else
+    /// base64 encoded.
+    #[default]
+    Content,
+    /// Full response: request summary, status code, headers and content.
+    Response,
+}
+
+impl FromStr for HttpOutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "content" => Ok(Self::Content),
+            "response" => Ok(Self::Response),
+            other => Err(format!("unsupported http output format '{other}'")),
+        }
+    }
+}
+
+/// Returns whether an HTTP status is treated as a successful call.
+///
+/// 2xx is always accepted; 3xx only when `redirect` is set, as described by the DSL.
+pub fn is_status_allowed(status: reqwest::StatusCode, redirect: bool) -> bool {
+    status.is_success() || (redirect && status.is_redirection())
+}
+
+/// A status accepted by `with.acceptStatus`: a code such as `404`, or a class such as `"4xx"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptedStatus {
+    Code(u16),
+    /// The first digit of the accepted codes.
+    Class(u16),
+}
+
+impl AcceptedStatus {
+    fn try_from_value(value: &Value) -> StepResult<Self> {
+        let code = value.as_u64().and_then(|code| u16::try_from(code).ok());
+        let class = value.as_str().and_then(|class| class.to_ascii_lowercase().strip_suffix("xx")?.parse().ok());
+        match (code, class) {
+            (Some(code), _) if (100..600).contains(&code) => Ok(Self::Code(code)),
+            (_, Some(class)) if (1..6).contains(&class) => Ok(Self::Class(class)),
+            _ => Err(format!("invalid acceptStatus entry {value}").into()),
+        }
+    }
+
+    fn matches(&self, status: reqwest::StatusCode) -> bool {
+        match self {
+            Self::Code(code) => status.as_u16() == *code,
+            Self::Class(class) => status.as_u16() / 100 == *class,
+        }
+    }
+}
+
+/// Turns a rejected response into the task's error, with its body: 429 and 503 are retried no earlier than
+/// their `Retry-After` header asks, other 4xx statuses are not retried, and 5xx statuses are retried as usual.
+fn status_error(url: &reqwest::Url, status: reqwest::StatusCode, headers: &HeaderMap, body: String) -> StepError {
+    let error = StepError::new(format!("http call to '{url}' failed with status {status}: {body}"));
+    match status_hint(status, headers) {
+        Some(hint) => error.with_hint(hint),
+        None => error,
+    }
+}
+
+fn status_hint(status: reqwest::StatusCode, headers: &HeaderMap) -> Option<RetryHint> {
+    match status.as_u16() {
+        429 | 503 => retry_after_header(headers).map(RetryHint::After),
+        400..=499 => Some(RetryHint::NotRetryable),
+        _ => None,
+    }
+}
+
+/// Reads a `Retry-After` header, given either in seconds or as an HTTP date.
+fn retry_after_header(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+/// `call: http` task; also performs the requests of `call: openapi` tasks.
+///
+/// The endpoint, headers, query and body may hold expressions, evaluated against the task input. Values of the
+/// input also fill the endpoint's `{name}` placeholders, percent-encoded.
+///
+/// Responses are accepted per [`is_status_allowed`], or per `with.acceptStatus` when set; see
+/// [`status_error`] for how other statuses fail the task.
+#[derive(Debug, Clone)]
+pub struct HTTPNode {
+    pub(crate) endpoint: String,
+    pub(crate) method: reqwest::Method,
+    pub(crate) headers: HashMap<String, String>,
+    pub(crate) query: HashMap<String, String>,
+    pub(crate) body: Option<Value>,
+    pub(crate) output: HttpOutputFormat,
+    pub(crate) redirect: bool,
+    /// Statuses accepted instead of the default ones, from `with.acceptStatus`.
+    pub(crate) accept_status: Option<Vec<AcceptedStatus>>,
+    pub(crate) authentication: Option<AuthenticationRef>,
+}
+
+impl HTTPNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Call(call) => match call.call.to_lowercase().as_str() {
+                "http" => Self::try_from_http(call),
+                _ => Err(format!("expected call 'http', got '{}'", call.call).into()),
+            },
+            _ => Err("HTTPNode expects a `call` task definition".into()),
+        }
+    }
+
+    pub fn try_from_http(call: &CallTaskDefinition) -> StepResult<Self> {
+        let with = call
+            .with
+            .as_ref()
+            .ok_or_else(|| "http call requires a `with` block".to_string())?;
+
+        let endpoint_auth = with.get("endpoint").and_then(|e| e.get("authentication"));
+        let endpoint = match with.get("endpoint") {
+            Some(Value::String(uri)) => uri.clone(),
+            Some(Value::Object(endpoint)) => endpoint
+                .get("uri")
+                .and_then(Value::as_str)
+                .ok_or_else(|| "http call endpoint requires a `uri`".to_string())?
+                .to_string(),
+            _ => return Err("http call requires an `endpoint`".into()),
+        };
+        let method = with
+            .get("method")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "http call requires a `method`".to_string())?;
+        let method = reqwest::Method::from_str(&method.to_uppercase())
+            .map_err(|e| format!("invalid http method '{method}': {e}"))?;
+        let output = match with.get("output").and_then(Value::as_str) {
+            Some(output) => output.parse()?,
+            None => HttpOutputFormat::default(),
+        };
+
+        Ok(HTTPNode {
+            endpoint,
+            method,
+            headers: string_map(with.get("headers"), "headers")?,
+            query: string_map(with.get("query"), "query")?,
+            body: with.get("body").cloned(),
+            output,
+            redirect: with.get("redirect").and_then(Value::as_bool).unwrap_or(false),
+            accept_status: match with.get("acceptStatus") {
+                None | Some(Value::Null) => None,
+                Some(Value::Array(statuses)) => {
+                    Some(statuses.iter().map(AcceptedStatus::try_from_value).collect::<StepResult<_>>()?)
+                }
+                Some(other) => Some(vec![AcceptedStatus::try_from_value(other)?]),
+            },
+            authentication: with
+                .get("authentication")
+                .or(endpoint_auth)
+                .map(AuthenticationRef::try_from_value)
+                .transpose()?,
+        })
+    }
+
+    /// Evaluates the expressions of the endpoint, headers, query and body against `input` and `vars`.
+    /// Headers and query parameters that evaluate to null are left out.
+    fn resolve(&self, input: &Value, vars: &Map<String, Value>) -> StepResult<Self> {
+        let string = |value: &str| -> StepResult<Option<String>> {
+            match resolve_with(&Value::String(value.to_string()), input, vars)? {
+                Value::Null => Ok(None),
+                value => Ok(Some(value_to_string(&value))),
+            }
+        };
+        let strings = |map: &HashMap<String, String>| -> StepResult<HashMap<String, String>> {
+            let mut resolved = HashMap::new();
+            for (name, value) in map {
+                if let Some(value) = string(value)? {
+                    resolved.insert(name.clone(), value);
+                }
+            }
+            Ok(resolved)
+        };
+        Ok(Self {
+            endpoint: string(&self.endpoint)?
+                .ok_or_else(|| format!("http endpoint '{}' evaluated to null", self.endpoint))?,
+            headers: strings(&self.headers)?,
+            query: strings(&self.query)?,
+            body: self.body.as_ref().map(|body| resolve_with(body, input, vars)).transpose()?,
+            ..self.clone()
+        })
+    }
+
+    /// Resolves `{name}` placeholders of the endpoint URI template from the input object, percent-encoding the
+    /// values.
+    fn resolve_endpoint(&self, input: &Value) -> StepResult<reqwest::Url> {
+        let mut uri = self.endpoint.clone();
+        if let Value::Object(args) = input {
+            for (key, value) in args {
+                let placeholder = format!("{{{key}}}");
+                if uri.contains(&placeholder) {
+                    uri = uri.replace(&placeholder, &encode_component(&value_to_string(value)));
+                }
+            }
+        }
+        reqwest::Url::parse(&uri).map_err(|e| format!("invalid http endpoint '{uri}': {e}").into())
+    }
+
+    fn build_request(&self, client: &reqwest::Client, input: &Value) -> StepResult<reqwest::Request> {
+        let mut builder = client
+            .request(self.method.clone(), self.resolve_endpoint(input)?)
+            .query(&self.query);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &self.body {
+            builder = builder.json(body);
+        }
+        builder.build().map_err(|e| format!("failed to build http request: {e}").into())
+    }
+
+    /// Performs the request as configured, without evaluating expressions; `input` fills the endpoint's `{name}`
+    /// placeholders.
+    pub(crate) async fn send_request(&self, ctx: &WorkflowContext, input: &Value) -> StepResult<Value> {
+        let mut req = self.build_request(&ctx.http_client, input)?;
+        if let Some(authentication) = &self.authentication {
+            authentication.apply(ctx, &mut req).await?;
+        }
+        let (summary, response) = exchange(ctx, req).await?;
+        self.read_output(&summary, response)
+    }
+
+    fn read_output(&self, request: &reqwest::Request, response: HttpResponse) -> StepResult<Value> {
+        let status = response.status;
+        let headers = headers_to_value(&response.headers);
+        let content_type = response
+            .headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let bytes = response.body;
+
+        let accepted = match &self.accept_status {
+            Some(accept) => accept.iter().any(|accepted| accepted.matches(status)),
+            None => is_status_allowed(status, self.redirect),
+        };
+        if !accepted {
+            let body = match &response.spooled {
+                Some(path) => {
+                    let _ = std::fs::remove_file(path);
+                    "<body too large to include>".to_string()
+                }
+                None => String::from_utf8_lossy(&bytes).into_owned(),
+            };
+            return Err(status_error(request.url(), status, &response.headers, body));
+        }
+
+        let content = match &response.spooled {
+            Some(path) => spooled_file(path, content_type.as_deref()),
+            None if self.output == HttpOutputFormat::Raw => Value::String(BASE64.encode(&bytes)),
+            None => decode_content(&bytes, content_type.as_deref()),
+        };
+        match self.output {
+            HttpOutputFormat::Raw | HttpOutputFormat::Content => Ok(content),
+            HttpOutputFormat::Response => {
+                let mut req = Map::new();
+                req.insert("method".into(), Value::String(request.method().to_string()));
+                req.insert("uri".into(), Value::String(request.url().to_string()));
+                req.insert("headers".into(), headers_to_value(request.headers()));
+
+                let mut map = Map::new();
+                map.insert("request".into(), Value::Object(req));
+                map.insert("statusCode".into(), Value::from(status.as_u16()));
+                map.insert("headers".into(), headers);
+                map.insert("content".into(), content);
+                Ok(Value::Object(map))
+            }
+        }
+    }
+}
+
+/// Percent-encodes `value` for use as a URI path segment or query value, keeping only unreserved characters.
+pub(crate) fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Sends `request` through the context's interceptors and, unless one of them answers it, with the context's
+/// client for its host. Returns the request as sent, after the interceptors changed it, along with the response.
+pub(crate) async fn exchange(
+    ctx: &WorkflowContext,
+    mut req: reqwest::Request,
+) -> StepResult<(reqwest::Request, HttpResponse)> {
+    let mut answered = None;
+    for interceptor in &ctx.http_interceptors {
+        answered = interceptor.before_request(ctx, &mut req).await?;
+        if answered.is_some() {
+            break;
+        }
+    }
+    let summary = req
+        .try_clone()
+        .ok_or_else(|| "http request body cannot be cloned".to_string())?;
+    let mut response = match answered {
+        Some(response) => response,
+        None => send(ctx, req).await?,
+    };
+    for interceptor in ctx.http_interceptors.iter().rev() {
+        interceptor.after_response(ctx, &summary, &mut response).await?;
+    }
+    Ok((summary, response))
+}
+
+/// Sends `request` with the context's client for its host, holding one of the host's request slots until the
+/// response is read.
+///
+/// The body is read chunk by chunk within the context's [`HttpBodyLimits`]: the call fails once the body crosses
+/// `max_bytes`, and the body moves to a file once it crosses `spool_above`. The file is deleted if reading fails.
+async fn send(ctx: &WorkflowContext, request: reqwest::Request) -> StepResult<HttpResponse> {
+    let url = request.url().clone();
+    let (client, _slot) = ctx.call_clients().acquire(&url).await;
+    let mut response = client
+        .execute(request)
+        .await
+        .map_err(|e| format!("http call to '{url}' failed: {e}"))?;
+    let limits = &ctx.http_body_limits;
+    let too_large = |max: u64| format!("http response from '{url}' exceeds the limit of {max} bytes");
+    if let (Some(max), Some(length)) = (limits.max_bytes, response.content_length())
+        && length > max
+    {
+        return Err(too_large(max).into());
+    }
+
+    let mut received = HttpResponse::new(response.status(), Vec::new());
+    received.headers = response.headers().clone();
+    if let Err(e) = read_body(&mut response, limits, &mut received, &too_large).await {
+        if let Some(path) = &received.spooled {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        return Err(e);
+    }
+    Ok(received)
+}
+
+/// Downloads a resource of a task, such as an API description or a script, the way calls send their requests:
+/// with the context's client for the host, `authentication` if set, and within the context's `max_bytes`. The
+/// body is never spooled. `what` names the resource in errors, e.g. `script`.
+pub(crate) async fn fetch(
+    ctx: &WorkflowContext,
+    uri: &str,
+    authentication: Option<&AuthenticationRef>,
+    what: &str,
+) -> StepResult<Vec<u8>> {
+    let url = reqwest::Url::parse(uri).map_err(|e| format!("invalid {what} uri '{uri}': {e}"))?;
+    let (client, _slot) = ctx.call_clients().acquire(&url).await;
+    let mut request = client
+        .get(url)
+        .build()
+        .map_err(|e| format!("failed to build request for {what} '{uri}': {e}"))?;
+    if let Some(authentication) = authentication {
+        authentication.apply(ctx, &mut request).await?;
+    }
+    let mut response = client
+        .execute(request)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("failed to fetch {what} '{uri}': {e}"))?;
+    let limits = HttpBodyLimits {
+        max_bytes: ctx.http_body_limits.max_bytes,
+        ..Default::default()
+    };
+    let too_large = |max: u64| format!("{what} '{uri}' exceeds the limit of {max} bytes");
+    if let (Some(max), Some(length)) = (limits.max_bytes, response.content_length())
+        && length > max
+    {
+        return Err(too_large(max).into());
+    }
+    let mut received = HttpResponse::new(response.status(), Vec::new());
+    read_body(&mut response, &limits, &mut received, &too_large).await?;
+    Ok(received.body)
+}
+
+async fn read_body(
+    response: &mut reqwest::Response,
+    limits: &HttpBodyLimits,
+    received: &mut HttpResponse,
+    too_large: &impl Fn(u64) -> String,
+) -> StepResult<()> {
+    let mut file = None;
+    let mut size = 0u64;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("failed to read http response: {e}"))?
+    {
+        size += chunk.len() as u64;
+        if let Some(max) = limits.max_bytes
+            && size > max
+        {
+            return Err(too_large(max).into());
+        }
+        if file.is_none() && limits.spool_above.is_some_and(|above| size > above) {
+            let dir = limits.spool_dir.clone().unwrap_or_else(std::env::temp_dir);
+            let path = dir.join(format!("tideloom-{}.body", uuid::Uuid::new_v4()));
+            let mut spool = tokio::fs::File::create(&path)
+                .await
+                .map_err(|e| format!("failed to create {}: {e}", path.display()))?;
+            spool
+                .write_all(&std::mem::take(&mut received.body))
+                .await
+                .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+            received.spooled = Some(path);
+            file = Some(spool);
+        }
+        match &mut file {
+            Some(spool) => spool
+                .write_all(&chunk)
+                .await
+                .map_err(|e| format!("failed to write http response to a file: {e}"))?,
+            None => received.body.extend_from_slice(&chunk),
+        }
+    }
+    if let Some(mut spool) = file {
+        spool
+            .flush()
+            .await
+            .map_err(|e| format!("failed to write http response to a file: {e}"))?;
+    }
+    Ok(())
+}
+
+fn string_map(value: Option<&Value>, field: &str) -> StepResult<HashMap<String, String>> {
+    match value {
+        None | Some(Value::Null) => Ok(HashMap::new()),
+        Some(Value::Object(map)) => Ok(map
+            .iter()
+            .map(|(k, v)| {
+                let v = match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (k.clone(), v)
+            })
+            .collect()),
+        Some(_) => Err(format!("http call `{field}` must be an object").into()),
+    }
+}
+
+fn headers_to_value(headers: &reqwest::header::HeaderMap) -> Value {
+    let mut map = Map::new();
+    for (name, value) in headers {
+        map.insert(
+            name.to_string(),
+            Value::String(String::from_utf8_lossy(value.as_bytes()).into_owned()),
+        );
+    }
+    Value::Object(map)
+}
+
+/// Decodes a response body according to its content type: JSON types as JSON, textual types as a string and
+/// other types base64 encoded. Without a content type, the body is read as JSON, falling back to a string.
+fn decode_content(bytes: &[u8], content_type: Option<&str>) -> Value {
+    if bytes.is_empty() {
+        return Value::Null;
+    }
+    let text = || Value::String(String::from_utf8_lossy(bytes).into_owned());
+    let Some(content_type) = content_type else {
+        return serde_json::from_slice(bytes).unwrap_or_else(|_| text());
+    };
+    let media_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    if media_type == "application/json" || media_type.ends_with("+json") {
+        serde_json::from_slice(bytes).unwrap_or_else(|_| text())
+    } else if media_type.starts_with("text/")
+        || media_type.ends_with("xml")
+        || media_type.ends_with("yaml")
+        || media_type == "application/x-www-form-urlencoded"
+    {
+        text()
+    } else {
+        Value::String(BASE64.encode(bytes))
+    }
+}
+
+/// Output of a call whose response body was spooled to `path`.
+fn spooled_file(path: &std::path::Path, content_type: Option<&str>) -> Value {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or_default();
+    let mut file = Map::new();
+    file.insert("path".into(), Value::String(path.display().to_string()));
+    file.insert("size".into(), Value::from(size));
+    file.insert("contentType".into(), content_type.map_or(Value::Null, |t| Value::String(t.into())));
+    Value::Object(file)
+}
+
+impl TryFrom<&TaskDefinition> for HTTPNode {
+    type Error = StepError;
+
+    fn try_from(task: &TaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_task(task)
+    }
+}
+
+impl TryFrom<&CallTaskDefinition> for HTTPNode {
+    type Error = StepError;
+
+    fn try_from(call: &CallTaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_http(call)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for HTTPNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        self.resolve(&input, &ctx.vars())?.send_request(ctx, &input).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serverless_workflow_core::models::workflow::WorkflowDefinition;
+
+    use super::*;
+    use crate::nodes::testing::{serve_once, serve_with_headers};
+
+    fn load_first_task(yaml: &str) -> TaskDefinition {
+        let workflow: WorkflowDefinition = serde_yaml::from_str(yaml).expect("invalid yaml");
+        workflow
+            .do_
+            .entries
+            .first()
+            .and_then(|entry| entry.iter().next())
+            .map(|(_, task)| task.clone())
+            .expect("missing task")
+    }
+
+    fn http_task(method: &str, endpoint: &str, extra: &str) -> TaskDefinition {
+        let yaml = format!(
+            r#"
+document:
+  dsl: '1.0.1'
+  namespace: test
+  name: http-example
+  version: '0.1.0'
+do:
+ - test:
+     call: http
+     with:
+        method: {method}
+        endpoint: {endpoint}
+{extra}
+ "#
+        );
+        load_first_task(&yaml)
+    }
+
+    #[tokio::test]
+    async fn http_node_from_task() {
+        let server = serve_once("200 OK", "application/json", r#"{"id":42,"name":"rex"}"#);
+        let extra = "        headers:\n          x-token: ${ .token }\n        query:\n          name: ${ .name }";
+        let task = http_task("get", &format!("{}/pet/{{petId}}", server.url), extra);
+        let step = HTTPNode::try_from_task(&task).expect("http node");
+        let ctx = WorkflowContext::default();
+
+        let output = step
+            .execute(&ctx, json!({ "petId": "a b/c", "token": "t0k", "name": "rex" }))
+            .await
+            .expect("step should succeed");
+
+        assert_eq!(output, json!({ "id": 42, "name": "rex" }));
+        let request = server.next_request();
+        assert!(request.starts_with("GET /pet/a%20b%2Fc?name=rex "), "{request}");
+        assert!(request.contains("x-token: t0k"), "{request}");
+    }
+
+    #[tokio::test]
+    async fn http_node_response_output() {
+        let server = serve_once("201 Created", "text/plain", "created");
+        let extra = "        output: response\n        body:\n          name: ${ .name }";
+        let task = http_task("post", &server.url, extra);
+        let step = HTTPNode::try_from_task(&task).expect("http node");
+
+        let output = step
+            .execute(&WorkflowContext::default(), json!({ "name": "rex" }))
+            .await
+            .expect("step should succeed");
+
+        assert_eq!(output["statusCode"], json!(201));
+        assert_eq!(output["content"], json!("created"));
+        assert_eq!(output["request"]["method"], json!("POST"));
+        assert_eq!(output["headers"]["content-type"], json!("text/plain"));
+        assert!(server.next_request().ends_with(r#"{"name":"rex"}"#));
+    }
+
+    #[tokio::test]
+    async fn http_node_raw_output() {
+        let server = serve_once("200 OK", "text/plain", "hello");
+        let task = http_task("get", &server.url, "        output: raw");
+        let step = HTTPNode::try_from_task(&task).expect("http node");
+
+        let output = step
+            .execute(&WorkflowContext::default(), json!({}))
+            .await
+            .expect("step should succeed");
+
+        assert_eq!(output, json!("aGVsbG8="));
+    }
+
+    #[tokio::test]
+    async fn http_node_rejects_error_status() {
+        let server = serve_once("404 Not Found", "text/plain", "missing");
+        let task = http_task("get", &server.url, "");
+        let step = HTTPNode::try_from_task(&task).expect("http node");
+
+        let err = step
+            .execute(&WorkflowContext::default(), json!({}))
+            .await
+            .expect_err("404 should fail");
+
+        assert!(err.contains("404"), "{err}");
+    }
+
+    #[test]
+    fn content_is_decoded_by_content_type() {
+        let body = br#"{"a":1}"#;
+        assert_eq!(decode_content(body, Some("application/problem+json")), json!({ "a": 1 }));
+        assert_eq!(decode_content(body, Some("text/plain; charset=utf-8")), json!(r#"{"a":1}"#));
+        assert_eq!(decode_content(b"hello", Some("application/octet-stream")), json!("aGVsbG8="));
+        assert_eq!(decode_content(body, None), json!({ "a": 1 }));
+    }
+
+    #[tokio::test]
+    async fn large_bodies_are_capped_or_spooled() {
+        let body = "x".repeat(64);
+        let limits = |max_bytes, spool_above| {
+            WorkflowContext::default().with_http_body_limits(HttpBodyLimits {
+                max_bytes,
+                spool_above,
+                spool_dir: None,
+            })
+        };
+
+        let server = serve_once("200 OK", "text/plain", &body);
+        let step = HTTPNode::try_from_task(&http_task("get", &server.url, "")).expect("http node");
+        let err = step.execute(&limits(Some(32), None), json!({})).await.unwrap_err();
+        assert!(err.contains("exceeds the limit of 32 bytes"), "{err}");
+
+        let server = serve_once("200 OK", "text/plain", &body);
+        let step = HTTPNode::try_from_task(&http_task("get", &server.url, "")).expect("http node");
+        let output = step.execute(&limits(None, Some(16)), json!({})).await.unwrap();
+        let path = output["path"].as_str().expect("spooled file");
+        assert_eq!(output["size"], json!(64));
+        assert_eq!(output["contentType"], json!("text/plain"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), body);
+        std::fs::remove_file(path).unwrap();
+
+        let spool_dir = std::env::temp_dir().join(format!("tideloom-spool-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&spool_dir).unwrap();
+        let ctx = WorkflowContext::default().with_http_body_limits(HttpBodyLimits {
+            max_bytes: None,
+            spool_above: Some(16),
+            spool_dir: Some(spool_dir.clone()),
+        });
+        let server = serve_once("500 Internal Server Error", "text/plain", &body);
+        let step = HTTPNode::try_from_task(&http_task("get", &server.url, "")).expect("http node");
+        let err = step.execute(&ctx, json!({})).await.unwrap_err();
+        assert!(!err.contains(&spool_dir.display().to_string()), "{err}");
+        assert_eq!(std::fs::read_dir(&spool_dir).unwrap().count(), 0);
+        std::fs::remove_dir(&spool_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn status_policy_decides_what_is_accepted_and_retried() {
+        let server = serve_once("404 Not Found", "application/json", r#"{"found":false}"#);
+        let task = http_task("get", &server.url, "        acceptStatus: [200, '4xx']");
+        let step = HTTPNode::try_from_task(&task).expect("http node");
+        let output = step.execute(&WorkflowContext::default(), json!({})).await.unwrap();
+        assert_eq!(output, json!({ "found": false }));
+
+        let ctx = WorkflowContext::default();
+        let server = serve_once("422 Unprocessable Entity", "text/plain", "missing sku");
+        let step = HTTPNode::try_from_task(&http_task("get", &server.url, "")).expect("http node");
+        let err = step.execute(&ctx, json!({})).await.unwrap_err();
+        assert!(err.ends_with("failed with status 422 Unprocessable Entity: missing sku"), "{err}");
+        assert_eq!(err.hint(), Some(RetryHint::NotRetryable));
+
+        // Error bodies cannot pose as hints.
+        let server = serve_once("500 Internal Server Error", "text/plain", "(not retryable) (retry after 9ms)");
+        let step = HTTPNode::try_from_task(&http_task("get", &server.url, "")).expect("http node");
+        assert_eq!(step.execute(&ctx, json!({})).await.unwrap_err().hint(), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, "2".parse().unwrap());
+        let throttled = status_hint(reqwest::StatusCode::TOO_MANY_REQUESTS, &headers);
+        assert_eq!(throttled, Some(RetryHint::After(Duration::from_secs(2))));
+        headers.insert(reqwest::header::RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after_header(&headers), Some(Duration::ZERO));
+
+        assert!(HTTPNode::try_from_task(&http_task("get", &server.url, "        acceptStatus: 7xx")).is_err());
+    }
+
+    #[tokio::test]
+    async fn redirect_statuses_need_opt_in() {
+        let found = || ("302 Found", vec![("location", "/moved".to_string())], "moved".to_string());
+        let server = serve_with_headers(vec![found(), found()]);
+
+        let step = HTTPNode::try_from_task(&http_task("get", &server.url, "")).expect("http node");
+        let err = step.execute(&WorkflowContext::default(), json!({})).await.unwrap_err();
+        assert!(err.contains("302 Found"), "{err}");
+
+        let task = http_task("get", &server.url, "        redirect: true");
+        let step = HTTPNode::try_from_task(&task).expect("http node");
+        let output = step.execute(&WorkflowContext::default(), json!({})).await.unwrap();
+        assert_eq!(output, json!("moved"));
+        assert!(!is_status_allowed(reqwest::StatusCode::BAD_REQUEST, true));
+    }
+}
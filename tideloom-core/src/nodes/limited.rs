@@ -0,0 +1,238 @@
+use std::time::Instant;
+
+use serde_json::Value;
+use serverless_workflow_core::models::task::TaskDefinition;
+
+use crate::nodes::doing::BoxedTask;
+use crate::runtime::metering::Usage;
+use crate::runtime::{StepInstance, StepResult, Task, WorkflowContext, run_step_with_retry};
+
+/// Runs a task within the context's concurrency limit for its kind, and reports its usage to the context's meter.
+///
+/// A failed task is retried per the context's default retry policy for its kind, each attempt taking its
+/// own slot and reported on its own.
+pub struct LimitedNode {
+    attempt: Attempt,
+}
+
+/// A single attempt of a limited task.
+struct Attempt {
+    kind: String,
+    inner: BoxedTask,
+}
+
+impl LimitedNode {
+    /// Wraps `inner` when `task` performs an external effect, and returns it unchanged otherwise.
+    pub fn wrap(inner: BoxedTask, task: &TaskDefinition) -> BoxedTask {
+        let kind = match task {
+            TaskDefinition::Call(call) => call.call.to_ascii_lowercase(),
+            TaskDefinition::Emit(_) => "emit".to_string(),
+            TaskDefinition::Listen(_) => "listen".to_string(),
+            TaskDefinition::Run(_) => "run".to_string(),
+            _ => return inner,
+        };
+        Box::new(Self {
+            attempt: Attempt { kind, inner },
+        })
+    }
+}
+
+fn json_size(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
+impl std::fmt::Debug for LimitedNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LimitedNode").field("kind", &self.attempt.kind).finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for LimitedNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        match ctx.default_retry.policy(&self.attempt.kind) {
+            Some(policy) => {
+                let mut step = StepInstance::new(self.attempt.kind.as_str());
+                run_step_with_retry(&mut step, &self.attempt, ctx, input, policy).await
+            }
+            None => self.attempt.execute(ctx, input).await,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for Attempt {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let _in_flight = ctx.limits.acquire(&self.kind).await?;
+        let Some(meter) = &ctx.meter else {
+            return self.inner.execute(ctx, input).await;
+        };
+        let bytes_in = json_size(&input);
+        let started = Instant::now();
+        let result = self.inner.execute(ctx, input).await;
+        meter.record(&Usage {
+            kind: self.kind.clone(),
+            instance: ctx.instance.clone(),
+            tenant: ctx.tenant.clone(),
+            duration: started.elapsed(),
+            bytes_in,
+            bytes_out: result.as_ref().map_or(0, json_size),
+            succeeded: result.is_ok(),
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::runtime::WorkflowContext;
+    use crate::runtime::limits::ConcurrencyLimits;
+    use crate::runtime::metering::UsageAggregator;
+    use crate::runtime::retry::{RetryDefaults, RetryPolicy};
+
+    #[tokio::test]
+    async fn run_tasks_share_the_cap_across_instances() {
+        let workflow = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: slow
+  version: '1.0.0'
+do:
+  - nap:
+      run:
+        shell:
+          command: sleep
+          arguments: ['0.2']
+",
+        )
+        .unwrap();
+        let ctx = WorkflowContext::default().with_limits(ConcurrencyLimits::default().with_cap("run", 1));
+
+        let first = tokio::spawn({
+            let (workflow, ctx) = (workflow.clone(), ctx.clone());
+            async move { workflow.run(&ctx, json!({})).await }
+        });
+        let second = tokio::spawn({
+            let (workflow, ctx) = (workflow.clone(), ctx.clone());
+            async move { workflow.run(&ctx, json!({})).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(ctx.limits.in_flight("run"), 1);
+        first.await.unwrap().unwrap();
+        second.await.unwrap().unwrap();
+        assert_eq!(ctx.limits.in_flight("run"), 0);
+    }
+
+    #[tokio::test]
+    async fn usage_is_aggregated_per_instance_workflow_and_tenant() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: metered
+  version: '1.0.0'
+do:
+  - greet:
+      run:
+        shell:
+          command: echo
+          arguments: ["${ .name }"]
+  - fail:
+      run:
+        shell:
+          command: 'false'
+"#,
+        )
+        .unwrap();
+        let meter = Arc::new(UsageAggregator::default());
+        let ctx = WorkflowContext::default().with_meter(meter.clone()).with_tenant("acme");
+
+        workflow.run(&ctx, json!({ "name": "ada" })).await.unwrap_err();
+        workflow.run(&ctx.clone().with_tenant("globex"), json!({ "name": "bob" })).await.unwrap_err();
+
+        let acme = meter.by_tenant()["acme"];
+        assert_eq!((acme.executions, acme.failures), (2, 1));
+        assert_eq!(acme.bytes_in, 14 + 5);
+        assert_eq!(acme.bytes_out, 5);
+        assert_eq!(meter.by_workflow()["test/metered:1.0.0"].executions, 4);
+        assert_eq!(meter.by_instance().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn default_retry_applies_unless_a_try_declares_one() {
+        let dir = std::env::temp_dir().join(format!("tideloom-default-retry-{}", uuid::Uuid::new_v4()));
+        // Fails on the first attempt, then succeeds.
+        let script = format!(
+            "mkdir -p {0} && echo x >> {0}/attempts && test $(wc -l < {0}/attempts) -ge 2 && echo done",
+            dir.display()
+        );
+        let workflow = |tasks: String| {
+            Workflow::try_from_yaml(&format!(
+                "document:\n  dsl: '1.0.0'\n  namespace: test\n  name: flaky\n  version: '1.0.0'\n{tasks}"
+            ))
+            .unwrap()
+        };
+        let bare = workflow(format!(
+            r#"
+do:
+- flaky:
+    run:
+      shell:
+        command: sh
+        arguments: ['-c', '{script}']
+"#
+        ));
+        let caught = workflow(format!(
+            r#"
+do:
+- guarded:
+    try:
+    - flaky:
+        run:
+          shell:
+            command: sh
+            arguments: ['-c', '{script}']
+    catch:
+      retry:
+        limit: {{ attempt: {{ count: 0 }} }}
+      do:
+      - fallback:
+          run:
+            shell:
+              command: sh
+              arguments: ['-c', 'echo fallback']
+"#
+        ));
+        let quick = RetryPolicy {
+            delay: Duration::from_millis(5),
+            max_retries: Some(2),
+            ..Default::default()
+        };
+        let never = RetryPolicy {
+            max_retries: Some(0),
+            ..Default::default()
+        };
+        let ctx = WorkflowContext::default().with_default_retry(RetryDefaults::new(quick).with_kind("http", never));
+
+        assert_eq!(bare.run(&ctx, json!({})).await.unwrap(), json!("done"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(caught.run(&ctx, json!({})).await.unwrap(), json!("fallback"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
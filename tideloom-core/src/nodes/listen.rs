@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use regex::Regex;
+use serde_json::{Map, Value};
+use serverless_workflow_core::models::event::{
+    EventConsumptionStrategyDefinition, EventFilterDefinition, OneOfEventConsumptionStrategyDefinitionOrExpression,
+};
+use serverless_workflow_core::models::task::{ListenTaskDefinition, TaskDefinition};
+
+use crate::messaging::CloudEvent;
+use crate::runtime::expr::{is_expression, parse_path, select, select_with, value_to_string};
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext};
+
+/// Matcher for a single event attribute declared in a filter's `with` block.
+#[derive(Debug, Clone)]
+enum AttributeMatcher {
+    /// String attributes are matched as anchored regular expressions.
+    Pattern(Regex),
+    Exact(Value),
+}
+
+impl AttributeMatcher {
+    fn new(expected: &Value) -> StepResult<Self> {
+        match expected {
+            Value::String(s) if is_expression(s) => {
+                Err(format!("runtime expressions are not supported in event filters: '{s}'").into())
+            }
+            Value::String(s) => Regex::new(&format!("^(?:{s})$"))
+                .map(AttributeMatcher::Pattern)
+                .map_err(|e| format!("invalid event filter pattern '{s}': {e}").into()),
+            other => Ok(AttributeMatcher::Exact(other.clone())),
+        }
+    }
+
+    fn matches(&self, actual: Option<&Value>) -> bool {
+        match (self, actual) {
+            (AttributeMatcher::Pattern(re), Some(Value::String(s))) => re.is_match(s),
+            (AttributeMatcher::Exact(expected), Some(actual)) => contains(actual, expected),
+            _ => false,
+        }
+    }
+}
+
+/// Where the expected value of a correlation key comes from.
+#[derive(Debug, Clone)]
+enum Expectation {
+    Constant(String),
+    /// Path into the task input, resolved when the listen task starts.
+    Input(String),
+}
+
+#[derive(Debug, Clone)]
+struct CorrelationKey {
+    name: String,
+    from: String,
+    expect: Option<Expectation>,
+}
+
+/// Compiled form of an `EventFilterDefinition`.
+#[derive(Debug, Clone)]
+pub struct EventFilter {
+    attributes: Vec<(String, AttributeMatcher)>,
+    correlate: Vec<CorrelationKey>,
+}
+
+impl EventFilter {
+    pub fn try_from_definition(def: &EventFilterDefinition) -> StepResult<Self> {
+        let mut attributes = Vec::new();
+        for (name, expected) in def.with.iter().flatten() {
+            attributes.push((name.clone(), AttributeMatcher::new(expected)?));
+        }
+        let mut correlate = Vec::new();
+        for (name, key) in def.correlate.iter().flatten() {
+            parse_path(&key.from)?;
+            let expect = match &key.expect {
+                Some(expect) if is_expression(expect) => {
+                    parse_path(expect)?;
+                    Some(Expectation::Input(expect.clone()))
+                }
+                Some(expect) => Some(Expectation::Constant(expect.clone())),
+                None => None,
+            };
+            correlate.push(CorrelationKey {
+                name: name.clone(),
+                from: key.from.clone(),
+                expect,
+            });
+        }
+        Ok(Self { attributes, correlate })
+    }
+
+    /// Checks the event against the filter, recording first-seen correlation values on success.
+    fn matches(&self, event: &Value, correlation: &mut HashMap<String, String>) -> bool {
+        if !self
+            .attributes
+            .iter()
+            .all(|(name, matcher)| matcher.matches(event.get(name)))
+        {
+            return false;
+        }
+
+        let mut seen = Vec::new();
+        for key in &self.correlate {
+            let Some(actual) = select(event, &key.from).map(|v| value_to_string(&v)) else {
+                return false;
+            };
+            match correlation.get(&key.name) {
+                Some(expected) if *expected != actual => return false,
+                Some(_) => {}
+                None => seen.push((key.name.clone(), actual)),
+            }
+        }
+        correlation.extend(seen);
+        true
+    }
+}
+
+/// Compiled form of the `listen.to` consumption strategy.
+#[derive(Debug, Clone)]
+pub enum ConsumptionStrategy {
+    One(EventFilter),
+    Any {
+        filters: Vec<EventFilter>,
+        until: Option<Box<ConsumptionStrategy>>,
+    },
+    All(Vec<EventFilter>),
+}
+
+impl ConsumptionStrategy {
+    pub fn try_from_definition(def: &EventConsumptionStrategyDefinition) -> StepResult<Self> {
+        let compile = |filters: &Vec<EventFilterDefinition>| {
+            filters
+                .iter()
+                .map(EventFilter::try_from_definition)
+                .collect::<StepResult<Vec<_>>>()
+        };
+        match (&def.one, &def.any, &def.all) {
+            (Some(one), None, None) => Ok(Self::One(EventFilter::try_from_definition(one)?)),
+            (None, Some(any), None) => {
+                let until = match def.until.as_deref() {
+                    None => None,
+                    Some(OneOfEventConsumptionStrategyDefinitionOrExpression::Strategy(until)) => {
+                        Some(Box::new(Self::try_from_definition(until)?))
+                    }
+                    Some(OneOfEventConsumptionStrategyDefinitionOrExpression::Expression(expr)) => {
+                        return Err(format!("runtime expressions are not supported in `until`: '{expr}'").into());
+                    }
+                };
+                Ok(Self::Any {
+                    filters: compile(any)?,
+                    until,
+                })
+            }
+            (None, None, Some(all)) => Ok(Self::All(compile(all)?)),
+            _ => Err("listen `to` must define exactly one of `one`, `any` or `all`".into()),
+        }
+    }
+
+    fn filters(&self) -> &[EventFilter] {
+        match self {
+            Self::One(filter) => std::slice::from_ref(filter),
+            Self::Any { filters, .. } | Self::All(filters) => filters,
+        }
+    }
+}
+
+/// Tracks which events a strategy has consumed so far.
+struct Progress<'a> {
+    strategy: &'a ConsumptionStrategy,
+    slots: Vec<Option<Value>>,
+    consumed: Vec<Value>,
+    until: Option<Box<Progress<'a>>>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(strategy: &'a ConsumptionStrategy) -> Self {
+        let until = match strategy {
+            ConsumptionStrategy::Any { until: Some(until), .. } => Some(Box::new(Progress::new(until))),
+            _ => None,
+        };
+        Self {
+            strategy,
+            slots: vec![None; strategy.filters().len()],
+            consumed: Vec::new(),
+            until,
+        }
+    }
+
+    /// Offers an event, returning whether it was consumed.
+    fn offer(&mut self, event: &Value, correlation: &mut HashMap<String, String>) -> bool {
+        if let Some(until) = &mut self.until {
+            until.offer(event, correlation);
+        }
+        match self.strategy {
+            ConsumptionStrategy::One(filter) => {
+                if self.consumed.is_empty() && filter.matches(event, correlation) {
+                    self.consumed.push(event.clone());
+                    return true;
+                }
+                false
+            }
+            ConsumptionStrategy::Any { filters, .. } => {
+                if filters.is_empty() || filters.iter().any(|f| f.matches(event, correlation)) {
+                    self.consumed.push(event.clone());
+                    return true;
+                }
+                false
+            }
+            ConsumptionStrategy::All(filters) => {
+                for (slot, filter) in self.slots.iter_mut().zip(filters) {
+                    if slot.is_none() && filter.matches(event, correlation) {
+                        *slot = Some(event.clone());
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        match self.strategy {
+            ConsumptionStrategy::One(_) => !self.consumed.is_empty(),
+            ConsumptionStrategy::Any { .. } => match &self.until {
+                Some(until) => until.is_complete(),
+                None => !self.consumed.is_empty(),
+            },
+            ConsumptionStrategy::All(_) => self.slots.iter().all(Option::is_some),
+        }
+    }
+
+    fn into_events(self) -> Vec<Value> {
+        match self.strategy {
+            ConsumptionStrategy::All(_) => self.slots.into_iter().flatten().collect(),
+            _ => self.consumed,
+        }
+    }
+}
+
+/// How consumed events are exposed in the task output, per `listen.read`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListenReadMode {
+    Data,
+    #[default]
+    Envelope,
+    Raw,
+}
+
+/// `listen` task: waits on the context's event source until its consumption strategy is satisfied.
+#[derive(Debug, Clone)]
+pub struct ListenNode {
+    strategy: ConsumptionStrategy,
+    read: ListenReadMode,
+}
+
+impl ListenNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Listen(listen) => Self::try_from_listen(listen),
+            _ => Err("ListenNode expects a `listen` task definition".into()),
+        }
+    }
+
+    pub fn try_from_listen(listen: &ListenTaskDefinition) -> StepResult<Self> {
+        if listen.foreach.as_ref().is_some_and(|f| f.do_.is_some()) {
+            return Err("listen `foreach` tasks are not supported".into());
+        }
+        let read = match listen.listen.read.as_deref() {
+            None | Some("envelope") => ListenReadMode::Envelope,
+            Some("data") => ListenReadMode::Data,
+            Some("raw") => ListenReadMode::Raw,
+            Some(other) => return Err(format!("unsupported listen read mode '{other}'").into()),
+        };
+        Ok(Self {
+            strategy: ConsumptionStrategy::try_from_definition(&listen.listen.to)?,
+            read,
+        })
+    }
+
+    /// Seeds correlation expectations declared with a constant or an input path.
+    fn initial_correlation(&self, input: &Value, vars: &Map<String, Value>) -> HashMap<String, String> {
+        let mut correlation = HashMap::new();
+        let mut pending = vec![&self.strategy];
+        while let Some(strategy) = pending.pop() {
+            for key in strategy.filters().iter().flat_map(|f| &f.correlate) {
+                let expected = match &key.expect {
+                    Some(Expectation::Constant(value)) => Some(value.clone()),
+                    Some(Expectation::Input(path)) => select_with(input, vars, path).map(|v| value_to_string(&v)),
+                    None => None,
+                };
+                if let Some(expected) = expected {
+                    correlation.insert(key.name.clone(), expected);
+                }
+            }
+            if let ConsumptionStrategy::Any { until: Some(until), .. } = strategy {
+                pending.push(until);
+            }
+        }
+        correlation
+    }
+
+    fn read(&self, event: Value) -> Value {
+        match self.read {
+            ListenReadMode::Envelope => event,
+            ListenReadMode::Data => event.get("data").cloned().unwrap_or(Value::Null),
+            ListenReadMode::Raw => Value::String(event.to_string()),
+        }
+    }
+}
+
+impl TryFrom<&TaskDefinition> for ListenNode {
+    type Error = StepError;
+
+    fn try_from(task: &TaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_task(task)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for ListenNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let source = ctx
+            .event_source
+            .as_ref()
+            .ok_or_else(|| "listen task requires an event source in the workflow context".to_string())?;
+        let mut events = source.subscribe().await?;
+        let mut correlation = self.initial_correlation(&input, &ctx.vars());
+        let mut progress = Progress::new(&self.strategy);
+
+        while !progress.is_complete() {
+            let event: CloudEvent = events
+                .next()
+                .await
+                .ok_or_else(|| "event source closed before the listen task completed".to_string())?;
+            progress.offer(&event.to_value(), &mut correlation);
+        }
+
+        Ok(Value::Array(
+            progress.into_events().into_iter().map(|e| self.read(e)).collect(),
+        ))
+    }
+}
+
+/// Returns whether `actual` contains `expected`: objects match on a subset of keys, other values on equality.
+fn contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(k, v)| actual.get(k).is_some_and(|a| contains(a, v))),
+        _ => actual == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::messaging::InMemoryEventBus;
+
+    fn listen_node(yaml: &str) -> ListenNode {
+        let task: TaskDefinition = serde_yaml::from_str(yaml).expect("invalid yaml");
+        ListenNode::try_from_task(&task).expect("listen node")
+    }
+
+    fn event(id: &str, type_: &str, data: Value) -> CloudEvent {
+        CloudEvent::new(id, "/test", type_).with_data(data)
+    }
+
+    async fn run(node: &ListenNode, input: Value, events: Vec<CloudEvent>) -> StepResult<Value> {
+        let bus = Arc::new(InMemoryEventBus::default());
+        let ctx = WorkflowContext::default().with_event_source(bus.clone());
+        let publish = async {
+            tokio::task::yield_now().await;
+            for event in events {
+                bus.publish(event);
+            }
+        };
+        let (output, _) = tokio::join!(node.execute(&ctx, input), publish);
+        output
+    }
+
+    #[tokio::test]
+    async fn listen_one_skips_unmatched_events() {
+        let node = listen_node(
+            r#"
+listen:
+  to:
+    one:
+      with:
+        type: com.example.order.(created|updated)
+"#,
+        );
+        let output = run(
+            &node,
+            json!({}),
+            vec![
+                event("1", "com.example.user.created", json!({})),
+                event("2", "com.example.order.updated", json!({ "id": 7 })),
+            ],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output.as_array().unwrap().len(), 1);
+        assert_eq!(output[0]["id"], json!("2"));
+    }
+
+    #[tokio::test]
+    async fn listen_all_correlates_with_input() {
+        let node = listen_node(
+            r#"
+listen:
+  to:
+    all:
+      - with:
+          type: order.paid
+        correlate:
+          order:
+            from: ${ .data.orderId }
+            expect: ${ .orderId }
+      - with:
+          type: order.shipped
+        correlate:
+          order:
+            from: .data.orderId
+  read: data
+"#,
+        );
+        let output = run(
+            &node,
+            json!({ "orderId": 42 }),
+            vec![
+                event("1", "order.shipped", json!({ "orderId": 41 })),
+                event("2", "order.shipped", json!({ "orderId": 42, "carrier": "ups" })),
+                event("3", "order.paid", json!({ "orderId": 42 })),
+            ],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            output,
+            json!([{ "orderId": 42 }, { "orderId": 42, "carrier": "ups" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn listen_any_until() {
+        let node = listen_node(
+            r#"
+listen:
+  to:
+    any:
+      - with:
+          type: tick
+    until:
+      one:
+        with:
+          type: stop
+"#,
+        );
+        let output = run(
+            &node,
+            json!({}),
+            vec![
+                event("1", "tick", json!(1)),
+                event("2", "tick", json!(2)),
+                event("3", "stop", json!(null)),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let ids: Vec<_> = output.as_array().unwrap().iter().map(|e| e["id"].clone()).collect();
+        assert_eq!(ids, [json!("1"), json!("2")]);
+    }
+
+    #[tokio::test]
+    async fn listen_requires_event_source() {
+        let node = listen_node("listen:\n  to:\n    any: []\n");
+        let err = node.execute(&WorkflowContext::default(), json!({})).await.unwrap_err();
+        assert!(err.contains("event source"), "{err}");
+    }
+}
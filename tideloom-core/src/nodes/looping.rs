@@ -0,0 +1,235 @@
+use std::sync::atomic::Ordering;
+
+use serde_json::Value;
+use serverless_workflow_core::models::task::{ForTaskDefinition, TaskDefinition};
+
+use crate::nodes::doing::{DoNode, TaskSite};
+use crate::runtime::expr::{parse_path, select_with};
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext, cancelled_error};
+
+/// `for` task: runs its `do` tasks once per item of the collection selected by `for.in`.
+///
+/// The current item and its index are bound to the variables named by `for.each` and `for.at`
+/// (`$item` and `$index` by default), so the body keeps access to its regular input. The first iteration
+/// receives the task input and each next one the previous iteration's output; the last output is the
+/// task output. An optional `while` path is checked before every iteration and stops the loop once it
+/// selects `null` or `false`.
+///
+/// The loop yields to the runtime every [`YIELD_EVERY`] iterations, so that a long loop over tasks that never
+/// wait, such as `call` targets answering from memory, does not hold up other instances on the same worker.
+#[derive(Debug)]
+pub struct ForNode {
+    each: String,
+    at: String,
+    in_: String,
+    while_: Option<String>,
+    body: DoNode,
+}
+
+/// Iterations a `for` loop runs between two yields to the runtime.
+pub const YIELD_EVERY: usize = 64;
+
+impl ForNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::For(def) => Self::try_from_for(def),
+            _ => Err("ForNode expects a `for` task definition".into()),
+        }
+    }
+
+    pub fn try_from_for(def: &ForTaskDefinition) -> StepResult<Self> {
+        Self::build(def, &TaskSite::new(""))
+    }
+
+    /// Builds the node of the `for` task at `site`.
+    pub(crate) fn build(def: &ForTaskDefinition, site: &TaskSite) -> StepResult<Self> {
+        let variable = |name: Option<&str>, default: &str| {
+            let name = name.map(str::trim).filter(|n| !n.is_empty()).unwrap_or(default);
+            let name = name.strip_prefix('$').unwrap_or(name);
+            parse_path(&format!("${name}"))
+                .ok()
+                .filter(|segments| segments.len() == 1)
+                .map(|_| format!("${name}"))
+                .ok_or_else(|| format!("invalid for loop variable name '{name}'"))
+        };
+        parse_path(&def.for_.in_).map_err(|e| format!("for.in: {e}"))?;
+        if let Some(while_) = &def.while_ {
+            parse_path(while_).map_err(|e| format!("for while: {e}"))?;
+        }
+        Ok(Self {
+            each: variable(Some(&def.for_.each), "item")?,
+            at: variable(def.for_.at.as_deref(), "index")?,
+            in_: def.for_.in_.clone(),
+            while_: def.while_.clone(),
+            body: DoNode::build(&def.do_, &site.join("do"))?,
+        })
+    }
+}
+
+impl TryFrom<&ForTaskDefinition> for ForNode {
+    type Error = StepError;
+
+    fn try_from(def: &ForTaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_for(def)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for ForNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let items = match select_with(&input, &ctx.vars(), &self.in_) {
+            Some(Value::Array(items)) => items,
+            Some(Value::Null) | None => Vec::new(),
+            Some(other) => return Err(format!("for.in must select an array, got {other}").into()),
+        };
+        let mut output = input;
+        for (index, item) in items.into_iter().enumerate() {
+            if index > 0 && index % YIELD_EVERY == 0 {
+                tokio::task::yield_now().await;
+            }
+            if ctx.cancellation.is_cancelled() {
+                return Err(cancelled_error(&format!("for iteration {index}")));
+            }
+            let mut scope = ctx.clone();
+            scope.scope.insert(self.each.clone(), item);
+            scope.scope.insert(self.at.clone(), Value::from(index));
+            if let Some(while_) = &self.while_
+                && matches!(select_with(&output, &scope.vars(), while_), None | Some(Value::Null | Value::Bool(false)))
+            {
+                break;
+            }
+            output = self
+                .body
+                .execute(&scope, output)
+                .await
+                .map_err(|e| e.map_message(|e| format!("for iteration {index} failed: {e}")))?;
+            if ctx.ended.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::nodes::testing::value_context;
+    use crate::runtime::WorkflowContext;
+
+    fn workflow(for_: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: loop
+  version: '1.0.0'
+do:
+  - each:
+{for_}
+      do:
+        - greet:
+            output:
+              as:
+                prefix: ${{ $input.prefix }}
+                last: ${{ $greeting }}
+                at: ${{ $position }}
+            run:
+              shell:
+                command: 'true'
+"#
+        )).unwrap()
+    }
+
+    #[tokio::test]
+    async fn loop_variables_are_bound_without_replacing_the_input() {
+        let workflow =
+            workflow("      for:\n        each: greeting\n        in: .greetings\n        at: position");
+        let input = json!({ "prefix": "hello", "greetings": ["ada", "bob"] });
+
+        let output = workflow.run(&WorkflowContext::default(), input).await.unwrap();
+
+        assert_eq!(output, json!({ "prefix": "hello", "last": "bob", "at": 1 }));
+    }
+
+    #[tokio::test]
+    async fn while_stops_the_loop_and_defaults_apply() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: loop
+  version: '1.0.0'
+do:
+  - each:
+      for:
+        in: $context.items
+      while: .more
+      do:
+        - step:
+            output:
+              as:
+                more: ${ $item }
+                index: ${ $index }
+            run:
+              shell:
+                command: 'true'
+"#,
+        ).unwrap();
+        let ctx = WorkflowContext::default().with_context(json!({ "items": [true, false, true] }));
+
+        let output = workflow.run(&ctx, json!({ "more": true })).await.unwrap();
+
+        assert_eq!(output, json!({ "more": false, "index": 1 }));
+    }
+
+    #[tokio::test]
+    async fn long_loops_let_other_tasks_run() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: loop
+  version: '1.0.0'
+do:
+  - each:
+      for:
+        in: .items
+      do:
+        - step:
+            call: value
+            with:
+              item: ${ $item }
+"#,
+        )
+        .unwrap();
+        // The test runtime has a single thread, so the ticker only runs when the loop yields.
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    ticks.fetch_add(1, Ordering::Relaxed);
+                    tokio::task::yield_now().await;
+                }
+            }
+        });
+
+        let output = workflow.run(&value_context(), json!({ "items": (0..1000).collect::<Vec<_>>() })).await.unwrap();
+
+        ticker.abort();
+        assert_eq!(output, json!({ "item": 999 }));
+        assert!(ticks.load(Ordering::Relaxed) > 1);
+    }
+}
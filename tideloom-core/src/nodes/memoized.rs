@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use serde_json::Value;
+use serverless_workflow_core::models::duration::OneOfDurationOrIso8601Expression;
+use serverless_workflow_core::models::task::TaskDefinition;
+
+use crate::extensions::{MEMOIZE, extension};
+use crate::nodes::doing::{BoxedTask, task_fields};
+use crate::runtime::expr::resolve_with;
+use crate::runtime::timeout::duration_of;
+use crate::runtime::{StepResult, Task, WorkflowContext};
+
+/// Reuses the result of a call task whose `metadata.tideloom.memoize` key, resolved against the task input, matches
+/// a result stored less than `ttl` ago, within or across instances sharing the context's cache.
+///
+/// The key defaults to the whole input. Results are only shared by runs of the same tenant whose call resolves to
+/// the same `with`, so that calls reading `$context` or `$secrets` never see each other's results. Only
+/// successful results are stored.
+pub struct MemoizedNode {
+    call: String,
+    /// The call's `with`, resolved on every run to tell calls with equal keys apart.
+    with: Value,
+    key: Value,
+    ttl: Duration,
+    inner: BoxedTask,
+}
+
+impl MemoizedNode {
+    /// Wraps `inner` when `task` declares `metadata.tideloom.memoize`, and returns it unchanged otherwise.
+    pub fn wrap(inner: BoxedTask, task: &TaskDefinition) -> StepResult<BoxedTask> {
+        let Some(memoize) = extension(task_fields(task), MEMOIZE) else {
+            return Ok(inner);
+        };
+        let TaskDefinition::Call(call) = task else {
+            return Err("`memoize` is only supported on call tasks".into());
+        };
+        let ttl = memoize
+            .get("ttl")
+            .ok_or_else(|| "`memoize` requires a `ttl`".to_string())?;
+        let ttl: OneOfDurationOrIso8601Expression =
+            serde_json::from_value(ttl.clone()).map_err(|e| format!("invalid memoize `ttl`: {e}"))?;
+        Ok(Box::new(Self {
+            call: call.call.clone(),
+            with: serde_json::to_value(&call.with).map_err(|e| format!("invalid call: {e}"))?,
+            key: memoize.get("key").cloned().unwrap_or_else(|| Value::String("${ . }".into())),
+            ttl: duration_of(&ttl)?,
+            inner,
+        }))
+    }
+}
+
+impl std::fmt::Debug for MemoizedNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoizedNode")
+            .field("key", &self.key)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for MemoizedNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let vars = ctx.vars();
+        let key = resolve_with(&self.key, &input, &vars).map_err(|e| format!("memoize key: {e}"))?;
+        let with = resolve_with(&self.with, &input, &vars).map_err(|e| format!("memoize key: {e}"))?;
+        let key = serde_json::to_string(&(&ctx.tenant, &self.call, with, key))
+            .map_err(|e| format!("memoize key: {e}"))?;
+        if let Some(output) = ctx.memo.get(&key) {
+            return Ok(output);
+        }
+        let output = self.inner.execute(ctx, input).await?;
+        ctx.memo.insert(key, output.clone(), self.ttl);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::nodes::testing::serve;
+    use crate::runtime::WorkflowContext;
+
+    fn lookup(url: &str, memoize: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: lookup
+  version: '1.0.0'
+do:
+- fetch:
+    call: http
+    metadata:
+      tideloom:
+        memoize: {memoize}
+    with:
+      method: get
+      endpoint: {url}/customers/{{id}}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn calls_reuse_results_across_instances() {
+        let server = serve(vec![
+            ("200 OK", "application/json", r#"{"name":"ada"}"#.to_string()),
+            ("200 OK", "application/json", r#"{"name":"bob"}"#.to_string()),
+        ]);
+        let workflow = lookup(&server.url, "{ key: '${ .id }', ttl: PT1M }");
+        let ctx = WorkflowContext::default();
+
+        for _ in 0..2 {
+            assert_eq!(workflow.run(&ctx, json!({ "id": 1 })).await.unwrap(), json!({ "name": "ada" }));
+        }
+        assert_eq!(workflow.run(&ctx, json!({ "id": 2 })).await.unwrap(), json!({ "name": "bob" }));
+        assert!(server.next_request().starts_with("GET /customers/1 "));
+        assert!(server.next_request().starts_with("GET /customers/2 "));
+        assert_eq!(ctx.memo.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn tenants_do_not_share_results() {
+        let server = serve(vec![
+            ("200 OK", "application/json", r#"{"name":"ada"}"#.to_string()),
+            ("200 OK", "application/json", r#"{"name":"bob"}"#.to_string()),
+        ]);
+        let workflow = lookup(&server.url, "{ key: '${ .id }', ttl: PT1M }");
+        let acme = WorkflowContext::default().with_tenant("acme");
+        let globex = acme.clone().with_tenant("globex");
+
+        assert_eq!(workflow.run(&acme, json!({ "id": 1 })).await.unwrap(), json!({ "name": "ada" }));
+        assert_eq!(workflow.run(&globex, json!({ "id": 1 })).await.unwrap(), json!({ "name": "bob" }));
+        assert_eq!(workflow.run(&acme, json!({ "id": 1 })).await.unwrap(), json!({ "name": "ada" }));
+        assert_eq!(acme.memo.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn memoize_requires_a_call_task_and_a_ttl() {
+        let run = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: lookup
+  version: '1.0.0'
+do:
+- fetch:
+    metadata:
+      tideloom:
+        memoize:
+          ttl: PT1M
+    run:
+      workflow: { namespace: test, name: customer, version: '1.0.0' }
+"#,
+        )
+        .unwrap();
+        let err = run.run(&WorkflowContext::default(), json!({})).await.unwrap_err();
+        assert!(err.contains("only supported on call tasks"), "{err}");
+
+        let without_ttl = lookup("http://localhost", "{ key: '${ .id }' }");
+        let err = without_ttl.run(&WorkflowContext::default(), json!({})).await.unwrap_err();
+        assert!(err.contains("requires a `ttl`"), "{err}");
+    }
+}
@@ -0,0 +1,21 @@
+use serde_json::Value;
+
+use crate::runtime::{StepResult, Task, WorkflowContext};
+
+/// Stands in for a task mocked or disabled by [`Overrides`](crate::overrides::Overrides).
+///
+/// A mock outputs its canned value; a disabled task passes its input through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockNode {
+    pub output: Option<Value>,
+}
+
+#[async_trait::async_trait]
+impl Task for MockNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, _ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        Ok(self.output.clone().unwrap_or(input))
+    }
+}
@@ -1,2 +1,22 @@
 pub mod asyncapi;
-mod doing;
+pub mod conditional;
+pub mod doing;
+pub mod emit;
+pub mod fanout;
+pub mod forking;
+pub mod function;
+pub mod http;
+pub mod limited;
+pub mod listen;
+pub mod looping;
+pub mod memoized;
+pub mod mock;
+pub mod openapi;
+pub mod pipeline;
+pub mod run;
+pub mod switch;
+pub mod timeout;
+pub mod trying;
+pub mod workflow;
+#[cfg(test)]
+pub(crate) mod testing;
@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde_json::{Map, Value};
+use serverless_workflow_core::models::task::{CallTaskDefinition, TaskDefinition};
+
+use crate::nodes::http::{HTTPNode, HttpOutputFormat, encode_component, fetch};
+use crate::runtime::auth::AuthenticationRef;
+use crate::runtime::expr::{resolve_with, value_to_string};
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext};
+
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Where the OpenAPI document of a call comes from.
+#[derive(Debug, Clone)]
+pub enum OpenApiSource {
+    Uri(String),
+    Inline(Value),
+}
+
+/// An operation resolved from an OpenAPI document by its `operationId`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApiOperation {
+    pub method: String,
+    pub base_url: String,
+    pub path: String,
+    pub parameters: Vec<OpenApiParameter>,
+    pub has_body: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApiParameter {
+    pub name: String,
+    pub location: String,
+    pub required: bool,
+}
+
+/// `call: openapi` task: resolves `operationId` in the referenced document and executes it over HTTP.
+#[derive(Debug)]
+pub struct OpenApiNode {
+    source: OpenApiSource,
+    operation_id: String,
+    parameters: Map<String, Value>,
+    output: HttpOutputFormat,
+    redirect: bool,
+    authentication: Option<AuthenticationRef>,
+    document: OnceLock<Value>,
+}
+
+impl OpenApiNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Call(call) if call.call.eq_ignore_ascii_case("openapi") => Self::try_from_openapi(call),
+            TaskDefinition::Call(call) => Err(format!("expected call 'openapi', got '{}'", call.call).into()),
+            _ => Err("OpenApiNode expects a `call` task definition".into()),
+        }
+    }
+
+    pub fn try_from_openapi(call: &CallTaskDefinition) -> StepResult<Self> {
+        let with = call
+            .with
+            .as_ref()
+            .ok_or_else(|| "openapi call requires a `with` block".to_string())?;
+
+        let document = with
+            .get("document")
+            .ok_or_else(|| "openapi call requires a `document`".to_string())?;
+        let source = match document {
+            Value::String(uri) => OpenApiSource::Uri(uri.clone()),
+            Value::Object(doc) => match (doc.get("content"), doc.get("endpoint"), doc.get("uri")) {
+                (Some(content), _, _) => OpenApiSource::Inline(content.clone()),
+                (_, Some(Value::String(uri)), _) | (_, _, Some(Value::String(uri))) => OpenApiSource::Uri(uri.clone()),
+                (_, Some(Value::Object(endpoint)), _) => OpenApiSource::Uri(
+                    endpoint
+                        .get("uri")
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| "openapi document endpoint requires a `uri`".to_string())?
+                        .to_string(),
+                ),
+                _ => return Err("openapi document requires an `endpoint` or inline `content`".into()),
+            },
+            _ => return Err("openapi `document` must be a uri or an object".into()),
+        };
+        let operation_id = with
+            .get("operationId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "openapi call requires an `operationId`".to_string())?
+            .to_string();
+        let parameters = match with.get("parameters") {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(map)) => map.clone(),
+            Some(_) => return Err("openapi `parameters` must be an object".into()),
+        };
+        let output = match with.get("output").and_then(Value::as_str) {
+            Some(output) => output.parse()?,
+            None => HttpOutputFormat::default(),
+        };
+
+        Ok(Self {
+            source,
+            operation_id,
+            parameters,
+            output,
+            redirect: with.get("redirect").and_then(Value::as_bool).unwrap_or(false),
+            authentication: with
+                .get("authentication")
+                .map(AuthenticationRef::try_from_value)
+                .transpose()?,
+            document: OnceLock::new(),
+        })
+    }
+
+    async fn document(&self, ctx: &WorkflowContext) -> StepResult<&Value> {
+        if let Some(document) = self.document.get() {
+            return Ok(document);
+        }
+        let document = match &self.source {
+            OpenApiSource::Inline(content) => content.clone(),
+            OpenApiSource::Uri(uri) => fetch_document(ctx, uri, self.authentication.as_ref(), "openapi").await?,
+        };
+        Ok(self.document.get_or_init(|| document))
+    }
+
+    fn document_uri(&self) -> Option<&str> {
+        match &self.source {
+            OpenApiSource::Uri(uri) => Some(uri),
+            OpenApiSource::Inline(_) => None,
+        }
+    }
+
+    /// Maps the call parameters, with their expressions already evaluated, onto the resolved operation, producing
+    /// the HTTP call to perform. Parameters that evaluated to null count as missing.
+    fn build_http(&self, operation: &OpenApiOperation, parameters: &Map<String, Value>) -> StepResult<HTTPNode> {
+        let mut path = operation.path.clone();
+        let mut query = HashMap::new();
+        let mut headers = HashMap::new();
+        let mut cookies = Vec::new();
+        let mut body = None;
+
+        for param in &operation.parameters {
+            let Some(value) = parameters.get(&param.name).filter(|value| !value.is_null()) else {
+                if param.required {
+                    return Err(format!(
+                        "missing required parameter '{}' for operation '{}'",
+                        param.name, self.operation_id
+                    ).into());
+                }
+                continue;
+            };
+            match param.location.as_str() {
+                "path" => {
+                    path = path.replace(&format!("{{{}}}", param.name), &encode_component(&value_to_string(value)));
+                }
+                "query" => {
+                    query.insert(param.name.clone(), value_to_string(value));
+                }
+                "header" => {
+                    headers.insert(param.name.clone(), value_to_string(value));
+                }
+                "cookie" => cookies.push(format!("{}={}", param.name, value_to_string(value))),
+                "body" => body = Some(value.clone()),
+                other => return Err(format!("unsupported parameter location '{other}'").into()),
+            }
+        }
+        if !cookies.is_empty() {
+            headers.insert("cookie".into(), cookies.join("; "));
+        }
+        if operation.has_body && body.is_none() {
+            body = parameters.get("body").cloned();
+        }
+
+        Ok(HTTPNode {
+            endpoint: format!("{}{}", operation.base_url.trim_end_matches('/'), path),
+            method: operation
+                .method
+                .to_uppercase()
+                .parse()
+                .map_err(|e| format!("invalid http method '{}': {e}", operation.method))?,
+            headers,
+            query,
+            body,
+            output: self.output,
+            redirect: self.redirect,
+            accept_status: None,
+            authentication: self.authentication.clone(),
+        })
+    }
+}
+
+/// Finds `operation_id` in an OpenAPI 3 or Swagger 2 document.
+pub fn resolve_operation(
+    document: &Value,
+    operation_id: &str,
+    document_uri: Option<&str>,
+) -> StepResult<OpenApiOperation> {
+    let paths = document
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| "openapi document has no `paths`".to_string())?;
+
+    for (path, item) in paths {
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            if operation.get("operationId").and_then(Value::as_str) != Some(operation_id) {
+                continue;
+            }
+
+            // Operation-level parameters override path-level ones with the same name and location.
+            let mut parameters: Vec<OpenApiParameter> = Vec::new();
+            for param in [item.get("parameters"), operation.get("parameters")]
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_array)
+                .flatten()
+            {
+                let param = resolve_ref(document, param);
+                let (Some(name), Some(location)) = (
+                    param.get("name").and_then(Value::as_str),
+                    param.get("in").and_then(Value::as_str),
+                ) else {
+                    continue;
+                };
+                let param = OpenApiParameter {
+                    name: name.to_string(),
+                    location: location.to_string(),
+                    required: param.get("required").and_then(Value::as_bool).unwrap_or(location == "path"),
+                };
+                parameters.retain(|p| p.name != param.name || p.location != param.location);
+                parameters.push(param);
+            }
+
+            return Ok(OpenApiOperation {
+                method: method.to_string(),
+                base_url: base_url(document, document_uri)?,
+                path: path.clone(),
+                parameters,
+                has_body: operation.get("requestBody").is_some(),
+            });
+        }
+    }
+
+    Err(format!("operation '{operation_id}' not found in openapi document").into())
+}
+
+fn resolve_ref<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+    match value.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference
+            .strip_prefix('#')
+            .and_then(|pointer| document.pointer(pointer))
+            .unwrap_or(value),
+        None => value,
+    }
+}
+
+fn base_url(document: &Value, document_uri: Option<&str>) -> StepResult<String> {
+    let declared = if let Some(server) = document
+        .get("servers")
+        .and_then(Value::as_array)
+        .and_then(|servers| servers.first())
+        .and_then(|server| server.get("url"))
+        .and_then(Value::as_str)
+    {
+        server.to_string()
+    } else if let Some(host) = document.get("host").and_then(Value::as_str) {
+        let scheme = document
+            .get("schemes")
+            .and_then(Value::as_array)
+            .and_then(|schemes| {
+                schemes
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .find(|s| *s == "https")
+                    .or_else(|| schemes.first().and_then(Value::as_str))
+            })
+            .unwrap_or("https");
+        let base_path = document.get("basePath").and_then(Value::as_str).unwrap_or("");
+        format!("{scheme}://{host}{base_path}")
+    } else {
+        document
+            .get("basePath")
+            .and_then(Value::as_str)
+            .unwrap_or("/")
+            .to_string()
+    };
+
+    if reqwest::Url::parse(&declared).is_ok() {
+        return Ok(declared);
+    }
+    let document_uri =
+        document_uri.ok_or_else(|| format!("cannot resolve relative server url '{declared}' of an inline document"))?;
+    reqwest::Url::parse(document_uri)
+        .and_then(|uri| uri.join(&declared))
+        .map(|url| url.to_string())
+        .map_err(|e| format!("invalid openapi server url '{declared}': {e}").into())
+}
+
+/// Fetches a JSON or YAML API description, as [`fetch`] does with the `authentication` of the call, and parses
+/// it on the context's blocking pool. `kind` names the document in errors, e.g. `openapi`.
+pub(crate) async fn fetch_document(
+    ctx: &WorkflowContext,
+    uri: &str,
+    authentication: Option<&AuthenticationRef>,
+    kind: &str,
+) -> StepResult<Value> {
+    let bytes = fetch(ctx, uri, authentication, &format!("{kind} document")).await?;
+    let parsed = ctx
+        .blocking
+        .run(move || serde_json::from_slice(&bytes).or_else(|_| serde_yaml::from_slice(&bytes)))
+        .await?;
+    parsed.map_err(|e| format!("invalid {kind} document '{uri}': {e}").into())
+}
+
+impl TryFrom<&TaskDefinition> for OpenApiNode {
+    type Error = StepError;
+
+    fn try_from(task: &TaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_task(task)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for OpenApiNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let document = self.document(ctx).await?;
+        let operation = resolve_operation(document, &self.operation_id, self.document_uri())?;
+        let parameters = match resolve_with(&Value::Object(self.parameters.clone()), &input, &ctx.vars())? {
+            Value::Object(parameters) => parameters,
+            _ => Map::new(),
+        };
+        self.build_http(&operation, &parameters)?.send_request(ctx, &input).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::nodes::testing::serve;
+    use crate::runtime::http_client::HttpBodyLimits;
+
+    fn petstore(server: &str) -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "servers": [{ "url": server }],
+            "paths": {
+                "/pet/{petId}": {
+                    "parameters": [{ "name": "petId", "in": "path", "required": true }],
+                    "get": {
+                        "operationId": "getPetById",
+                        "parameters": [
+                            { "name": "verbose", "in": "query" },
+                            { "$ref": "#/components/parameters/Tenant" }
+                        ]
+                    }
+                },
+                "/pet": {
+                    "post": { "operationId": "addPet", "requestBody": {} }
+                }
+            },
+            "components": {
+                "parameters": { "Tenant": { "name": "X-Tenant", "in": "header" } }
+            }
+        })
+    }
+
+    fn call(with: Value) -> CallTaskDefinition {
+        let with = with.as_object().unwrap().clone().into_iter().collect();
+        CallTaskDefinition::new("openapi", Some(with), None)
+    }
+
+    #[test]
+    fn resolve_operation_collects_parameters() {
+        let operation = resolve_operation(&petstore("https://api.example.com/v1"), "getPetById", None).unwrap();
+
+        assert_eq!(operation.method, "get");
+        assert_eq!(operation.base_url, "https://api.example.com/v1");
+        assert_eq!(operation.path, "/pet/{petId}");
+        let names: Vec<_> = operation.parameters.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["petId", "verbose", "X-Tenant"]);
+        assert!(!operation.has_body);
+    }
+
+    #[test]
+    fn resolve_swagger2_base_url() {
+        let doc = json!({
+            "swagger": "2.0",
+            "host": "petstore.swagger.io",
+            "basePath": "/v2",
+            "schemes": ["http", "https"],
+            "paths": { "/pet": { "post": { "operationId": "addPet" } } }
+        });
+        let operation = resolve_operation(&doc, "addPet", None).unwrap();
+        assert_eq!(operation.base_url, "https://petstore.swagger.io/v2");
+    }
+
+    #[test]
+    fn missing_required_parameter_is_rejected() {
+        let node = OpenApiNode::try_from_openapi(&call(json!({
+            "document": { "content": petstore("https://api.example.com") },
+            "operationId": "getPetById",
+        })))
+        .unwrap();
+        let operation = resolve_operation(&petstore("https://api.example.com"), "getPetById", None).unwrap();
+
+        let err = node.build_http(&operation, &node.parameters).unwrap_err();
+        assert!(err.contains("petId"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn openapi_call_fetches_document_and_executes() {
+        let server = serve(vec![
+            ("200 OK", "application/json", petstore("/").to_string()),
+            ("200 OK", "application/json", r#"{"id":7}"#.to_string()),
+            ("200 OK", "application/json", petstore("/").to_string()),
+        ]);
+        let with = json!({
+            "document": { "endpoint": format!("{}/openapi.json", server.url) },
+            "operationId": "getPetById",
+            "parameters": { "petId": "${ .id }", "verbose": true, "X-Tenant": "${ .tenant }" },
+            "authentication": { "bearer": { "token": "t0k" } },
+        });
+        let node = OpenApiNode::try_from_openapi(&call(with.clone())).unwrap();
+
+        let output = node.execute(&WorkflowContext::default(), json!({ "id": 7, "tenant": "acme" })).await.unwrap();
+
+        assert_eq!(output, json!({ "id": 7 }));
+        let document_request = server.next_request();
+        assert!(document_request.starts_with("GET /openapi.json "), "{document_request}");
+        assert!(document_request.contains("authorization: Bearer t0k"), "{document_request}");
+        let request = server.next_request();
+        assert!(request.starts_with("GET /pet/7?verbose=true "), "{request}");
+        assert!(request.to_lowercase().contains("x-tenant: acme"), "{request}");
+
+        // Documents are read within the body limit of calls.
+        let ctx = WorkflowContext::default().with_http_body_limits(HttpBodyLimits {
+            max_bytes: Some(64),
+            ..Default::default()
+        });
+        let node = OpenApiNode::try_from_openapi(&call(with)).unwrap();
+        let err = node.execute(&ctx, json!({ "id": 7 })).await.unwrap_err();
+        assert!(err.contains("openapi document") && err.contains("exceeds the limit of 64 bytes"), "{err}");
+    }
+}
@@ -0,0 +1,189 @@
+use serde_json::Value;
+use serverless_workflow_core::models::task::TaskDefinitionFields;
+
+use crate::nodes::doing::BoxedTask;
+use crate::runtime::expr::resolve_with;
+use crate::runtime::schema::Schema;
+use crate::runtime::{StepResult, Task, WorkflowContext};
+
+/// Applies the data flow declared on a task definition around the task itself.
+///
+/// In order: the raw input is checked against `input.schema` and transformed by `input.from`; the task runs;
+/// its output is transformed by `output.as` and checked against `output.schema`; finally `export.as`,
+/// evaluated against that output, replaces the instance's `$context`.
+/// Expressions can read `$context` and `$input` (the transformed task input).
+pub struct PipelineNode {
+    inner: BoxedTask,
+    input_schema: Option<Schema>,
+    from: Option<Value>,
+    as_: Option<Value>,
+    output_schema: Option<Schema>,
+    export: Option<Value>,
+    export_schema: Option<Schema>,
+}
+
+impl PipelineNode {
+    /// Wraps `inner` when its definition declares a schema or a transformation, and returns it unchanged otherwise.
+    pub fn wrap(inner: BoxedTask, fields: &TaskDefinitionFields) -> StepResult<BoxedTask> {
+        let compile = |schema, what: &str| match schema {
+            Some(schema) => Schema::compile(schema).map(Some).map_err(|e| format!("{what} {e}")),
+            None => Ok(None),
+        };
+        let input = fields.input.as_ref();
+        let output = fields.output.as_ref();
+        let export = fields.export.as_ref();
+        let node = Self {
+            inner,
+            input_schema: compile(input.and_then(|i| i.schema.as_ref()), "input")?,
+            from: input.and_then(|i| i.from.clone()),
+            as_: output.and_then(|o| o.as_.clone()),
+            output_schema: compile(output.and_then(|o| o.schema.as_ref()), "output")?,
+            export: export.and_then(|e| e.as_.clone()),
+            export_schema: compile(export.and_then(|e| e.schema.as_ref()), "export")?,
+        };
+        if node.is_passthrough() {
+            return Ok(node.inner);
+        }
+        Ok(Box::new(node))
+    }
+
+    fn is_passthrough(&self) -> bool {
+        self.input_schema.is_none()
+            && self.from.is_none()
+            && self.as_.is_none()
+            && self.output_schema.is_none()
+            && self.export.is_none()
+    }
+}
+
+impl std::fmt::Debug for PipelineNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PipelineNode")
+            .field("from", &self.from)
+            .field("as", &self.as_)
+            .field("export", &self.export)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for PipelineNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        if let Some(schema) = &self.input_schema {
+            schema.check(&input, "task input")?;
+        }
+        let mut vars = ctx.vars();
+        let input = match &self.from {
+            Some(from) => resolve_with(from, &input, &vars).map_err(|e| format!("input.from: {e}"))?,
+            None => input,
+        };
+        vars.insert("$input".to_string(), input.clone());
+
+        let output = self.inner.execute(ctx, input).await?;
+        let output = match &self.as_ {
+            Some(as_) => resolve_with(as_, &output, &vars).map_err(|e| format!("output.as: {e}"))?,
+            None => output,
+        };
+        if let Some(schema) = &self.output_schema {
+            schema.check(&output, "task output")?;
+        }
+
+        if let Some(export) = &self.export {
+            vars.insert("$context".to_string(), ctx.context.get());
+            let exported = resolve_with(export, &output, &vars).map_err(|e| format!("export.as: {e}"))?;
+            if let Some(schema) = &self.export_schema {
+                schema.check(&exported, "exported context")?;
+            }
+            ctx.context.set(exported);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serverless_workflow_core::models::task::DoTaskDefinition;
+
+    use crate::Workflow;
+    use crate::nodes::doing::DoNode;
+    use crate::nodes::testing::value_context;
+    use crate::runtime::{Task, WorkflowContext};
+
+    fn do_node(yaml: &str) -> DoNode {
+        let def: DoTaskDefinition = serde_yaml::from_str(yaml).unwrap();
+        DoNode::try_from_definition(&def).unwrap()
+    }
+
+    #[tokio::test]
+    async fn task_schemas_are_checked() {
+        let node = do_node(
+            r#"
+do:
+  - echo:
+      input:
+        schema:
+          document:
+            type: object
+            required: [name]
+      output:
+        schema:
+          document:
+            type: integer
+      run:
+        shell:
+          command: echo
+          arguments: ["${ .name }"]
+"#,
+        );
+        let ctx = WorkflowContext::default();
+
+        let err = node.execute(&ctx, json!({})).await.unwrap_err();
+        assert!(err.contains("task input does not match its schema"), "{err}");
+        let err = node.execute(&ctx, json!({ "name": "bob" })).await.unwrap_err();
+        assert!(err.contains("task output does not match its schema"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn transformations_and_context_export() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: greet
+  version: '1.0.0'
+do:
+  - greet:
+      input:
+        from: ${ .customer }
+      output:
+        as:
+          greeting: ${ . }
+          customer: ${ $input.name }
+      export:
+        as:
+          lastCustomer: ${ .customer }
+      run:
+        shell:
+          command: echo
+          arguments: ["${ .name }"]
+  - remember:
+      call: value
+      with:
+        lastCustomer: ${ $context.lastCustomer }
+"#,
+        )
+        .unwrap();
+        let ctx = value_context().with_context(json!({ "tenant": "acme" }));
+
+        let output = workflow.run(&ctx, json!({ "customer": { "name": "ada" } })).await.unwrap();
+
+        assert_eq!(output, json!({ "lastCustomer": "ada" }));
+        // The export is the instance's own: the host's context, and the next instances, do not see it.
+        assert_eq!(ctx.context.get(), json!({ "tenant": "acme" }));
+    }
+}
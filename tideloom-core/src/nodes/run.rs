@@ -0,0 +1,701 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+
+use serde_json::{Map, Value, json};
+use serverless_workflow_core::models::resource::OneOfEndpointDefinitionOrUri;
+use serverless_workflow_core::models::task::{
+    ContainerProcessDefinition, RunTaskDefinition, ScriptProcessDefinition, ShellProcessDefinition, TaskDefinition,
+    WorkflowProcessDefinition,
+};
+use tokio::process::Command;
+
+use crate::extensions::{DETACHED, ON_CHILD_FAULT, extension};
+use crate::nodes::http::fetch;
+use crate::nodes::workflow::WorkflowNode;
+use crate::runtime::auth::AuthenticationRef;
+use crate::runtime::expr::{interpolate_with, resolve_with, value_to_string};
+use crate::runtime::{CancellationToken, ContextData, StepError, StepResult, Task, WorkflowContext, workflow_key};
+
+/// The process started by a `run` task.
+#[derive(Debug, Clone)]
+pub enum Process {
+    Shell(ShellProcessDefinition),
+    Script(Box<ScriptProcessDefinition>),
+    Container(ContainerProcessDefinition),
+    Workflow(WorkflowProcessDefinition),
+}
+
+/// What a `run: workflow` task does when its child instance faults, as set by `metadata.tideloom.onChildFault`.
+#[derive(Debug, Clone, Default)]
+pub enum ChildFault {
+    /// Fail the task with the child's error.
+    #[default]
+    Propagate,
+    /// Output `{ "error": ... }` instead of the child's result.
+    Ignore,
+    /// Run the registered compensation workflow with `{ "input": ..., "error": ... }`, then fail the task.
+    Compensate(WorkflowProcessDefinition),
+}
+
+impl ChildFault {
+    fn try_from_value(value: &Value) -> StepResult<Self> {
+        match value {
+            Value::String(mode) if mode == "propagate" => Ok(Self::Propagate),
+            Value::String(mode) if mode == "ignore" => Ok(Self::Ignore),
+            Value::Object(map) if map.len() == 1 && map.contains_key("compensate") => {
+                serde_json::from_value(map["compensate"].clone())
+                    .map(Self::Compensate)
+                    .map_err(|e| format!("invalid onChildFault compensation workflow: {e}").into())
+            }
+            other => Err(format!("unsupported onChildFault mode {other}").into()),
+        }
+    }
+}
+
+/// `run` task: starts a shell command, a script, a container or a registered sub-workflow.
+///
+/// Shell commands are command lines run with `sh -c` (`cmd /C` on Windows), with the task's `arguments` appended.
+/// Expressions embedded in the command, as in `echo "Hello, ${ .user.name }"`, are replaced by their values as
+/// is, so the shell interprets them; untrusted values belong in `arguments` or `environment`.
+/// Processes output their stdout, with a trailing newline removed; a non-zero exit code fails the task
+/// with the captured stderr. Sub-workflows output the result of their `do` block; they run as child
+/// instances, linked to their parent through [`InstanceInfo::parent`](crate::runtime::metering::InstanceInfo),
+/// with a `$context` of their own. When `await` is false the process is started in the background and the
+/// task passes its input through.
+///
+/// Cancelling a parent instance cancels its children, including non-awaited ones that are still running; a
+/// faulted child is handled per [`ChildFault`], whether awaited or not.
+///
+/// With `metadata.tideloom.detached: true`, a sub-workflow is started without any lifetime coupling: the task outputs
+/// the child's instance id right away, and the child neither follows the parent's cancellation nor reports
+/// its faults to it. The child still records its parent.
+#[derive(Debug, Clone)]
+pub struct RunNode {
+    process: Process,
+    await_: bool,
+    detached: bool,
+    on_child_fault: ChildFault,
+}
+
+impl RunNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Run(run) => Self::try_from_run(run),
+            _ => Err("RunNode expects a `run` task definition".into()),
+        }
+    }
+
+    pub fn try_from_run(run: &RunTaskDefinition) -> StepResult<Self> {
+        let def = &run.run;
+        let process = match (&def.shell, &def.script, &def.container, &def.workflow) {
+            (Some(shell), None, None, None) => Process::Shell(shell.clone()),
+            (None, Some(script), None, None) => {
+                interpreter(&script.language)?;
+                if script.code.is_none() && script.source.is_none() {
+                    return Err("run script requires `code` or a `source`".into());
+                }
+                Process::Script(Box::new(script.clone()))
+            }
+            (None, None, Some(container), None) => {
+                if !cfg!(feature = "container") {
+                    return Err("run container requires the `container` feature".into());
+                }
+                Process::Container(container.clone())
+            }
+            (None, None, None, Some(workflow)) => Process::Workflow(workflow.clone()),
+            (None, None, None, None) => return Err("run task requires a process to run".into()),
+            _ => return Err("run task must define exactly one process".into()),
+        };
+        let on_child_fault = match extension(&run.common, ON_CHILD_FAULT) {
+            None => ChildFault::default(),
+            Some(_) if !matches!(process, Process::Workflow(_)) => {
+                return Err("`onChildFault` is only supported on `run: workflow` tasks".into());
+            }
+            Some(value) => ChildFault::try_from_value(value)?,
+        };
+        let detached = match extension(&run.common, DETACHED) {
+            None | Some(Value::Bool(false)) => false,
+            Some(Value::Bool(true)) if !matches!(process, Process::Workflow(_)) => {
+                return Err("`detached` is only supported on `run: workflow` tasks".into());
+            }
+            Some(Value::Bool(true))
+                if def.await_ == Some(true) || extension(&run.common, ON_CHILD_FAULT).is_some() =>
+            {
+                return Err("a detached sub-workflow cannot be awaited or handle child faults".into());
+            }
+            Some(Value::Bool(true)) => true,
+            Some(other) => return Err(format!("`detached` must be a boolean, got {other}").into()),
+        };
+        Ok(Self {
+            process,
+            await_: def.await_.unwrap_or(true),
+            detached,
+            on_child_fault,
+        })
+    }
+
+    async fn run_shell(
+        &self,
+        ctx: &WorkflowContext,
+        shell: &ShellProcessDefinition,
+        input: Value,
+    ) -> StepResult<Value> {
+        let vars = ctx.vars();
+        let command_line = interpolate_with(&shell.command, &input, &vars);
+        let arguments = shell
+            .arguments
+            .iter()
+            .flatten()
+            .map(|arg| resolve_str(arg, &input, &vars))
+            .collect::<StepResult<Vec<_>>>()?;
+        let mut command = shell_command(&command_line, &arguments);
+        sandbox(&mut command, ctx, shell.environment.as_ref(), &input, &vars)?;
+        self.spawn(command, input).await
+    }
+
+    async fn run_script(
+        &self,
+        ctx: &WorkflowContext,
+        script: &ScriptProcessDefinition,
+        input: Value,
+    ) -> StepResult<Value> {
+        let code = match (&script.code, &script.source) {
+            (Some(code), _) => code.clone(),
+            (None, Some(source)) => {
+                let (uri, authentication) = match &source.endpoint {
+                    OneOfEndpointDefinitionOrUri::Uri(uri) => (uri, None),
+                    OneOfEndpointDefinitionOrUri::Endpoint(endpoint) => (
+                        &endpoint.uri,
+                        endpoint.authentication.as_ref().map(AuthenticationRef::try_from_definition).transpose()?,
+                    ),
+                };
+                let script = fetch(ctx, uri, authentication.as_ref(), "script").await?;
+                String::from_utf8(script).map_err(|_| format!("script '{uri}' is not valid UTF-8"))?
+            }
+            (None, None) => return Err("run script requires `code` or a `source`".into()),
+        };
+        let (program, flag) = interpreter(&script.language)?;
+        let mut command = Command::new(program);
+        command.arg(flag).arg(code);
+        let vars = ctx.vars();
+        sandbox(&mut command, ctx, script.environment.as_ref(), &input, &vars)?;
+        // Script arguments are exposed to the script as environment variables.
+        for (name, value) in script.arguments.iter().flatten() {
+            command.env(name, resolve_str(value, &input, &vars)?);
+        }
+        self.spawn(command, input).await
+    }
+
+    #[cfg(feature = "container")]
+    async fn run_container(
+        &self,
+        ctx: &WorkflowContext,
+        container: &ContainerProcessDefinition,
+        input: Value,
+    ) -> StepResult<Value> {
+        let vars = ctx.vars();
+        let mut command = Command::new("docker");
+        command.args(["run", "--rm"]);
+        if let Some(name) = &container.name {
+            command.args(["--name", &resolve_str(name, &input, &vars)?]);
+        }
+        for (name, value) in container.environment.iter().flatten() {
+            command.arg("-e").arg(format!("{name}={}", resolve_str(value, &input, &vars)?));
+        }
+        for (host, port) in container.ports.iter().flatten() {
+            command.arg("-p").arg(format!("{host}:{port}"));
+        }
+        for (host, path) in container.volumes.iter().flatten() {
+            command.arg("-v").arg(format!("{host}:{path}"));
+        }
+        command.arg(resolve_str(&container.image, &input, &vars)?);
+        if let Some(cmd) = &container.command {
+            command.args(resolve_str(cmd, &input, &vars)?.split_whitespace());
+        }
+        self.spawn(command, input).await
+    }
+
+    #[cfg(not(feature = "container"))]
+    async fn run_container(
+        &self,
+        _ctx: &WorkflowContext,
+        _container: &ContainerProcessDefinition,
+        _input: Value,
+    ) -> StepResult<Value> {
+        Err("run container requires the `container` feature".into())
+    }
+
+    async fn run_workflow(
+        &self,
+        ctx: &WorkflowContext,
+        workflow: &WorkflowProcessDefinition,
+        input: Value,
+    ) -> StepResult<Value> {
+        let (key, node) = child_workflow(ctx, workflow)?;
+        let input = match &workflow.input {
+            Some(sub_input) => resolve_with(sub_input, &input, &ctx.vars())?,
+            None => input,
+        };
+        // The child starts from its own `$context` and scope; it shares the parent's services, and is cancelled
+        // with the parent unless detached.
+        let ctx = WorkflowContext {
+            context: ContextData::default(),
+            scope: Map::new(),
+            cancellation: match self.detached {
+                true => CancellationToken::new(),
+                false => ctx.cancellation.child_token(),
+            },
+            ..ctx.clone()
+        };
+        if self.detached {
+            let id = uuid::Uuid::new_v4().to_string();
+            let instance = id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = node.execute_as(&ctx, id, input).await {
+                    tracing::warn!(workflow = %key, error = %e, "detached sub-workflow faulted");
+                }
+            });
+            return Ok(Value::String(instance));
+        }
+        if self.await_ {
+            return match node.execute(&ctx, input.clone()).await {
+                Ok(output) => Ok(output),
+                Err(e) => self.on_fault(&ctx, &key, input, e).await,
+            };
+        }
+        let task = self.clone();
+        let passthrough = input.clone();
+        tokio::spawn(async move {
+            if let Err(e) = node.execute(&ctx, input.clone()).await
+                && let Err(e) = task.on_fault(&ctx, &key, input, e).await
+            {
+                tracing::warn!(workflow = %key, error = %e, "non-awaited sub-workflow faulted");
+            }
+        });
+        Ok(passthrough)
+    }
+
+    /// Applies the task's [`ChildFault`] policy to the fault `error` of the child `key` started with `input`.
+    async fn on_fault(&self, ctx: &WorkflowContext, key: &str, input: Value, error: StepError) -> StepResult<Value> {
+        let error = error.map_message(|e| format!("workflow '{key}' failed: {e}"));
+        match &self.on_child_fault {
+            ChildFault::Propagate => Err(error),
+            ChildFault::Ignore => Ok(json!({ "error": error.message() })),
+            ChildFault::Compensate(workflow) => {
+                let (compensation, node) = child_workflow(ctx, workflow)?;
+                match node.execute(ctx, json!({ "input": input, "error": error.message() })).await {
+                    Ok(_) => Err(error.map_message(|e| format!("{e}; compensated by '{compensation}'"))),
+                    Err(failure) => {
+                        Err(error.map_message(|e| format!("{e}; compensation '{compensation}' failed: {failure}")))
+                    }
+                }
+            }
+        }
+    }
+
+    async fn spawn(&self, mut command: Command, input: Value) -> StepResult<Value> {
+        command.stdin(Stdio::null());
+        if !self.await_ {
+            command.stdout(Stdio::null()).stderr(Stdio::null());
+            let mut child = command.spawn().map_err(|e| format!("failed to start process: {e}"))?;
+            tokio::spawn(async move { child.wait().await });
+            return Ok(input);
+        }
+        // Killed if the task is cancelled, e.g. when it loses a competing fork.
+        command.kill_on_drop(true);
+        let output = command
+            .output()
+            .await
+            .map_err(|e| format!("failed to start process: {e}"))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match output.status.code() {
+            Some(0) => Ok(Value::String(trim_newline(&stdout).to_string())),
+            Some(code) => Err(format!("process exited with code {code}: {}", stderr.trim()).into()),
+            None => Err(format!("process was terminated by a signal: {}", stderr.trim()).into()),
+        }
+    }
+}
+
+/// Looks up the registered workflow started by `workflow`, returning its key and node.
+fn child_workflow(ctx: &WorkflowContext, workflow: &WorkflowProcessDefinition) -> StepResult<(String, WorkflowNode)> {
+    let key = workflow_key(&workflow.namespace, &workflow.name, &workflow.version);
+    let definition = ctx
+        .workflows
+        .get(&key)
+        .ok_or_else(|| format!("workflow '{key}' is not registered in the workflow context"))?;
+    Ok((key, WorkflowNode::try_from_definition(definition)?))
+}
+
+/// Runs `command_line` with the platform shell, followed by `arguments`.
+#[cfg(not(windows))]
+fn shell_command(command_line: &str, arguments: &[String]) -> Command {
+    let mut command = Command::new("sh");
+    if arguments.is_empty() {
+        command.arg("-c").arg(command_line);
+    } else {
+        // `"$@"` appends the arguments as words of their own, without the shell splitting or expanding them.
+        command.arg("-c").arg(format!("{command_line} \"$@\"")).arg("sh").args(arguments);
+    }
+    command
+}
+
+/// Runs `command_line` with the platform shell, followed by `arguments`.
+#[cfg(windows)]
+fn shell_command(command_line: &str, arguments: &[String]) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(command_line).args(arguments);
+    command
+}
+
+/// Maps a script language to its interpreter and the flag that passes inline code.
+fn interpreter(language: &str) -> StepResult<(&'static str, &'static str)> {
+    match language.to_ascii_lowercase().as_str() {
+        "js" | "javascript" => Ok(("node", "-e")),
+        "python" => Ok(("python3", "-c")),
+        other => Err(format!("unsupported script language '{other}'").into()),
+    }
+}
+
+/// Clears the environment down to what the context allows and adds the task's own variables.
+fn sandbox(
+    command: &mut Command,
+    ctx: &WorkflowContext,
+    environment: Option<&HashMap<String, String>>,
+    input: &Value,
+    vars: &Map<String, Value>,
+) -> StepResult<()> {
+    command.env_clear().envs(ctx.process_env.host_vars());
+    for (name, value) in environment.into_iter().flatten() {
+        command.env(name, resolve_str(value, input, vars)?);
+    }
+    Ok(())
+}
+
+fn resolve_str(value: &str, input: &Value, vars: &Map<String, Value>) -> StepResult<String> {
+    Ok(value_to_string(&resolve_with(&Value::String(value.to_string()), input, vars)?))
+}
+
+fn trim_newline(s: &str) -> &str {
+    s.strip_suffix('\n').map(|s| s.strip_suffix('\r').unwrap_or(s)).unwrap_or(s)
+}
+
+impl TryFrom<&TaskDefinition> for RunNode {
+    type Error = StepError;
+
+    fn try_from(task: &TaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_task(task)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for RunNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        match &self.process {
+            Process::Shell(shell) => self.run_shell(ctx, shell, input).await,
+            Process::Script(script) => self.run_script(ctx, script, input).await,
+            Process::Container(container) => self.run_container(ctx, container, input).await,
+            Process::Workflow(workflow) => self.run_workflow(ctx, workflow, input).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::runtime::ProcessEnvironment;
+    use crate::runtime::metering::{Meter, Usage};
+
+    fn run_node(yaml: &str) -> StepResult<RunNode> {
+        let task: TaskDefinition = serde_yaml::from_str(yaml).expect("invalid yaml");
+        RunNode::try_from_task(&task)
+    }
+
+    #[tokio::test]
+    async fn run_shell_captures_stdout() {
+        let node = run_node(
+            r#"
+run:
+  shell:
+    command: sh
+    arguments: ["-c", "echo $GREETING $0", "${ .name }"]
+    environment:
+      GREETING: hello
+"#,
+        )
+        .unwrap();
+        let output = node.execute(&WorkflowContext::default(), json!({ "name": "world" })).await.unwrap();
+        assert_eq!(output, json!("hello world"));
+    }
+
+    #[tokio::test]
+    async fn run_shell_runs_command_lines() {
+        let node = run_node(
+            r#"
+run:
+  shell:
+    command: 'printf "%s, %s!" "Hello" "$NAME"'
+    environment:
+      NAME: ${ .user.name }
+"#,
+        )
+        .unwrap();
+        let output = node.execute(&WorkflowContext::default(), json!({ "user": { "name": "Ada L" } })).await.unwrap();
+        assert_eq!(output, json!("Hello, Ada L!"));
+
+        let node = run_node("run:\n  shell:\n    command: printf '%s|'\n    arguments: ['a b', '$HOME', '${ .x }']\n")
+            .unwrap();
+        let output = node.execute(&WorkflowContext::default(), json!({ "x": "it's" })).await.unwrap();
+        assert_eq!(output, json!("a b|$HOME|it's|"));
+
+        let node = run_node("run:\n  shell:\n    command: echo \"Hello, ${ .user.name }\"\n").unwrap();
+        let output = node.execute(&WorkflowContext::default(), json!({ "user": { "name": "Ada" } })).await.unwrap();
+        assert_eq!(output, json!("Hello, Ada"));
+    }
+
+    #[tokio::test]
+    async fn run_shell_reads_seeded_context() {
+        let node = run_node("run:\n  shell:\n    command: echo\n    arguments: ['${ $context.tenant }']\n").unwrap();
+        let ctx = WorkflowContext::default().with_context(json!({ "tenant": "acme" }));
+        assert_eq!(node.execute(&ctx, json!({})).await.unwrap(), json!("acme"));
+    }
+
+    #[tokio::test]
+    async fn run_shell_maps_exit_code_to_error() {
+        let node = run_node(
+            "run:\n  shell:\n    command: sh\n    arguments: ['-c', 'echo boom >&2; exit 3']\n",
+        )
+        .unwrap();
+        let err = node.execute(&WorkflowContext::default(), json!({})).await.unwrap_err();
+        assert_eq!(err, "process exited with code 3: boom");
+    }
+
+    #[tokio::test]
+    async fn run_shell_sandboxes_host_environment() {
+        let node = run_node("run:\n  shell:\n    command: sh\n    arguments: ['-c', 'echo ${HOME:-unset}']\n").unwrap();
+
+        let sandboxed = node.execute(&WorkflowContext::default(), json!({})).await.unwrap();
+        assert_eq!(sandboxed, json!("unset"));
+
+        let ctx = WorkflowContext::default().with_process_env(ProcessEnvironment::inherit());
+        let inherited = node.execute(&ctx, json!({})).await.unwrap();
+        assert_eq!(inherited, json!(std::env::var("HOME").unwrap_or_else(|_| "unset".into())));
+    }
+
+    #[tokio::test]
+    async fn run_workflow_executes_registered_definition() {
+        let child = serde_yaml::from_str(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: child
+  version: '1.0.0'
+do:
+  - echo:
+      run:
+        shell:
+          command: echo
+          arguments: ["${ .id }"]
+"#,
+        )
+        .unwrap();
+        let ctx = WorkflowContext::default().with_workflow(child);
+        let node = run_node(
+            "run:\n  workflow:\n    namespace: test\n    name: child\n    version: '1.0.0'\n    input:\n      id: ${ .order }\n",
+        )
+        .unwrap();
+
+        let output = node.execute(&ctx, json!({ "order": "o-1" })).await.unwrap();
+        assert_eq!(output, json!("o-1"));
+
+        let missing = run_node(
+            "run:\n  workflow:\n    namespace: test\n    name: other\n    version: '1.0.0'\n",
+        )
+        .unwrap();
+        let err = missing.execute(&ctx, json!({})).await.unwrap_err();
+        assert!(err.contains("test/other:1.0.0"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn child_faults_follow_the_task_policy() {
+        let workflow = |name: &str, script: &str| {
+            serde_yaml::from_str::<serverless_workflow_core::models::workflow::WorkflowDefinition>(&format!(
+                "document:\n  dsl: '1.0.0'\n  namespace: test\n  name: {name}\n  version: '1.0.0'\n\
+                 do:\n- step:\n    run:\n      shell:\n        command: sh\n        arguments: ['-c', '{script}']\n"
+            ))
+            .unwrap()
+        };
+        let ctx = WorkflowContext::default()
+            .with_workflow(workflow("charge", "echo declined >&2; exit 1"))
+            .with_workflow(workflow("refund", "true"));
+        let charge = |policy: &str| {
+            run_node(&format!(
+                "metadata:\n  tideloom:\n    onChildFault: {policy}\n\
+                 run:\n  workflow:\n    namespace: test\n    name: charge\n    version: '1.0.0'\n"
+            ))
+            .unwrap()
+        };
+
+        let err = charge("propagate").execute(&ctx, json!({})).await.unwrap_err();
+        assert!(err.starts_with("workflow 'test/charge:1.0.0' failed:") && err.contains("declined"), "{err}");
+
+        let output = charge("ignore").execute(&ctx, json!({})).await.unwrap();
+        assert!(output["error"].as_str().unwrap().contains("declined"), "{output}");
+
+        let compensate = "{ compensate: { namespace: test, name: refund, version: '1.0.0' } }";
+        let err = charge(compensate).execute(&ctx, json!({})).await.unwrap_err();
+        assert!(err.ends_with("; compensated by 'test/refund:1.0.0'"), "{err}");
+
+        let shell = "metadata:\n  tideloom:\n    onChildFault: ignore\nrun:\n  shell:\n    command: 'true'\n";
+        let shell = run_node(shell).unwrap_err();
+        assert!(shell.contains("only supported on `run: workflow`"), "{shell}");
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_parent_cancels_its_children() {
+        let child = serde_yaml::from_str(
+            "document:\n  dsl: '1.0.0'\n  namespace: test\n  name: slow\n  version: '1.0.0'\n\
+             do:\n- wait:\n    run:\n      shell:\n        command: sleep\n        arguments: ['5']\n",
+        )
+        .unwrap();
+        let token = tokio_util::sync::CancellationToken::new();
+        let ctx = WorkflowContext::default().with_workflow(child).with_cancellation(token.clone());
+        let node = run_node("run:\n  workflow:\n    namespace: test\n    name: slow\n    version: '1.0.0'\n").unwrap();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            token.cancel();
+        });
+        let err = tokio::time::timeout(std::time::Duration::from_secs(2), node.execute(&ctx, json!({})))
+            .await
+            .expect("the child should stop with its parent")
+            .unwrap_err();
+        assert!(err.contains("cancelled"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn detached_children_outlive_their_parent() {
+        use crate::runtime::observer::WorkflowEvent;
+
+        let workflow = |name: &str, tasks: &str| {
+            serde_yaml::from_str::<serverless_workflow_core::models::workflow::WorkflowDefinition>(&format!(
+                "document:\n  dsl: '1.0.0'\n  namespace: test\n  name: {name}\n  version: '1.0.0'\n{tasks}"
+            ))
+            .unwrap()
+        };
+        let child = workflow(
+            "report",
+            "do:\n- build:\n    run:\n      shell:\n        command: sleep\n        arguments: ['0.2']\n",
+        );
+        let parent = workflow(
+            "parent",
+            "do:\n- start:\n    metadata:\n      tideloom:\n        detached: true\n\
+             \x20   run:\n      workflow:\n        namespace: test\n        name: report\n        version: '1.0.0'\n",
+        );
+        let completed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = completed.clone();
+        let token = tokio_util::sync::CancellationToken::new();
+        let ctx = WorkflowContext::default()
+            .with_workflow(child)
+            .with_cancellation(token.clone())
+            .with_observer(Arc::new(move |event: &WorkflowEvent| {
+                if let WorkflowEvent::WorkflowCompleted { instance, .. } = event {
+                    recorded.lock().unwrap().push(instance.clone());
+                }
+            }));
+
+        let id = WorkflowNode::try_from_definition(&parent).unwrap().execute(&ctx, json!({})).await.unwrap();
+        token.cancel();
+        let child = tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            loop {
+                if let Some(child) = completed.lock().unwrap().iter().find(|i| json!(i.id) == id) {
+                    return child.clone();
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("the detached child should complete after its parent was cancelled");
+        assert_eq!(child.parent.unwrap().workflow, "test/parent:1.0.0");
+
+        let awaited = "metadata:\n  tideloom: { detached: true }\n\
+                       run:\n  await: true\n  workflow: { namespace: test, name: report, version: '1' }\n";
+        assert!(run_node(awaited).unwrap_err().contains("cannot be awaited"));
+    }
+
+    #[tokio::test]
+    async fn sub_workflows_run_as_isolated_child_instances() {
+        #[derive(Default)]
+        struct Recorder(std::sync::Mutex<Vec<Usage>>);
+        impl Meter for Recorder {
+            fn record(&self, usage: &Usage) {
+                self.0.lock().unwrap().push(usage.clone());
+            }
+        }
+        let workflow = |name: &str, tasks: &str| {
+            serde_yaml::from_str::<serverless_workflow_core::models::workflow::WorkflowDefinition>(&format!(
+                "document:\n  dsl: '1.0.0'\n  namespace: test\n  name: {name}\n  version: '1.0.0'\n{tasks}"
+            ))
+            .unwrap()
+        };
+        let child = workflow(
+            "child",
+            r#"
+do:
+- mark:
+    run:
+      shell:
+        command: echo
+        arguments: [child]
+    export:
+      as: { by: child }
+"#,
+        );
+        let parent = workflow(
+            "parent",
+            r#"
+do:
+- start:
+    run:
+      shell:
+        command: echo
+        arguments: [parent]
+    export:
+      as: { by: parent }
+- delegate:
+    run:
+      workflow:
+        namespace: test
+        name: child
+        version: '1.0.0'
+output:
+  as: { child: '${ . }', context: '${ $context }' }
+"#,
+        );
+        let recorder = Arc::new(Recorder::default());
+        let ctx = WorkflowContext::default().with_workflow(child).with_meter(recorder.clone());
+
+        let output = WorkflowNode::try_from_definition(&parent).unwrap().execute(&ctx, json!({})).await.unwrap();
+
+        assert_eq!(output, json!({ "child": "child", "context": { "by": "parent" } }));
+        let usages = recorder.0.lock().unwrap();
+        let parent_instance = &usages.iter().find(|u| u.instance.workflow == "test/parent:1.0.0").unwrap().instance;
+        let child_instance = &usages.iter().find(|u| u.instance.workflow == "test/child:1.0.0").unwrap().instance;
+        assert_eq!(parent_instance.parent, None);
+        assert_eq!(child_instance.parent.as_deref(), Some(parent_instance));
+    }
+
+    #[test]
+    fn run_rejects_unknown_script_language() {
+        let err = run_node("run:\n  script:\n    language: ruby\n    code: puts 1\n").unwrap_err();
+        assert!(err.contains("ruby"), "{err}");
+    }
+}
@@ -0,0 +1,302 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Map, Value};
+use serverless_workflow_core::models::task::{SwitchTaskDefinition, TaskDefinition};
+
+use crate::runtime::expr::{holds_with, parse_path};
+use crate::runtime::{StepError, StepResult, Task, WorkflowContext};
+
+/// Where execution continues after a task, as given by `then` or a switch case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowDirective {
+    /// Runs the next task of the enclosing block.
+    Continue,
+    /// Leaves the enclosing block.
+    Exit,
+    /// Ends the workflow.
+    End,
+    /// Jumps to the named task of the enclosing block, then runs the following tasks in order.
+    Goto(String),
+}
+
+impl FromStr for FlowDirective {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "continue" => Ok(Self::Continue),
+            "exit" => Ok(Self::Exit),
+            "end" => Ok(Self::End),
+            "" => Err("flow directive must not be empty".into()),
+            task => Ok(Self::Goto(task.to_string())),
+        }
+    }
+}
+
+/// Directive picked by a `switch` task, left for the `do` block that ran it through
+/// [`WorkflowContext::switched`]. Empty when the switch did not run, e.g. because its `if` did not hold.
+#[derive(Debug, Clone, Default)]
+pub struct SwitchOutcome(Arc<Mutex<Option<FlowDirective>>>);
+
+impl SwitchOutcome {
+    fn set(&self, directive: FlowDirective) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(directive);
+    }
+
+    pub(crate) fn take(&self) -> Option<FlowDirective> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+/// `switch` task: picks a flow directive from the first case whose `when` holds.
+///
+/// Cases are evaluated in declaration order and only the first match applies. A case without `when` is
+/// the default, taken only when no other case matches; without one, an unmatched input fails the task.
+/// `when` is a path expression that must select a boolean; a `null` or missing value counts as `false`.
+/// The task passes its input through unchanged and leaves the directive in the context's
+/// [`switched`](WorkflowContext::switched), for the enclosing `do` block to apply.
+#[derive(Debug, Clone)]
+pub struct SwitchNode {
+    cases: Vec<(String, String, FlowDirective)>,
+    default: Option<FlowDirective>,
+}
+
+impl SwitchNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Switch(switch) => Self::try_from_switch(switch),
+            _ => Err("SwitchNode expects a `switch` task definition".into()),
+        }
+    }
+
+    pub fn try_from_switch(switch: &SwitchTaskDefinition) -> StepResult<Self> {
+        if switch.common.then.is_some() {
+            return Err("switch tasks direct the flow through their cases and do not accept `then`".into());
+        }
+        let mut cases = Vec::new();
+        let mut default = None;
+        for entry in &switch.switch.entries {
+            for (name, case) in entry {
+                let then = case
+                    .then
+                    .as_deref()
+                    .ok_or_else(|| format!("switch case '{name}' requires `then`"))?
+                    .parse()?;
+                match &case.when {
+                    Some(when) => {
+                        parse_path(when).map_err(|e| format!("switch case '{name}': {e}"))?;
+                        cases.push((name.clone(), when.clone(), then));
+                    }
+                    None if default.is_some() => {
+                        return Err(format!("switch case '{name}' is a second default case").into());
+                    }
+                    None => default = Some(then),
+                }
+            }
+        }
+        Ok(Self { cases, default })
+    }
+
+    /// Directive of the first case matching `input`, or of the default case.
+    pub fn evaluate(&self, input: &Value, vars: &Map<String, Value>) -> StepResult<FlowDirective> {
+        let mut matched = None;
+        for case in &self.cases {
+            if holds_with(input, vars, &case.1).map_err(|e| format!("switch case '{}': {e}", case.0))? {
+                matched = Some(case);
+                break;
+            }
+        }
+        match (matched, &self.default) {
+            (Some((_, _, then)), _) => Ok(then.clone()),
+            (None, Some(then)) => Ok(then.clone()),
+            (None, None) => Err("no switch case matched and no default case is defined".into()),
+        }
+    }
+
+    /// Every directive the switch can produce.
+    pub fn directives(&self) -> impl Iterator<Item = &FlowDirective> {
+        self.cases.iter().map(|(_, _, then)| then).chain(&self.default)
+    }
+}
+
+impl TryFrom<&SwitchTaskDefinition> for SwitchNode {
+    type Error = StepError;
+
+    fn try_from(switch: &SwitchTaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_switch(switch)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for SwitchNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        if let Some(outcome) = &ctx.switched {
+            outcome.set(self.evaluate(&input, &ctx.vars())?);
+        }
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::nodes::testing::value_context;
+
+    fn switch(yaml: &str) -> StepResult<SwitchNode> {
+        SwitchNode::try_from_task(&serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn first_matching_case_wins_and_default_applies_last() {
+        let node = switch(
+            r#"
+switch:
+  - fallback:
+      then: exit
+  - rush:
+      when: ${ .rush }
+      then: express
+  - large:
+      when: .large
+      then: freight
+"#,
+        )
+        .unwrap();
+
+        let vars = Map::new();
+        let evaluate = |input| node.evaluate(&input, &vars).unwrap();
+        assert_eq!(evaluate(json!({ "rush": true, "large": true })), FlowDirective::Goto("express".into()));
+        assert_eq!(evaluate(json!({ "rush": false, "large": true })), FlowDirective::Goto("freight".into()));
+        assert_eq!(evaluate(json!({})), FlowDirective::Exit);
+        let err = node.evaluate(&json!({ "large": 1 }), &vars).unwrap_err();
+        assert!(err.contains("expected a boolean"), "{err}");
+
+        let strict = switch("switch:\n  - rush:\n      when: .rush\n      then: continue\n").unwrap();
+        let err = strict.evaluate(&json!({}), &vars).unwrap_err();
+        assert!(err.contains("no switch case matched"), "{err}");
+        assert!(switch("switch:\n  - a:\n      then: exit\n  - b:\n      then: end\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn switch_directs_the_enclosing_block() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: route
+  version: '1.0.0'
+do:
+  - route:
+      switch:
+        - express:
+            when: .rush
+            then: express
+        - standard:
+            then: continue
+  - standard:
+      call: value
+      with:
+        service: ${ .standard }
+      then: end
+  - express:
+      call: value
+      with:
+        service: ${ .express }
+"#,
+        ).unwrap();
+        let ctx = value_context();
+        let input = json!({ "standard": "ground", "express": "air" });
+
+        let output = workflow.run(&ctx, input.clone()).await.unwrap();
+        assert_eq!(output, json!({ "service": "ground" }));
+
+        let mut rush = input;
+        rush["rush"] = json!(true);
+        assert_eq!(workflow.run(&ctx, rush).await.unwrap(), json!({ "service": "air" }));
+    }
+
+    #[tokio::test]
+    async fn end_in_a_nested_block_ends_the_workflow() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: first-urgent
+  version: '1.0.0'
+do:
+  - orders:
+      for:
+        in: .orders
+      do:
+        - pick:
+            call: value
+            with:
+              order: ${ $item }
+        - check:
+            switch:
+              - urgent:
+                  when: .order.urgent
+                  then: end
+              - other:
+                  then: continue
+  - none:
+      call: value
+      with:
+        order: null
+"#,
+        ).unwrap();
+        let ctx = value_context();
+        let orders = json!({ "orders": [{ "id": 1 }, { "id": 2, "urgent": true }, { "id": 3 }] });
+
+        let output = workflow.run(&ctx, orders).await.unwrap();
+        assert_eq!(output, json!({ "order": { "id": 2, "urgent": true } }));
+        let calm = json!({ "orders": [{ "id": 1 }] });
+        assert_eq!(workflow.run(&ctx, calm).await.unwrap(), json!({ "order": null }));
+    }
+    #[tokio::test]
+    async fn a_skipped_switch_does_not_branch() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: route
+  version: '1.0.0'
+do:
+  - route:
+      if: .routed
+      switch:
+        - express:
+            when: .rush
+            then: express
+  - standard:
+      call: value
+      with:
+        service: ground
+      then: end
+  - express:
+      call: value
+      with:
+        service: air
+"#,
+        ).unwrap();
+        let ctx = value_context();
+
+        let output = workflow.run(&ctx, json!({ "routed": false })).await.unwrap();
+        assert_eq!(output, json!({ "service": "ground" }));
+
+        let routed = json!({ "routed": true, "rush": true });
+        assert_eq!(workflow.run(&ctx, routed).await.unwrap(), json!({ "service": "air" }));
+        let err = workflow.run(&ctx, json!({ "routed": true })).await.unwrap_err();
+        assert!(err.contains("no switch case matched"), "{err}");
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, mpsc};
+
+use serde_json::Value;
+
+use crate::runtime::registry::TaskRegistry;
+use crate::runtime::{StepResult, Task, WorkflowContext};
+
+/// Minimal HTTP server for node tests: answers each connection with the next canned response.
+pub(crate) struct TestServer {
+    pub url: String,
+    requests: mpsc::Receiver<String>,
+}
+
+impl TestServer {
+    /// Returns the next request received by the server (request line, headers and body).
+    pub fn next_request(&self) -> String {
+        self.requests.recv().expect("no request received")
+    }
+}
+
+pub(crate) fn serve(responses: Vec<(&'static str, &'static str, String)>) -> TestServer {
+    let responses = responses
+        .into_iter()
+        .map(|(status, content_type, body)| (status, vec![("content-type", content_type.to_string())], body))
+        .collect();
+    serve_with_headers(responses)
+}
+
+/// A canned response: status line, headers other than `content-length`, and body.
+pub(crate) type Response = (&'static str, Vec<(&'static str, String)>, String);
+
+/// Like [`serve`], with all the headers of each response.
+pub(crate) fn serve_with_headers(responses: Vec<Response>) -> TestServer {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for (status, headers, body) in responses {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).expect("read") == 0 || line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+                request.push_str(&line);
+            }
+            let mut body_buf = vec![0; content_length];
+            reader.read_exact(&mut body_buf).expect("read body");
+            request.push_str("\r\n");
+            request.push_str(&String::from_utf8_lossy(&body_buf));
+            let _ = tx.send(request);
+
+            let headers: String = headers.iter().map(|(name, value)| format!("{name}: {value}\r\n")).collect();
+            let response = format!(
+                "HTTP/1.1 {status}\r\n{headers}content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            reader.get_mut().write_all(response.as_bytes()).expect("write");
+        }
+    });
+    TestServer {
+        url: format!("http://{addr}"),
+        requests: rx,
+    }
+}
+
+pub(crate) fn serve_once(status: &'static str, content_type: &'static str, body: &str) -> TestServer {
+    serve(vec![(status, content_type, body.to_string())])
+}
+
+/// Outputs its input; see [`value_context`].
+struct Echo;
+
+#[async_trait::async_trait]
+impl Task for Echo {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, _ctx: &WorkflowContext, input: Value) -> StepResult<Value> {
+        Ok(input)
+    }
+}
+
+/// A context whose `call: value` tasks output their `with` arguments, resolved against the task input: a stand-in
+/// for the `set` tasks the engine does not run, for tests that only need a task to produce some output.
+pub(crate) fn value_context() -> WorkflowContext {
+    let tasks = TaskRegistry::new().register("value", Arc::new(Echo)).expect("`value` is not a built-in call");
+    WorkflowContext::default().with_task_registry(tasks)
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use serde_json::Value;
+use serverless_workflow_core::models::task::TaskDefinitionFields;
+
+use crate::nodes::doing::BoxedTask;
+use crate::runtime::timeout::{timeout_after, timeout_error};
+use crate::runtime::{StepResult, Task, WorkflowContext};
+
+/// Fails a task that does not complete within its `timeout`, cancelling it.
+///
+/// The error is of kind [`ErrorKind::Timeout`](crate::runtime::ErrorKind::Timeout), which an enclosing `try` task
+/// catches like any other error or selects with `catch.errors.with.type`.
+pub struct TimeoutNode {
+    /// Name of the task, as reported in the error.
+    task: String,
+    after: Duration,
+    inner: BoxedTask,
+}
+
+impl TimeoutNode {
+    /// Wraps `inner`, the node of the task named `task`, when its definition declares a timeout, and returns it
+    /// unchanged otherwise.
+    pub fn wrap(inner: BoxedTask, task: &str, fields: &TaskDefinitionFields) -> StepResult<BoxedTask> {
+        match &fields.timeout {
+            Some(timeout) => Ok(Box::new(Self {
+                task: task.to_string(),
+                after: timeout_after(timeout)?,
+                inner,
+            })),
+            None => Ok(inner),
+        }
+    }
+}
+
+impl std::fmt::Debug for TimeoutNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimeoutNode")
+            .field("task", &self.task)
+            .field("after", &self.after)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for TimeoutNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        tokio::time::timeout(self.after, self.inner.execute(ctx, input))
+            .await
+            .map_err(|_| timeout_error(&format!("task '{}'", self.task), self.after))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Workflow;
+    use crate::runtime::{ErrorKind, WorkflowContext};
+
+    fn workflow(timeouts: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: slow
+  version: '1.0.0'
+{timeouts}
+do:
+  - guarded:
+      try:
+        - nap:
+            timeout:
+              after: PT0.05S
+            run:
+              shell:
+                command: sleep
+                arguments: ['1']
+      catch:
+        do:
+          - recover:
+              run:
+                shell:
+                  command: echo
+                  arguments: [recovered]
+  - nap:
+      timeout:
+        after:
+          milliseconds: 50
+      run:
+        shell:
+          command: sleep
+          arguments: ['1']
+"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn task_timeouts_fail_the_task_and_can_be_caught() {
+        let started = std::time::Instant::now();
+
+        let err = workflow("").run(&WorkflowContext::default(), json!({})).await.unwrap_err();
+
+        assert_eq!(err.kind(), Some(ErrorKind::Timeout));
+        assert_eq!(err, "task 'nap' failed: task 'nap' timed out after 50ms");
+        assert!(started.elapsed() < std::time::Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    async fn workflow_timeout_bounds_the_whole_instance() {
+        let err = workflow("timeout:\n  after: PT0.02S")
+            .run(&WorkflowContext::default(), json!({}))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err, "workflow 'slow' timed out after 20ms");
+    }
+}
@@ -0,0 +1,290 @@
+use std::sync::Mutex;
+
+use serde_json::Value;
+use serverless_workflow_core::models::retry::OneOfRetryPolicyDefinitionOrReference;
+use serverless_workflow_core::models::task::{TaskDefinition, TryTaskDefinition};
+
+use crate::nodes::doing::{DoNode, TaskSite};
+use crate::runtime::retry::{RetryDefaults, RetryHint, RetryPolicy};
+use crate::runtime::{StepInstance, StepError, StepResult, Task, WorkflowContext, run_step_with_retry};
+
+/// `try` task: runs its `try` tasks, retrying them per `catch.retry` and falling back to `catch.do`.
+///
+/// Every error is caught, unless `catch.errors.with.type` selects the errors of one
+/// [`ErrorKind`](crate::runtime::ErrorKind), such as `https://serverlessworkflow.io/spec/1.0.0/errors/timeout`;
+/// other errors are neither retried nor caught. Other error filters, `as` and `when`/`exceptWhen` conditions are
+/// rejected when the node is built. Once retries are exhausted, `catch.do` runs with the task input. Without
+/// `catch.do` the error is swallowed and the task input is passed through. Cancellation is never caught.
+#[derive(Debug)]
+pub struct TryNode {
+    body: DoNode,
+    /// Type of the caught errors, from `catch.errors.with.type`; every error is caught without one.
+    error_type: Option<String>,
+    retry: Option<RetryPolicy>,
+    handler: Option<DoNode>,
+}
+
+impl TryNode {
+    pub fn try_from_task(task: &TaskDefinition) -> StepResult<Self> {
+        match task {
+            TaskDefinition::Try(def) => Self::try_from_try(def),
+            _ => Err("TryNode expects a `try` task definition".into()),
+        }
+    }
+
+    pub fn try_from_try(def: &TryTaskDefinition) -> StepResult<Self> {
+        Self::build(def, &TaskSite::new(""))
+    }
+
+    /// Builds the node of the `try` task at `site`.
+    pub(crate) fn build(def: &TryTaskDefinition, site: &TaskSite) -> StepResult<Self> {
+        let catch = &def.catch;
+        let mut error_type = None;
+        for (property, value) in catch.errors.iter().flat_map(|e| e.with.iter().flatten()) {
+            match (property.as_str(), value) {
+                ("type", Value::String(uri)) => error_type = Some(uri.clone()),
+                ("type", _) => return Err("catch error filter `type` must be a string".into()),
+                _ => return Err(format!("catch error filters on `{property}` are not supported").into()),
+            }
+        }
+        if catch.when.is_some() || catch.except_when.is_some() {
+            return Err("catch `when`/`exceptWhen` conditions are not supported".into());
+        }
+        if catch.as_.is_some() {
+            return Err("catch `as` error variables are not supported".into());
+        }
+        let retry = match &catch.retry {
+            Some(OneOfRetryPolicyDefinitionOrReference::Retry(policy)) => {
+                Some(RetryPolicy::try_from_definition(policy)?)
+            }
+            Some(OneOfRetryPolicyDefinitionOrReference::Reference(name)) => {
+                return Err(format!("retry policy references are not supported: '{name}'").into());
+            }
+            None => None,
+        };
+        Ok(Self {
+            body: DoNode::build(&def.try_, &site.join("try"))?,
+            error_type,
+            retry,
+            handler: catch
+                .do_
+                .as_ref()
+                .map(|handler| DoNode::build(handler, &site.join("catch/do")))
+                .transpose()?,
+        })
+    }
+
+    fn catches(&self, error: &StepError) -> bool {
+        match &self.error_type {
+            Some(error_type) => error.kind().is_some_and(|kind| kind.type_uri() == error_type),
+            None => true,
+        }
+    }
+}
+
+impl TryFrom<&TryTaskDefinition> for TryNode {
+    type Error = StepError;
+
+    fn try_from(def: &TryTaskDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_try(def)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for TryNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        let body = Filtered {
+            node: self,
+            uncaught: Mutex::new(None),
+        };
+        let result = match &self.retry {
+            Some(policy) => {
+                // The declared policy replaces the runtime defaults for the tasks it retries.
+                let ctx = &WorkflowContext {
+                    default_retry: RetryDefaults::default(),
+                    ..ctx.clone()
+                };
+                let mut step = StepInstance::new("try");
+                run_step_with_retry(&mut step, &body, ctx, input.clone(), policy).await
+            }
+            None => body.execute(ctx, input.clone()).await,
+        };
+        if let Some(error) = body.uncaught.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            return Err(error);
+        }
+        match (result, &self.handler) {
+            (Ok(output), _) => Ok(output),
+            (Err(e), _) if ctx.cancellation.is_cancelled() => Err(e),
+            (Err(_), Some(handler)) => handler.execute(ctx, input).await,
+            (Err(_), None) => Ok(input),
+        }
+    }
+}
+
+/// The `try` tasks of a [`TryNode`]: an error its filter does not select is set aside, unchanged, and fails
+/// as not retryable so that `catch.retry` gives up on it.
+struct Filtered<'a> {
+    node: &'a TryNode,
+    uncaught: Mutex<Option<StepError>>,
+}
+
+#[async_trait::async_trait]
+impl Task for Filtered<'_> {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        match self.node.body.execute(ctx, input).await {
+            Err(error) if !self.node.catches(&error) => {
+                let uncaught = error.clone().with_hint(RetryHint::NotRetryable);
+                *self.uncaught.lock().unwrap_or_else(|e| e.into_inner()) = Some(error);
+                Err(uncaught)
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+
+    fn try_node(yaml: &str) -> StepResult<TryNode> {
+        TryNode::try_from_task(&serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_body_succeeds() {
+        let dir = std::env::temp_dir().join(format!("tideloom-retry-{}", uuid::Uuid::new_v4()));
+        // Fails twice, then succeeds on the third attempt.
+        let script = format!(
+            "mkdir -p {0} && echo x >> {0}/attempts && test $(wc -l < {0}/attempts) -ge 3 && echo done",
+            dir.display()
+        );
+        let node = try_node(&format!(
+            r#"
+try:
+  - flaky:
+      run:
+        shell:
+          command: sh
+          arguments: ['-c', '{script}']
+catch:
+  retry:
+    delay: {{ milliseconds: 5 }}
+    backoff: {{ exponential: {{}} }}
+    limit: {{ attempt: {{ count: 3 }} }}
+"#
+        ))
+        .unwrap();
+
+        let output = node.execute(&WorkflowContext::default(), json!({})).await.unwrap();
+
+        assert_eq!(output, json!("done"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_fall_back_to_catch_do() {
+        let node = try_node(
+            r#"
+try:
+  - fail:
+      run:
+        shell:
+          command: 'false'
+catch:
+  retry:
+    delay: { milliseconds: 1 }
+    limit: { attempt: { count: 1 } }
+  do:
+    - recover:
+        run:
+          shell:
+            command: echo
+            arguments: ["${ .fallback }"]
+"#,
+        )
+        .unwrap();
+
+        let output = node.execute(&WorkflowContext::default(), json!({ "fallback": "recovered" })).await.unwrap();
+
+        assert_eq!(output, json!("recovered"));
+        assert!(try_node("try: []\ncatch:\n  retry: default").unwrap_err().contains("default"));
+        assert!(try_node("try: []\ncatch:\n  as: failure").unwrap_err().contains("`as`"));
+    }
+
+    #[tokio::test]
+    async fn error_filters_select_timeouts() {
+        let guarded = |task: &str| {
+            try_node(&format!(
+                r#"
+try:
+  - {task}
+catch:
+  errors:
+    with:
+      type: https://serverlessworkflow.io/spec/1.0.0/errors/timeout
+  retry:
+    delay: {{ milliseconds: 1 }}
+    limit: {{ attempt: {{ count: 3 }} }}
+  do:
+    - recover:
+        run:
+          shell:
+            command: echo
+            arguments: [recovered]
+"#
+            ))
+            .unwrap()
+        };
+        let ctx = WorkflowContext::default();
+
+        let sleep = "run:\n        shell:\n          command: sleep\n          arguments: ['1']";
+        let nap = guarded(&format!("nap:\n      timeout:\n        after: PT0.02S\n      {sleep}"));
+        assert_eq!(nap.execute(&ctx, json!({})).await.unwrap(), json!("recovered"));
+
+        let fail = guarded("fail:\n      run:\n        shell:\n          command: 'false'");
+        let err = fail.execute(&ctx, json!({})).await.unwrap_err();
+        assert!(err.starts_with("task 'fail' failed"), "{err}");
+        assert_eq!(err.hint(), None);
+
+        let status = try_node("try: []\ncatch:\n  errors:\n    with:\n      status: 503");
+        assert!(status.unwrap_err().contains("`status`"));
+    }
+
+    #[tokio::test]
+    async fn cancellation_is_not_caught() {
+        // Retries race the body against the token, so the body ends in a cancellation error.
+        let node = try_node(
+            r#"
+try:
+  - wait:
+      run:
+        shell:
+          command: sleep
+          arguments: ['10']
+catch:
+  retry:
+    limit: { attempt: { count: 3 } }
+"#,
+        )
+        .unwrap();
+        let token = CancellationToken::new();
+        let ctx = WorkflowContext::default().with_cancellation(token.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            token.cancel();
+        });
+
+        let err = node.execute(&ctx, json!({})).await.unwrap_err();
+
+        assert_eq!(err, "step 'try' was cancelled");
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde_json::Value;
+use serverless_workflow_core::models::workflow::WorkflowDefinition;
+use tracing::Instrument;
+
+use crate::WorkflowSummary;
+use crate::nodes::doing::{DoNode, TaskSite};
+use crate::nodes::function::FunctionNode;
+use crate::nodes::mock::MockNode;
+use crate::runtime::auth::Authentication;
+use crate::runtime::expr::resolve_with;
+use crate::runtime::metering::InstanceInfo;
+use crate::runtime::metrics::{WORKFLOWS_COMPLETED, WORKFLOWS_FAULTED, WORKFLOWS_STARTED};
+use crate::runtime::observer::WorkflowEvent;
+use crate::runtime::schema::Schema;
+use crate::runtime::secrets;
+use crate::runtime::timeout::{timeout_after, timeout_error};
+use crate::runtime::{
+    ContextData, SharedTask, StepError, StepResult, Task, WorkflowContext, cancelled_error, workflow_key,
+};
+
+/// A whole workflow: its top-level `do` tasks between the document-level `input` and `output` blocks.
+///
+/// The start payload is checked against `input.schema` first; a mismatch fails the instance before any
+/// task runs. `input.from` then shapes the payload handed to the first task. The last task's output is
+/// transformed by `output.as` and checked against `output.schema` to produce the workflow output.
+/// The document-level `timeout` bounds the whole run, including those checks. Cancelling the context's
+/// token stops the run. Calls may reference the policies declared in `use.authentications` by name, and
+/// expressions may read the secrets declared in `use.secrets` as `$secrets.NAME`; their values are redacted
+/// from the workflow's error, emitted events, traces and observer events, but not from the data passed between
+/// tasks.
+///
+/// Each run is traced as a `workflow` span, parent of the spans of its tasks.
+pub struct WorkflowNode {
+    name: String,
+    key: String,
+    summary: Arc<WorkflowSummary>,
+    input_schema: Option<Schema>,
+    from: Option<Value>,
+    body: DoNode,
+    as_: Option<Value>,
+    output_schema: Option<Schema>,
+    timeout: Option<Duration>,
+    authentications: HashMap<String, Authentication>,
+    functions: HashMap<String, SharedTask>,
+    secrets: Vec<String>,
+}
+
+impl WorkflowNode {
+    pub fn try_from_definition(definition: &WorkflowDefinition) -> StepResult<Self> {
+        Self::build(definition, HashMap::new())
+    }
+
+    /// Builds the workflow with the tasks at the positions of `mocks` replaced by their stand-ins.
+    pub(crate) fn build(definition: &WorkflowDefinition, mocks: HashMap<String, MockNode>) -> StepResult<Self> {
+        let compile = |schema, what: &str| match schema {
+            Some(schema) => Schema::compile(schema).map(Some).map_err(|e| format!("workflow {what} {e}")),
+            None => Ok(None),
+        };
+        let input = definition.input.as_ref();
+        let output = definition.output.as_ref();
+        Ok(Self {
+            name: definition.document.name.clone(),
+            key: workflow_key(
+                &definition.document.namespace,
+                &definition.document.name,
+                &definition.document.version,
+            ),
+            summary: Arc::new(WorkflowSummary::from_definition(definition)),
+            input_schema: compile(input.and_then(|i| i.schema.as_ref()), "input")?,
+            from: input.and_then(|i| i.from.clone()),
+            body: DoNode::build(&definition.do_, &TaskSite::new("/do").with_mocks(mocks))?,
+            as_: output.and_then(|o| o.as_.clone()),
+            output_schema: compile(output.and_then(|o| o.schema.as_ref()), "output")?,
+            timeout: definition.timeout.as_ref().map(timeout_after).transpose()?,
+            authentications: definition
+                .use_
+                .iter()
+                .flat_map(|use_| use_.authentications.iter().flatten())
+                .map(|(name, policy)| {
+                    Authentication::try_from_definition(policy)
+                        .map(|auth| (name.clone(), auth))
+                        .map_err(|e| e.map_message(|e| format!("authentication policy '{name}': {e}")))
+                })
+                .collect::<StepResult<_>>()?,
+            functions: FunctionNode::registry(
+                definition.use_.iter().flat_map(|use_| use_.functions.iter().flatten()),
+            )?,
+            secrets: definition.use_.iter().flat_map(|use_| use_.secrets.iter().flatten()).cloned().collect(),
+        })
+    }
+}
+
+impl std::fmt::Debug for WorkflowNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkflowNode")
+            .field("key", &self.key)
+            .field("body", &self.body)
+            .field("timeout", &self.timeout)
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TryFrom<&WorkflowDefinition> for WorkflowNode {
+    type Error = StepError;
+
+    fn try_from(definition: &WorkflowDefinition) -> std::result::Result<Self, Self::Error> {
+        Self::try_from_definition(definition)
+    }
+}
+
+#[async_trait::async_trait]
+impl Task for WorkflowNode {
+    type Input = Value;
+    type Output = Value;
+
+    async fn execute(&self, ctx: &WorkflowContext, input: Self::Input) -> StepResult<Self::Output> {
+        self.execute_as(ctx, uuid::Uuid::new_v4().to_string(), input).await
+    }
+}
+
+impl WorkflowNode {
+    /// Runs the workflow as the instance `id`, a child of the context's instance if it has one. The instance gets
+    /// its own `$context`, seeded from the context's.
+    pub async fn execute_as(&self, ctx: &WorkflowContext, id: String, input: Value) -> StepResult<Value> {
+        let mut scope = ctx.scope.clone();
+        let secrets = secrets::load(ctx.secrets.as_deref(), &self.secrets)
+            .map_err(|e| format!("workflow '{}': {e}", self.name))?;
+        scope.insert("$secrets".to_string(), Value::Object(secrets));
+        let ctx = &WorkflowContext {
+            scope,
+            instance: InstanceInfo {
+                workflow: self.key.clone(),
+                id,
+                parent: (!ctx.instance.id.is_empty()).then(|| Box::new(ctx.instance.clone())),
+            },
+            authentications: self.authentications.clone(),
+            functions: self.functions.clone(),
+            context: ContextData::new(ctx.context.get()),
+            ended: Arc::default(),
+            ..ctx.clone()
+        };
+        ctx.observe(|| WorkflowEvent::WorkflowStarted {
+            instance: ctx.instance.clone(),
+            definition: self.summary.clone(),
+            input: ctx.redact(&input),
+            at: Utc::now(),
+        });
+        let run = async {
+            match self.timeout {
+                Some(after) => tokio::time::timeout(after, self.run(ctx, input))
+                    .await
+                    .map_err(|_| timeout_error(&format!("workflow '{}'", self.name), after))?,
+                None => self.run(ctx, input).await,
+            }
+        };
+        let span = tracing::info_span!(
+            "workflow",
+            workflow = %ctx.instance.workflow,
+            instance = %ctx.instance.id,
+            parent = ctx.instance.parent.as_ref().map(|parent| parent.id.as_str()),
+            tenant = ctx.tenant.as_deref(),
+            duration_ms = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        let started = Instant::now();
+        let labels = [("workflow", self.key.clone())];
+        ctx.count(WORKFLOWS_STARTED, &labels);
+        // Dropping the run cancels whatever task is in flight, including HTTP requests and processes. Cancellation
+        // is polled first, so that a run that ends in the same poll as its cancellation is reported cancelled.
+        let result = tokio::select! {
+            biased;
+            _ = ctx.cancellation.cancelled() => Err(cancelled_error(&format!("workflow '{}'", self.name))),
+            result = run.instrument(span.clone()) => result,
+        };
+        let duration = started.elapsed();
+        span.record("duration_ms", duration.as_millis() as u64);
+        let result = result.map_err(|e| ctx.redact_error(e));
+        match &result {
+            Ok(output) => {
+                ctx.count(WORKFLOWS_COMPLETED, &labels);
+                ctx.observe(|| WorkflowEvent::WorkflowCompleted {
+                    instance: ctx.instance.clone(),
+                    output: ctx.redact(output),
+                    at: Utc::now(),
+                    duration,
+                });
+            }
+            Err(e) => {
+                ctx.count(WORKFLOWS_FAULTED, &labels);
+                span.record("error", e.message());
+                ctx.observe(|| WorkflowEvent::WorkflowFaulted {
+                    instance: ctx.instance.clone(),
+                    error: e.to_string(),
+                    at: Utc::now(),
+                    duration,
+                });
+            }
+        }
+        result
+    }
+}
+
+impl WorkflowNode {
+    async fn run(&self, ctx: &WorkflowContext, input: Value) -> StepResult<Value> {
+        if let Some(schema) = &self.input_schema {
+            schema.check(&input, &format!("input of workflow '{}'", self.name))?;
+        }
+        let mut vars = ctx.vars();
+        let input = match &self.from {
+            Some(from) => resolve_with(from, &input, &vars).map_err(|e| format!("workflow input.from: {e}"))?,
+            None => input,
+        };
+        vars.insert("$input".to_string(), input.clone());
+
+        let output = self.body.execute(ctx, input).await?;
+
+        vars.insert("$context".to_string(), ctx.context.get());
+        let output = match &self.as_ {
+            Some(as_) => resolve_with(as_, &output, &vars).map_err(|e| format!("workflow output.as: {e}"))?,
+            None => output,
+        };
+        if let Some(schema) = &self.output_schema {
+            schema.check(&output, &format!("output of workflow '{}'", self.name))?;
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn workflow_input_is_validated_then_transformed() {
+        let definition: WorkflowDefinition = serde_yaml::from_str(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: greet
+  version: '1.0.0'
+input:
+  schema:
+    document:
+      type: object
+      required: [customer]
+  from: ${ .customer.name }
+do:
+  - echo:
+      run:
+        shell:
+          command: echo
+          arguments: ["${ . }"]
+"#,
+        )
+        .unwrap();
+        let node = WorkflowNode::try_from_definition(&definition).unwrap();
+        let ctx = WorkflowContext::default();
+
+        let output = node.execute(&ctx, json!({ "customer": { "name": "ada" } })).await.unwrap();
+        assert_eq!(output, json!("ada"));
+
+        let err = node.execute(&ctx, json!({ "name": "ada" })).await.unwrap_err();
+        assert!(err.starts_with("input of workflow 'greet' does not match its schema"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn workflow_output_is_transformed_then_validated() {
+        let definition: WorkflowDefinition = serde_yaml::from_str(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: greet
+  version: '1.0.0'
+output:
+  as:
+    greeting: ${ . }
+    for: ${ $input.name }
+  schema:
+    document:
+      type: object
+      properties:
+        greeting:
+          type: string
+          minLength: 3
+do:
+  - echo:
+      run:
+        shell:
+          command: echo
+          arguments: ["${ .name }"]
+"#,
+        )
+        .unwrap();
+        let node = WorkflowNode::try_from_definition(&definition).unwrap();
+        let ctx = WorkflowContext::default();
+
+        let output = node.execute(&ctx, json!({ "name": "ada" })).await.unwrap();
+        assert_eq!(output, json!({ "greeting": "ada", "for": "ada" }));
+
+        let err = node.execute(&ctx, json!({ "name": "al" })).await.unwrap_err();
+        assert!(err.starts_with("output of workflow 'greet' does not match its schema"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn cancelling_the_token_stops_the_instance() {
+        let definition: WorkflowDefinition = serde_yaml::from_str(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: slow
+  version: '1.0.0'
+do:
+  - nap:
+      run:
+        shell:
+          command: sleep
+          arguments: ['5']
+"#,
+        )
+        .unwrap();
+        let node = WorkflowNode::try_from_definition(&definition).unwrap();
+        let token = crate::runtime::CancellationToken::new();
+        let ctx = WorkflowContext::default().with_cancellation(token.clone());
+        let started = std::time::Instant::now();
+
+        let cancel = async {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            token.cancel();
+        };
+        let (result, _) = tokio::join!(node.execute(&ctx, json!({})), cancel);
+
+        let err = result.unwrap_err();
+        assert!(err.ends_with("was cancelled") && token.is_cancelled(), "{err}");
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    /// Records each new span as its name followed by its fields, e.g. `task task=greet position=/do/0/greet`.
+    #[derive(Clone, Default)]
+    struct SpanLog(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanLog {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut line = attrs.metadata().name().to_string();
+            attrs.record(&mut |field: &tracing::field::Field, value: &dyn std::fmt::Debug| {
+                line.push_str(&format!(" {field}={value:?}"));
+            });
+            self.0.lock().unwrap().push(line);
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_are_traced_per_workflow_and_task() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let definition: WorkflowDefinition = serde_yaml::from_str(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: traced
+  version: '1.0.0'
+do:
+  - guarded:
+      try:
+        - greet:
+            run:
+              shell:
+                command: echo
+                arguments: ['hi']
+      catch:
+        do:
+          - ignore:
+              run:
+                shell:
+                  command: 'true'
+"#,
+        )
+        .unwrap();
+        let node = WorkflowNode::try_from_definition(&definition).unwrap();
+        let log = SpanLog::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(log.clone()));
+
+        node.execute(&WorkflowContext::default().with_tenant("acme"), json!({})).await.unwrap();
+
+        let spans = log.0.lock().unwrap().clone();
+        assert_eq!(spans.len(), 3, "{spans:?}");
+        assert!(spans[0].starts_with("workflow workflow=test/traced:1.0.0 instance="), "{spans:?}");
+        assert!(spans[0].ends_with("tenant=\"acme\""), "{spans:?}");
+        assert_eq!(spans[1], "task task=guarded position=/do/0/guarded");
+        assert_eq!(spans[2], "task task=greet position=/do/0/guarded/try/0/greet");
+    }
+}
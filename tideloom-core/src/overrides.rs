@@ -0,0 +1,272 @@
+//! Host-supplied changes to individual tasks of a single run, keyed by task position.
+//!
+//! Positions are the JSON pointers reported by [`validate`](crate::validation::validate), such as
+//! `/do/0/fetchOrder` or `/do/1/handle/try/0/charge`. Overrides let a run target staging endpoints, tighten
+//! timeouts, skip tasks or replace them with canned outputs without editing the workflow document.
+//!
+//! Skipped and mocked tasks are swapped for a [`MockNode`] when the run is built. Nothing is written into the
+//! task definitions, so a workflow's own metadata cannot skip or mock a task.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json::Value;
+use serverless_workflow_core::models::duration::{Duration as DurationDefinition, OneOfDurationOrIso8601Expression};
+use serverless_workflow_core::models::map::Map;
+use serverless_workflow_core::models::task::TaskDefinition;
+use serverless_workflow_core::models::timeout::{OneOfTimeoutDefinitionOrReference, TimeoutDefinition};
+use serverless_workflow_core::models::workflow::WorkflowDefinition;
+
+use crate::nodes::doing::task_fields_mut;
+use crate::nodes::mock::MockNode;
+use crate::runtime::StepResult;
+
+/// Changes applied to one task.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskOverride {
+    /// Replaces the endpoint of an `http` call, or the document endpoint of an `openapi` or `asyncapi` call.
+    pub endpoint: Option<String>,
+    pub timeout: Option<Duration>,
+    /// Skips the task: its input becomes its output.
+    pub disabled: bool,
+    /// Replaces the task's effect with this output; the task's `input` and `output` blocks still apply.
+    pub mock: Option<Value>,
+}
+
+/// Overrides of a run, by task position.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    tasks: HashMap<String, TaskOverride>,
+}
+
+impl Overrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_task(mut self, position: impl Into<String>, task: TaskOverride) -> Self {
+        self.tasks.insert(position.into(), task);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Returns a copy of `definition` with the endpoint and timeout overrides applied; unknown positions are an
+    /// error. Skipped and mocked tasks are left unchanged; they are swapped in when the run is built.
+    pub fn apply(&self, definition: &WorkflowDefinition) -> StepResult<WorkflowDefinition> {
+        let mut definition = definition.clone();
+        for (position, changes) in &self.tasks {
+            let task = task_at(&mut definition, position).ok_or_else(|| format!("no task at '{position}'"))?;
+            apply(task, changes).map_err(|e| format!("override of '{position}': {e}"))?;
+        }
+        Ok(definition)
+    }
+
+    /// The stand-ins of the skipped and mocked tasks, by position.
+    pub(crate) fn mocks(&self) -> HashMap<String, MockNode> {
+        self.tasks
+            .iter()
+            .filter_map(|(position, changes)| {
+                let output = match (&changes.mock, changes.disabled) {
+                    (_, true) => None,
+                    (Some(output), false) => Some(output.clone()),
+                    (None, false) => return None,
+                };
+                Some((position.clone(), MockNode { output }))
+            })
+            .collect()
+    }
+}
+
+fn apply(task: &mut TaskDefinition, changes: &TaskOverride) -> StepResult<()> {
+    if let Some(endpoint) = &changes.endpoint {
+        let TaskDefinition::Call(call) = task else {
+            return Err("only call tasks have an endpoint".into());
+        };
+        let field = match call.call.to_ascii_lowercase().as_str() {
+            "http" => "endpoint",
+            "openapi" | "asyncapi" => "document",
+            other => return Err(format!("cannot override the endpoint of '{other}' calls").into()),
+        };
+        let with = call.with.get_or_insert_with(HashMap::new);
+        match with.get_mut(field) {
+            Some(Value::Object(target)) if target.contains_key("endpoint") => {
+                target.insert("endpoint".into(), Value::String(endpoint.clone()));
+            }
+            Some(Value::Object(target)) if field == "endpoint" => {
+                target.insert("uri".into(), Value::String(endpoint.clone()));
+            }
+            _ => {
+                with.insert(field.to_string(), Value::String(endpoint.clone()));
+            }
+        }
+    }
+    if let Some(after) = changes.timeout {
+        let millis = u64::try_from(after.as_millis()).map_err(|_| "timeout is too long".to_string())?;
+        task_fields_mut(task).timeout = Some(OneOfTimeoutDefinitionOrReference::Timeout(TimeoutDefinition {
+            after: OneOfDurationOrIso8601Expression::Duration(DurationDefinition::from_milliseconds(millis)),
+        }));
+    }
+    Ok(())
+}
+
+/// Finds the task at `position`, descending through `do`, `for`, `try`, `catch.do` and `fork` branches.
+fn task_at<'a>(definition: &'a mut WorkflowDefinition, position: &str) -> Option<&'a mut TaskDefinition> {
+    let mut segments = position.strip_prefix("/do/")?.split('/');
+    let mut task = entry(&mut definition.do_, segments.next()?, segments.next()?)?;
+    while let Some(segment) = segments.next() {
+        let field = match segment {
+            "catch" | "fork" => (segment, segments.next()?),
+            _ => (segment, ""),
+        };
+        let tasks = match (field, task) {
+            (("do", ""), TaskDefinition::Do(def)) => &mut def.do_,
+            (("do", ""), TaskDefinition::For(def)) => &mut def.do_,
+            (("try", ""), TaskDefinition::Try(def)) => &mut def.try_,
+            (("catch", "do"), TaskDefinition::Try(def)) => def.catch.do_.as_mut()?,
+            (("fork", "branches"), TaskDefinition::Fork(def)) => &mut def.fork.branches,
+            _ => return None,
+        };
+        task = entry(tasks, segments.next()?, segments.next()?)?;
+    }
+    Some(task)
+}
+
+fn entry<'a>(tasks: &'a mut Map<String, TaskDefinition>, index: &str, name: &str) -> Option<&'a mut TaskDefinition> {
+    tasks.entries.get_mut(index.parse::<usize>().ok()?)?.get_mut(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::nodes::testing::serve_once;
+    use crate::runtime::{ErrorKind, WorkflowContext};
+
+    fn workflow() -> Workflow {
+        Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: checkout
+  version: '1.0.0'
+do:
+- fetchOrder:
+    call: http
+    with:
+      method: get
+      endpoint:
+        uri: https://orders.invalid/orders/1
+- pay:
+    try:
+    - charge:
+        call: http
+        with:
+          method: post
+          endpoint: https://payments.invalid/charges
+        output:
+          as: { charged: '${ .ok }', order: '${ $input.id }' }
+    catch:
+      do:
+      - giveUp:
+          run:
+            shell:
+              command: sh
+              arguments: ['-c', 'exit 1']
+- notify:
+    run:
+      shell:
+        command: sh
+        arguments: ['-c', 'sleep 5']
+"#,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn overrides_redirect_mock_and_skip_tasks() {
+        let server = serve_once("200 OK", "application/json", r#"{"id":"o-1"}"#);
+        let overrides = Overrides::new()
+            .with_task(
+                "/do/0/fetchOrder",
+                TaskOverride {
+                    endpoint: Some(format!("{}/orders/1", server.url)),
+                    ..Default::default()
+                },
+            )
+            .with_task(
+                "/do/1/pay/try/0/charge",
+                TaskOverride {
+                    mock: Some(json!({ "ok": true })),
+                    ..Default::default()
+                },
+            )
+            .with_task(
+                "/do/2/notify",
+                TaskOverride {
+                    disabled: true,
+                    ..Default::default()
+                },
+            );
+
+        let output = workflow()
+            .run_with_overrides(&WorkflowContext::default(), json!({}), &overrides)
+            .await
+            .unwrap();
+
+        assert_eq!(output, json!({ "charged": true, "order": "o-1" }));
+        assert!(server.next_request().starts_with("GET /orders/1 "));
+
+        let server = serve_once("200 OK", "application/json", r#"{"id":"o-2"}"#);
+        let own_metadata = Workflow::try_from_yaml(&format!(
+            r#"
+document: {{ dsl: '1.0.0', namespace: test, name: labelled, version: '1.0.0' }}
+do:
+- fetchOrder:
+    call: http
+    with: {{ method: get, endpoint: '{}/orders/2' }}
+    metadata: {{ disabled: true, mock: {{ id: canned }} }}
+"#,
+            server.url
+        ))
+        .unwrap();
+        let output = own_metadata.run(&WorkflowContext::default(), json!({})).await.unwrap();
+        assert_eq!(output, json!({ "id": "o-2" }));
+    }
+
+    #[tokio::test]
+    async fn overrides_set_timeouts_and_reject_unknown_positions() {
+        let timeout = TaskOverride {
+            timeout: Some(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let overrides = Overrides::new()
+            .with_task("/do/0/fetchOrder", TaskOverride { mock: Some(json!({})), ..Default::default() })
+            .with_task("/do/1/pay/try/0/charge", TaskOverride { mock: Some(json!({})), ..Default::default() })
+            .with_task("/do/2/notify", timeout.clone());
+        let result = workflow().run_with_overrides(&WorkflowContext::default(), json!({}), &overrides).await;
+        assert_eq!(result.unwrap_err().kind(), Some(ErrorKind::Timeout));
+
+        let unknown = Overrides::new().with_task("/do/1/pay/catch/do/3/giveUp", timeout);
+        assert_eq!(
+            unknown.apply(workflow().definition()).unwrap_err(),
+            "no task at '/do/1/pay/catch/do/3/giveUp'"
+        );
+        let run_endpoint = Overrides::new().with_task(
+            "/do/2/notify",
+            TaskOverride {
+                endpoint: Some("http://localhost".into()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            run_endpoint.apply(workflow().definition()).unwrap_err(),
+            "override of '/do/2/notify': only call tasks have an endpoint"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Operational CloudEvents published when the runtime gives up on something, so that alerting can subscribe
+//! to them instead of scraping logs.
+
+use serde_json::{Value, json};
+
+use crate::messaging::CloudEvent;
+use crate::runtime::WorkflowContext;
+
+/// A retry policy allowed no further attempt and the task failed.
+pub const RETRY_EXHAUSTED: &str = "io.tideloom.retry.exhausted.v1";
+/// A scheduled start was later than the scheduler's misfire tolerance and was skipped.
+pub const SCHEDULE_MISFIRED: &str = "io.tideloom.schedule.misfired.v1";
+
+/// Publishes an operational event of `type_` to the context's alert sink, if the host configured one.
+///
+/// The event's source identifies the workflow and its subject the instance, when known. Failing to publish
+/// never fails the workflow.
+pub async fn publish(ctx: &WorkflowContext, type_: &str, data: Value) {
+    let Some(sink) = &ctx.alerts else {
+        return;
+    };
+    let mut event = CloudEvent::new(
+        uuid::Uuid::new_v4().to_string(),
+        format!("/tideloom/{}", ctx.instance.workflow),
+        type_,
+    )
+    .with_data(data);
+    event.time = Some(chrono::Utc::now().to_rfc3339());
+    if !ctx.instance.id.is_empty() {
+        event.subject = Some(ctx.instance.id.clone());
+    }
+    if let Some(tenant) = &ctx.tenant {
+        event.extensions.insert("tenant".to_string(), json!(tenant));
+    }
+    let _ = sink.publish(event).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::messaging::{EventSource, InMemoryEventBus};
+    use crate::runtime::retry::{RetryDefaults, RetryPolicy};
+
+    #[tokio::test]
+    async fn exhausted_retries_are_published() {
+        let workflow = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: flaky
+  version: '1.0.0'
+do:
+- fail:
+    run:
+      shell:
+        command: 'false'
+",
+        )
+        .unwrap();
+        let bus = Arc::new(InMemoryEventBus::default());
+        let mut events = bus.subscribe().await.unwrap();
+        let policy = RetryPolicy {
+            delay: Duration::from_millis(1),
+            max_retries: Some(2),
+            ..Default::default()
+        };
+        let ctx = WorkflowContext::default()
+            .with_default_retry(RetryDefaults::new(policy))
+            .with_alerts(bus.clone())
+            .with_tenant("acme");
+
+        workflow.run(&ctx, json!({})).await.unwrap_err();
+
+        let event = events.next().await.unwrap();
+        assert_eq!(event.type_, RETRY_EXHAUSTED);
+        assert_eq!(event.source, "/tideloom/test/flaky:1.0.0");
+        assert!(event.subject.is_some());
+        assert_eq!(event.extensions["tenant"], json!("acme"));
+        assert_eq!(event.data.as_ref().unwrap()["attempts"], json!(3));
+    }
+}
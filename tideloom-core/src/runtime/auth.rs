@@ -0,0 +1,543 @@
+//! Authentication policies of HTTP and OpenAPI calls (`with.authentication` and `use.authentications`).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::header::{AUTHORIZATION, HeaderName, HeaderValue};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use serverless_workflow_core::models::authentication::{
+    AuthenticationPolicyDefinition, OAuth2AuthenticationSchemeDefinition,
+};
+
+use crate::nodes::http::exchange;
+use crate::runtime::{StepResult, WorkflowContext};
+
+/// Tokens are refreshed this long before they expire, so they do not lapse while a request is in flight.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Where an API key is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyLocation {
+    Header,
+    Query,
+}
+
+/// An OAuth2 client using the `client_credentials` grant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuth2Client {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    /// Send the client credentials as HTTP basic authentication instead of in the request body.
+    pub basic_client_auth: bool,
+    /// Encode the token request as JSON instead of an URL-encoded form.
+    pub json: bool,
+    pub scopes: Vec<String>,
+    pub audiences: Vec<String>,
+}
+
+/// A resolved authentication policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authentication {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+    /// Not part of the DSL schemes: a static key sent in a header or query parameter.
+    ApiKey { name: String, value: String, location: ApiKeyLocation },
+    OAuth2(Box<OAuth2Client>),
+    /// A `basic`, `bearer` or `oauth2` policy read, when a request is sent, from the secret `secret` declared in
+    /// the workflow's `use.secrets`. The secret holds the scheme's fields as a JSON object; a bearer secret may
+    /// also be the token itself.
+    FromSecret { scheme: String, secret: String },
+}
+
+/// The `authentication` of a call: inline, or the name of a policy declared in `use.authentications`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthenticationRef {
+    Inline(Authentication),
+    Named(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyDefinition {
+    #[serde(flatten)]
+    policy: AuthenticationPolicyDefinition,
+    #[serde(rename = "apiKey")]
+    api_key: Option<ApiKeyDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiKeyDefinition {
+    name: String,
+    value: String,
+    #[serde(rename = "in", default = "default_api_key_location")]
+    location: ApiKeyLocation,
+}
+
+fn default_api_key_location() -> ApiKeyLocation {
+    ApiKeyLocation::Header
+}
+
+impl AuthenticationRef {
+    /// Parses the `authentication` value of a call's `with` block.
+    pub fn try_from_value(value: &Value) -> StepResult<Self> {
+        if let Value::String(name) = value {
+            return Ok(Self::Named(name.clone()));
+        }
+        let definition: PolicyDefinition =
+            serde_json::from_value(value.clone()).map_err(|e| format!("invalid authentication policy: {e}"))?;
+        match definition.api_key {
+            Some(key) => Ok(Self::Inline(Authentication::ApiKey {
+                name: key.name,
+                value: key.value,
+                location: key.location,
+            })),
+            None => Self::try_from_definition(&definition.policy),
+        }
+    }
+
+    /// Reads a policy of the DSL model, such as the `authentication` of an endpoint.
+    pub fn try_from_definition(definition: &AuthenticationPolicyDefinition) -> StepResult<Self> {
+        match &definition.use_ {
+            Some(name) => Ok(Self::Named(name.clone())),
+            None => Authentication::try_from_definition(definition).map(Self::Inline),
+        }
+    }
+
+    /// Adds the credentials to `request`, resolving named policies from the context.
+    pub async fn apply(&self, ctx: &WorkflowContext, request: &mut reqwest::Request) -> StepResult<()> {
+        let authentication = match self {
+            Self::Inline(authentication) => authentication,
+            Self::Named(name) => ctx
+                .authentications
+                .get(name)
+                .ok_or_else(|| format!("unknown authentication policy '{name}'"))?,
+        };
+        authentication.apply(ctx, request).await
+    }
+}
+
+impl Authentication {
+    pub fn try_from_definition(definition: &AuthenticationPolicyDefinition) -> StepResult<Self> {
+        let missing = |scheme: &str, field: &str| format!("{scheme} authentication requires `{field}`");
+        let secret_refs = [
+            ("basic", definition.basic.as_ref().and_then(|s| s.use_.as_ref())),
+            ("bearer", definition.bearer.as_ref().and_then(|s| s.use_.as_ref())),
+            ("oauth2", definition.oauth2.as_ref().and_then(|s| s.use_.as_ref())),
+        ];
+        if let Some((scheme, Some(secret))) = secret_refs.into_iter().find(|(_, secret)| secret.is_some()) {
+            return Ok(Self::FromSecret {
+                scheme: scheme.to_string(),
+                secret: secret.clone(),
+            });
+        }
+        if let Some(basic) = &definition.basic {
+            return Ok(Self::Basic {
+                username: basic.username.clone().ok_or_else(|| missing("basic", "username"))?,
+                password: basic.password.clone().ok_or_else(|| missing("basic", "password"))?,
+            });
+        }
+        if let Some(bearer) = &definition.bearer {
+            return Ok(Self::Bearer {
+                token: bearer.token.clone().ok_or_else(|| missing("bearer", "token"))?,
+            });
+        }
+        if let Some(oauth2) = &definition.oauth2 {
+            return OAuth2Client::try_from_definition(oauth2).map(|client| Self::OAuth2(Box::new(client)));
+        }
+        Err("unsupported authentication scheme: expected basic, bearer, oauth2 or apiKey".into())
+    }
+
+    /// Reads a [`FromSecret`](Self::FromSecret) policy from the running workflow's `$secrets`. Errors never include
+    /// the secret's value.
+    fn from_secret(ctx: &WorkflowContext, scheme: &str, secret: &str) -> StepResult<Self> {
+        let value = ctx
+            .scope
+            .get("$secrets")
+            .and_then(|secrets| secrets.get(secret))
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("authentication secret '{secret}' is not declared in `use.secrets`"))?;
+        let invalid = || format!("authentication secret '{secret}' is not a valid {scheme} policy");
+        let fields = match serde_json::from_str::<Value>(value) {
+            Ok(fields @ Value::Object(_)) => fields,
+            _ if scheme == "bearer" => json!({ "token": value }),
+            _ => return Err(invalid().into()),
+        };
+        let definition = serde_json::from_value(json!({ scheme: fields })).map_err(|_| invalid())?;
+        match Self::try_from_definition(&definition).map_err(|e| format!("authentication secret '{secret}': {e}"))? {
+            Self::FromSecret { .. } => Err(invalid().into()),
+            authentication => Ok(authentication),
+        }
+    }
+
+    /// Adds the credentials to `request`, fetching an OAuth2 token when needed.
+    pub async fn apply(&self, ctx: &WorkflowContext, request: &mut reqwest::Request) -> StepResult<()> {
+        let header = |value: String| {
+            HeaderValue::from_str(&value).map_err(|e| format!("invalid authentication header: {e}"))
+        };
+        let from_secret;
+        let authentication = match self {
+            Self::FromSecret { scheme, secret } => {
+                from_secret = Self::from_secret(ctx, scheme, secret)?;
+                &from_secret
+            }
+            authentication => authentication,
+        };
+        match authentication {
+            Self::Basic { username, password } => {
+                let credentials = BASE64.encode(format!("{username}:{password}"));
+                request.headers_mut().insert(AUTHORIZATION, header(format!("Basic {credentials}"))?);
+            }
+            Self::Bearer { token } => {
+                request.headers_mut().insert(AUTHORIZATION, header(format!("Bearer {token}"))?);
+            }
+            Self::ApiKey { name, value, location: ApiKeyLocation::Header } => {
+                let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| format!("invalid api key header: {e}"))?;
+                request.headers_mut().insert(name, header(value.clone())?);
+            }
+            Self::ApiKey { name, value, location: ApiKeyLocation::Query } => {
+                request.url_mut().query_pairs_mut().append_pair(name, value);
+            }
+            Self::OAuth2(client) => {
+                let token = ctx.tokens.token(ctx, client).await?;
+                request.headers_mut().insert(AUTHORIZATION, header(format!("Bearer {token}"))?);
+            }
+            Self::FromSecret { .. } => unreachable!("secret policies are resolved above"),
+        }
+        Ok(())
+    }
+}
+
+impl OAuth2Client {
+    pub fn try_from_definition(definition: &OAuth2AuthenticationSchemeDefinition) -> StepResult<Self> {
+        let grant = definition.grant.as_deref().unwrap_or("client_credentials");
+        if grant != "client_credentials" {
+            return Err(format!("unsupported oauth2 grant '{grant}': only client_credentials is supported").into());
+        }
+        let authority = definition
+            .authority
+            .as_deref()
+            .ok_or_else(|| "oauth2 authentication requires an `authority`".to_string())?;
+        let token_path = definition.endpoints.as_ref().map_or("/oauth2/token", |e| e.token.as_str());
+        let client = definition.client.as_ref();
+        let basic_client_auth = match client.and_then(|c| c.authentication.as_deref()) {
+            None | Some("client_secret_post") => false,
+            Some("client_secret_basic") => true,
+            Some(other) => return Err(format!("unsupported oauth2 client authentication '{other}'").into()),
+        };
+        Ok(Self {
+            token_url: format!("{}/{}", authority.trim_end_matches('/'), token_path.trim_start_matches('/')),
+            client_id: client
+                .and_then(|c| c.id.clone())
+                .ok_or_else(|| "oauth2 authentication requires a `client.id`".to_string())?,
+            client_secret: client.and_then(|c| c.secret.clone()),
+            basic_client_auth,
+            json: definition.request.as_ref().is_some_and(|r| r.encoding == "application/json"),
+            scopes: definition.scopes.clone().unwrap_or_default(),
+            audiences: definition.audiences.clone().unwrap_or_default(),
+        })
+    }
+
+    async fn request_token(
+        &self,
+        ctx: &WorkflowContext,
+        refresh_token: Option<&str>,
+    ) -> StepResult<CachedToken> {
+        let mut params = vec![("grant_type", "client_credentials".to_string())];
+        if let Some(refresh_token) = refresh_token {
+            params[0].1 = "refresh_token".to_string();
+            params.push(("refresh_token", refresh_token.to_string()));
+        }
+        if !self.scopes.is_empty() {
+            params.push(("scope", self.scopes.join(" ")));
+        }
+        if !self.audiences.is_empty() {
+            params.push(("audience", self.audiences.join(" ")));
+        }
+        let mut request = ctx.http_client.post(&self.token_url);
+        if self.basic_client_auth {
+            request = request.basic_auth(&self.client_id, self.client_secret.as_ref());
+        } else {
+            params.push(("client_id", self.client_id.clone()));
+            if let Some(secret) = &self.client_secret {
+                params.push(("client_secret", secret.clone()));
+            }
+        }
+        let params: HashMap<_, _> = params.into_iter().collect();
+        request = if self.json { request.json(&params) } else { request.form(&params) };
+
+        let request = request
+            .build()
+            .map_err(|e| format!("failed to build oauth2 token request to '{}': {e}", self.token_url))?;
+        let (_, response) = exchange(ctx, request).await?;
+        let body = match &response.spooled {
+            Some(path) => {
+                let body = tokio::fs::read(path).await;
+                let _ = tokio::fs::remove_file(path).await;
+                body.map_err(|e| format!("failed to read oauth2 token response: {e}"))?
+            }
+            None => response.body,
+        };
+        if !response.status.is_success() {
+            return Err(format!(
+                "oauth2 token request to '{}' failed with status {}: {}",
+                self.token_url,
+                response.status,
+                String::from_utf8_lossy(&body)
+            )
+            .into());
+        }
+        let token: TokenResponse =
+            serde_json::from_slice(&body).map_err(|e| format!("invalid oauth2 token response: {e}"))?;
+        Ok(CachedToken {
+            access_token: token.access_token,
+            expires_at: token.expires_in.map(|secs| Instant::now() + Duration::from_secs(secs)),
+            refresh_token: token.refresh_token,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<Instant>,
+    refresh_token: Option<String>,
+}
+
+impl CachedToken {
+    fn is_fresh(&self) -> bool {
+        self.expires_at.is_none_or(|at| Instant::now() + EXPIRY_MARGIN < at)
+    }
+}
+
+/// OAuth2 access tokens shared by every clone of a context, keyed by token endpoint, client, scopes and
+/// audiences.
+#[derive(Debug, Clone, Default)]
+pub struct TokenCache {
+    tokens: Arc<Mutex<HashMap<String, CachedToken>>>,
+}
+
+impl TokenCache {
+    /// Returns a cached token for `client`, fetching or refreshing it once expired.
+    async fn token(&self, ctx: &WorkflowContext, client: &OAuth2Client) -> StepResult<String> {
+        let key = format!(
+            "{} {} {} {}",
+            client.token_url,
+            client.client_id,
+            client.scopes.join(" "),
+            client.audiences.join(" ")
+        );
+        let cached = self.lock().get(&key).cloned();
+        let token = match cached {
+            Some(token) if token.is_fresh() => return Ok(token.access_token),
+            Some(CachedToken { refresh_token: Some(refresh_token), .. }) => {
+                match client.request_token(ctx, Some(&refresh_token)).await {
+                    Ok(token) => token,
+                    Err(_) => client.request_token(ctx, None).await?,
+                }
+            }
+            _ => client.request_token(ctx, None).await?,
+        };
+        let access_token = token.access_token.clone();
+        self.lock().insert(key, token);
+        Ok(access_token)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, CachedToken>> {
+        self.tokens.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Request;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::nodes::testing::serve;
+    use crate::runtime::interceptor::{HttpInterceptor, HttpResponse};
+    use crate::runtime::secrets::InMemorySecrets;
+
+    fn request(url: &str) -> reqwest::Request {
+        reqwest::Client::new().get(url).build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn static_schemes_set_credentials() {
+        let ctx = WorkflowContext::default();
+        let cases = [
+            (json!({ "basic": { "username": "ada", "password": "secret" } }), "authorization", "Basic YWRhOnNlY3JldA=="),
+            (json!({ "bearer": { "token": "t0k" } }), "authorization", "Bearer t0k"),
+            (json!({ "apiKey": { "name": "x-api-key", "value": "k3y" } }), "x-api-key", "k3y"),
+        ];
+        for (policy, header, expected) in cases {
+            let mut req = request("http://localhost/items");
+            AuthenticationRef::try_from_value(&policy).unwrap().apply(&ctx, &mut req).await.unwrap();
+            assert_eq!(req.headers()[header], expected);
+        }
+
+        let policy = json!({ "apiKey": { "name": "key", "value": "k3y", "in": "query" } });
+        let mut req = request("http://localhost/items?page=2");
+        AuthenticationRef::try_from_value(&policy).unwrap().apply(&ctx, &mut req).await.unwrap();
+        assert_eq!(req.url().query(), Some("page=2&key=k3y"));
+
+        let named = AuthenticationRef::try_from_value(&json!({ "use": "partner" })).unwrap();
+        assert_eq!(named, AuthenticationRef::Named("partner".into()));
+        let err = named.apply(&ctx, &mut request("http://localhost")).await.unwrap_err();
+        assert_eq!(err, "unknown authentication policy 'partner'");
+        let incomplete = json!({ "oauth2": { "authority": "http://a", "grant": "password" } });
+        assert!(AuthenticationRef::try_from_value(&incomplete).is_err());
+    }
+
+    #[tokio::test]
+    async fn oauth2_token_is_fetched_once_and_reused() {
+        let server = serve(vec![(
+            "200 OK",
+            "application/json",
+            json!({ "access_token": "abc", "token_type": "Bearer", "expires_in": 3600 }).to_string(),
+        )]);
+        let policy = json!({
+            "oauth2": {
+                "authority": server.url,
+                "client": { "id": "tideloom", "secret": "s3cret" },
+                "scopes": ["orders:read"]
+            }
+        });
+        let auth = AuthenticationRef::try_from_value(&policy).unwrap();
+        let ctx = WorkflowContext::default();
+
+        for _ in 0..2 {
+            let mut req = request("http://localhost/orders");
+            auth.apply(&ctx, &mut req).await.unwrap();
+            assert_eq!(req.headers()[AUTHORIZATION], "Bearer abc");
+        }
+
+        let token_request = server.next_request();
+        assert!(token_request.starts_with("POST /oauth2/token "));
+        for field in [
+            "grant_type=client_credentials",
+            "client_id=tideloom",
+            "client_secret=s3cret",
+            "scope=orders%3Aread",
+        ] {
+            assert!(token_request.contains(field), "{field} missing from {token_request}");
+        }
+    }
+
+    #[tokio::test]
+    async fn oauth2_tokens_are_kept_per_audience() {
+        let token = |access_token: &str| {
+            let body = json!({ "access_token": access_token, "expires_in": 3600 }).to_string();
+            ("200 OK", "application/json", body)
+        };
+        let server = serve(vec![token("orders"), token("billing")]);
+        let ctx = WorkflowContext::default();
+
+        for audience in ["orders", "billing", "orders"] {
+            let policy = json!({
+                "oauth2": { "authority": server.url, "client": { "id": "tideloom" }, "audiences": [audience] }
+            });
+            let mut req = request("http://localhost/items");
+            AuthenticationRef::try_from_value(&policy).unwrap().apply(&ctx, &mut req).await.unwrap();
+            assert_eq!(req.headers()[AUTHORIZATION], format!("Bearer {audience}").as_str());
+        }
+
+        assert!(server.next_request().contains("audience=orders"));
+        assert!(server.next_request().contains("audience=billing"));
+    }
+
+    /// Answers OAuth2 token requests itself.
+    struct TokenIssuer;
+
+    #[async_trait::async_trait]
+    impl HttpInterceptor for TokenIssuer {
+        async fn before_request(&self, _: &WorkflowContext, req: &mut Request) -> StepResult<Option<HttpResponse>> {
+            assert_eq!(req.url().path(), "/oauth2/token");
+            let body = json!({ "access_token": "intercepted" }).to_string();
+            Ok(Some(HttpResponse::new(reqwest::StatusCode::OK, body)))
+        }
+    }
+
+    #[tokio::test]
+    async fn oauth2_token_requests_go_through_interceptors() {
+        let policy = json!({ "oauth2": { "authority": "http://auth.invalid", "client": { "id": "tideloom" } } });
+        let ctx = WorkflowContext::default().with_http_interceptor(Arc::new(TokenIssuer));
+
+        let mut req = request("http://localhost/items");
+        AuthenticationRef::try_from_value(&policy).unwrap().apply(&ctx, &mut req).await.unwrap();
+
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer intercepted");
+    }
+
+    #[tokio::test]
+    async fn calls_use_named_workflow_policies() {
+        let ok = || ("200 OK", "application/json", "{}".to_string());
+        let server = serve(vec![ok(), ok(), ok()]);
+        let workflow = Workflow::try_from_yaml(&format!(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: authenticated
+  version: '1.0.0'
+use:
+  secrets: [vaultToken, vaultLogin]
+  authentications:
+    partner:
+      bearer:
+        token: t0k
+    vault:
+      bearer:
+        use: vaultToken
+do:
+- fetch:
+    call: http
+    with:
+      method: get
+      endpoint:
+        uri: {url}/orders
+        authentication:
+          use: partner
+- fetchFromVault:
+    call: http
+    with:
+      method: get
+      endpoint: {url}/orders
+      headers:
+        x-vault-token: ${{ $secrets.vaultToken }}
+      authentication: vault
+- login:
+    call: http
+    with:
+      method: get
+      endpoint: {url}/orders
+      authentication:
+        basic:
+          use: vaultLogin
+"#,
+            url = server.url
+        ))
+        .unwrap();
+        let secrets = InMemorySecrets::new()
+            .with_secret("vaultToken", "v4ult")
+            .with_secret("vaultLogin", r#"{"username":"ada","password":"secret"}"#);
+
+        workflow.run(&WorkflowContext::default().with_secrets(Arc::new(secrets)), json!({})).await.unwrap();
+
+        assert!(server.next_request().to_lowercase().contains("authorization: bearer t0k"));
+        let request = server.next_request().to_lowercase();
+        assert!(request.contains("authorization: bearer v4ult") && request.contains("x-vault-token: v4ult"));
+        assert!(server.next_request().contains("Basic YWRhOnNlY3JldA=="));
+    }
+}
@@ -0,0 +1,84 @@
+//! A bounded pool for synchronous or CPU-heavy work, such as parsing large documents, that would otherwise
+//! stall the async workers running other instances.
+
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+use crate::runtime::StepResult;
+
+/// Runs blocking work on Tokio's blocking threads, at most `size` jobs at a time; further jobs wait for a
+/// free slot. Clones share their slots.
+///
+/// Tasks reach the pool through [`WorkflowContext::blocking`](crate::runtime::WorkflowContext::blocking).
+#[derive(Debug, Clone)]
+pub struct BlockingPool {
+    slots: Arc<Semaphore>,
+    size: usize,
+}
+
+impl BlockingPool {
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(size.max(1))),
+            size: size.max(1),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Runs `work` once a slot is free, without blocking the calling async worker.
+    pub async fn run<T, F>(&self, work: F) -> StepResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let slot = self.slots.clone().acquire_owned().await.map_err(|e| e.to_string())?;
+        tokio::task::spawn_blocking(move || {
+            let _slot = slot;
+            work()
+        })
+        .await
+        .map_err(|e| format!("blocking work failed: {e}").into())
+    }
+}
+
+impl Default for BlockingPool {
+    /// One slot per available CPU.
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn jobs_beyond_the_pool_size_wait_for_a_slot() {
+        let pool = BlockingPool::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let jobs = (0..5).map(|i| {
+            let (running, peak) = (running.clone(), peak.clone());
+            pool.run(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(30));
+                running.fetch_sub(1, Ordering::SeqCst);
+                i * 2
+            })
+        });
+        let results = futures::future::try_join_all(jobs).await.unwrap();
+
+        assert_eq!(results, [0, 2, 4, 6, 8]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(BlockingPool::new(0).size(), 1);
+    }
+}
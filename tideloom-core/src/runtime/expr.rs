@@ -0,0 +1,190 @@
+//! Minimal runtime expression support.
+//!
+//! Until a full jq engine is available, expressions are limited to plain paths such as `.data.orderId`
+//! or `${ .items.0 }`, optionally rooted at a runtime variable such as `$context.tenant`.
+//! Anything else is rejected so callers can surface an explicit error.
+
+use serde_json::{Map, Value};
+
+use crate::runtime::StepResult;
+
+pub fn is_expression(s: &str) -> bool {
+    let s = s.trim();
+    s.starts_with("${") && s.ends_with('}')
+}
+
+/// Parses a simple path expression such as `.data.orderId` or `${ .data.items.0 }`.
+///
+/// A path rooted at a variable, such as `$context.tenant`, yields the variable name (`$context`) as its
+/// first segment.
+pub fn parse_path(expr: &str) -> StepResult<Vec<String>> {
+    let trimmed = expr.trim();
+    let path = match trimmed.strip_prefix("${").and_then(|s| s.strip_suffix('}')) {
+        Some(inner) => inner.trim(),
+        None => trimmed,
+    };
+    let unsupported = || format!("unsupported expression '{expr}': expected a path like `.data.id`");
+    let (variable, path) = match path.strip_prefix('$') {
+        Some(rest) => match rest.split_once('.') {
+            Some((name, rest)) => (Some(name), rest),
+            None => (Some(rest), ""),
+        },
+        None => (None, path.strip_prefix('.').ok_or_else(unsupported)?),
+    };
+    let is_segment = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    let mut segments = Vec::new();
+    if let Some(name) = variable {
+        if !is_segment(name) {
+            return Err(unsupported().into());
+        }
+        segments.push(format!("${name}"));
+    }
+    if path.is_empty() {
+        return Ok(segments);
+    }
+    for segment in path.split('.') {
+        if !is_segment(segment) {
+            return Err(unsupported().into());
+        }
+        segments.push(segment.to_string());
+    }
+    Ok(segments)
+}
+
+pub fn select(value: &Value, expr: &str) -> Option<Value> {
+    select_with(value, &Map::new(), expr)
+}
+
+/// Like [`select`], resolving paths rooted at a variable (`$context...`) against `vars`.
+pub fn select_with(value: &Value, vars: &Map<String, Value>, expr: &str) -> Option<Value> {
+    let mut segments = parse_path(expr).ok()?.into_iter().peekable();
+    let mut current = match segments.next_if(|s| s.starts_with('$')) {
+        Some(variable) => vars.get(&variable)?,
+        None => value,
+    };
+    for segment in segments {
+        current = match current {
+            Value::Object(map) => map.get(&segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current.clone())
+}
+
+/// Resolves every expression string nested in `value` against `input`, leaving literals untouched.
+pub fn resolve(value: &Value, input: &Value) -> StepResult<Value> {
+    resolve_with(value, input, &Map::new())
+}
+
+/// Like [`resolve`], also exposing runtime variables such as `$context` to the expressions.
+pub fn resolve_with(value: &Value, input: &Value, vars: &Map<String, Value>) -> StepResult<Value> {
+    match value {
+        Value::String(s) if is_expression(s) => {
+            parse_path(s)?;
+            Ok(select_with(input, vars, s).unwrap_or(Value::Null))
+        }
+        Value::Array(items) => items.iter().map(|item| resolve_with(item, input, vars)).collect(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), resolve_with(v, input, vars)?)))
+            .collect::<StepResult<_>>()
+            .map(Value::Object),
+        other => Ok(other.clone()),
+    }
+}
+
+/// Evaluates a condition such as `if` or a switch case's `when`.
+/// A missing or null result is false; any other value that is not a boolean is an error.
+pub fn holds_with(value: &Value, vars: &Map<String, Value>, expr: &str) -> StepResult<bool> {
+    match select_with(value, vars, expr) {
+        Some(Value::Bool(holds)) => Ok(holds),
+        None | Some(Value::Null) => Ok(false),
+        Some(other) => Err(format!("'{expr}' evaluated to {other}, expected a boolean").into()),
+    }
+}
+
+/// Replaces every expression embedded in `text`, as in `Hello, ${ .user.name }`, with the string form of its value.
+///
+/// Text between `${` and `}` that is not a path, such as a shell's `${HOME}`, is left as is.
+pub fn interpolate_with(text: &str, input: &Value, vars: &Map<String, Value>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}').map(|end| end + 1) else {
+            break;
+        };
+        let expr = &rest[start..start + len];
+        out.push_str(&rest[..start]);
+        match parse_path(expr) {
+            Ok(_) => out.push_str(&value_to_string(&select_with(input, vars, expr).unwrap_or(Value::Null))),
+            Err(_) => out.push_str(expr),
+        }
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn select_paths() {
+        let input = json!({ "data": { "items": [{ "id": 1 }] } });
+        assert_eq!(select(&input, ".data.items.0.id"), Some(json!(1)));
+        assert_eq!(select(&input, "${ .data.items }"), Some(json!([{ "id": 1 }])));
+        assert_eq!(select(&input, ".data.missing"), None);
+        assert!(parse_path(".data | length").is_err());
+    }
+
+    #[test]
+    fn interpolate_embedded_expressions() {
+        let input = json!({ "user": { "name": "ada", "age": 36 } });
+        let interpolate = |text| interpolate_with(text, &input, &Map::new());
+        assert_eq!(interpolate("Hello, ${ .user.name } (${.user.age})"), "Hello, ada (36)");
+        assert_eq!(interpolate("echo ${HOME} ${ .missing }"), "echo ${HOME} null");
+        assert_eq!(interpolate("unclosed ${ .user"), "unclosed ${ .user");
+    }
+
+    #[test]
+    fn resolve_nested_expressions() {
+        let input = json!({ "order": { "id": 7 } });
+        let template = json!({ "orderId": "${ .order.id }", "static": "value", "list": ["${ .order }"] });
+        assert_eq!(
+            resolve(&template, &input).unwrap(),
+            json!({ "orderId": 7, "static": "value", "list": [{ "id": 7 }] })
+        );
+    }
+
+    #[test]
+    fn resolve_variables() {
+        let mut vars = Map::new();
+        vars.insert("$context".to_string(), json!({ "tenant": "acme" }));
+        let template = json!({ "tenant": "${ $context.tenant }", "all": "${ $context }", "unknown": "${ $env.x }" });
+        assert_eq!(
+            resolve_with(&template, &json!({}), &vars).unwrap(),
+            json!({ "tenant": "acme", "all": { "tenant": "acme" }, "unknown": null })
+        );
+        assert_eq!(parse_path("$context.a").unwrap(), ["$context", "a"]);
+        assert!(parse_path("$.a").is_err());
+    }
+
+    #[test]
+    fn conditions_must_be_boolean() {
+        let input = json!({ "ok": true, "count": 1 });
+        assert!(holds_with(&input, &Map::new(), "${ .ok }").unwrap());
+        assert!(!holds_with(&input, &Map::new(), "${ .missing }").unwrap());
+        assert!(holds_with(&input, &Map::new(), "${ .count }").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Feature flags exposed to task `if` conditions as `$flags.NAME`.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::runtime::StepResult;
+use crate::runtime::metering::InstanceInfo;
+
+/// Who a flag is evaluated for, so that providers can target rollouts.
+#[derive(Debug, Clone, Copy)]
+pub struct FlagTarget<'a> {
+    pub tenant: Option<&'a str>,
+    pub instance: &'a InstanceInfo,
+}
+
+/// Evaluates feature flags, e.g. through a LaunchDarkly or Unleash client; registered on the context with
+/// [`WorkflowContext::with_flags`](crate::runtime::WorkflowContext::with_flags).
+///
+/// Flags are evaluated each time a task that reads them starts, so a rollout takes effect without
+/// redeploying the workflow.
+#[async_trait::async_trait]
+pub trait FlagProvider: Send + Sync {
+    /// Returns the value of flag `name` for `target`, or `None` when the provider does not know it.
+    async fn evaluate(&self, name: &str, target: FlagTarget<'_>) -> StepResult<Option<Value>>;
+}
+
+/// Reads flags from environment variables named `{prefix}{name}`. Values are parsed as JSON, so `true` and
+/// `false` are booleans; anything else is a string.
+#[derive(Debug, Clone, Default)]
+pub struct EnvFlags {
+    prefix: String,
+}
+
+impl EnvFlags {
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self { prefix: prefix.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl FlagProvider for EnvFlags {
+    async fn evaluate(&self, name: &str, _target: FlagTarget<'_>) -> StepResult<Option<Value>> {
+        Ok(std::env::var(format!("{}{name}", self.prefix))
+            .ok()
+            .map(|raw| serde_json::from_str(&raw).unwrap_or(Value::String(raw))))
+    }
+}
+
+/// Flags held in memory, optionally overridden per tenant.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryFlags {
+    flags: HashMap<String, Value>,
+    tenants: HashMap<(String, String), Value>,
+}
+
+impl InMemoryFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_flag(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.flags.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_tenant_flag(
+        mut self,
+        tenant: impl Into<String>,
+        name: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.tenants.insert((tenant.into(), name.into()), value.into());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl FlagProvider for InMemoryFlags {
+    async fn evaluate(&self, name: &str, target: FlagTarget<'_>) -> StepResult<Option<Value>> {
+        let tenant = target
+            .tenant
+            .and_then(|tenant| self.tenants.get(&(tenant.to_string(), name.to_string())));
+        Ok(tenant.or_else(|| self.flags.get(name)).cloned())
+    }
+}
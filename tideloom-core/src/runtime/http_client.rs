@@ -0,0 +1,224 @@
+//! HTTP clients of call tasks, configured per host.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use reqwest::{Client, Url};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::runtime::StepResult;
+
+/// Settings of the HTTP client used for some or all hosts.
+///
+/// Clients never follow redirects themselves: the `redirect` argument of a call decides whether a 3xx response
+/// is its result or an error.
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientConfig {
+    pub connect_timeout: Option<Duration>,
+    /// Longest wait for the next chunk of a response.
+    pub read_timeout: Option<Duration>,
+    /// Proxy for every request, such as `http://proxy.internal:3128`.
+    pub proxy: Option<String>,
+    /// PEM encoded client certificate and PKCS #8 private key presented for mutual TLS.
+    pub identity: Option<(Vec<u8>, Vec<u8>)>,
+    /// Idle connections kept open per host.
+    pub max_idle_per_host: Option<usize>,
+    /// Requests in flight per host, across every instance sharing the clients; further requests wait.
+    pub max_concurrent_per_host: Option<usize>,
+}
+
+impl HttpClientConfig {
+    fn build(&self) -> StepResult<Client> {
+        let mut builder = Client::builder().redirect(reqwest::redirect::Policy::none());
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(|e| format!("invalid proxy '{proxy}': {e}"))?);
+        }
+        if let Some((certificate, key)) = &self.identity {
+            let identity = reqwest::Identity::from_pkcs8_pem(certificate, key)
+                .map_err(|e| format!("invalid client certificate: {e}"))?;
+            builder = builder.identity(identity);
+        }
+        if let Some(idle) = self.max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(idle);
+        }
+        builder.build().map_err(|e| format!("failed to build http client: {e}").into())
+    }
+}
+
+/// Bounds on the response bodies read by HTTP calls.
+#[derive(Debug, Clone, Default)]
+pub struct HttpBodyLimits {
+    /// Largest body read; a call whose response is longer fails as soon as the limit is crossed.
+    pub max_bytes: Option<u64>,
+    /// Bodies longer than this are written to a file in `spool_dir` instead of being kept in memory, and the
+    /// call outputs a reference to the file.
+    ///
+    /// The file of a successful call is never deleted by the engine, since later tasks and the workflow output
+    /// refer to it: the host owns it and cleans up `spool_dir`. The file is deleted if reading the body fails or the
+    /// response status is rejected.
+    pub spool_above: Option<u64>,
+    /// Directory of spooled bodies; the system's temporary directory by default.
+    pub spool_dir: Option<PathBuf>,
+}
+
+/// The HTTP clients of `call: http` and `call: openapi` tasks: one per host with its own configuration and a
+/// default one for the others. Each client keeps its own connection pool; clones share clients, pools and
+/// concurrency limits.
+#[derive(Debug, Clone)]
+pub struct HttpClients {
+    default: Route,
+    hosts: HashMap<String, Route>,
+    /// Per-host request slots, created on first use.
+    slots: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    client: Client,
+    max_concurrent: Option<usize>,
+}
+
+impl Route {
+    fn build(config: &HttpClientConfig) -> StepResult<Self> {
+        Ok(Self {
+            client: config.build()?,
+            max_concurrent: config.max_concurrent_per_host,
+        })
+    }
+}
+
+impl HttpClients {
+    pub fn new(default: &HttpClientConfig) -> StepResult<Self> {
+        Ok(Self {
+            default: Route::build(default)?,
+            hosts: HashMap::new(),
+            slots: Arc::default(),
+        })
+    }
+
+    /// Clients built from the default configuration, shared by every context that configured none.
+    pub(crate) fn shared() -> &'static Self {
+        static SHARED: OnceLock<HttpClients> = OnceLock::new();
+        SHARED.get_or_init(|| Self::new(&HttpClientConfig::default()).expect("default http client"))
+    }
+
+    /// Uses a client built from `config` for requests to `host`, such as `api.example.com`.
+    pub fn with_host(mut self, host: &str, config: &HttpClientConfig) -> StepResult<Self> {
+        self.hosts.insert(host.to_ascii_lowercase(), Route::build(config)?);
+        Ok(self)
+    }
+
+    /// The client for requests to `url`.
+    pub fn client(&self, url: &Url) -> &Client {
+        &self.route(url).client
+    }
+
+    /// The client for hosts without their own configuration.
+    pub fn client_for_default(&self) -> &Client {
+        &self.default.client
+    }
+
+    /// Returns the client for `url` once a request slot for its host is free. Hold the permit until the
+    /// response is read.
+    pub async fn acquire(&self, url: &Url) -> (Client, Option<OwnedSemaphorePermit>) {
+        let route = self.route(url);
+        let Some(max) = route.max_concurrent else {
+            return (route.client.clone(), None);
+        };
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let slots = self
+            .slots
+            .lock()
+            .unwrap()
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(max.max(1))))
+            .clone();
+        // The semaphore is never closed.
+        let permit = slots.acquire_owned().await.ok();
+        (route.client.clone(), permit)
+    }
+
+    fn route(&self, url: &Url) -> &Route {
+        url.host_str()
+            .and_then(|host| self.hosts.get(&host.to_ascii_lowercase()))
+            .unwrap_or(&self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::nodes::testing::serve_once;
+    use crate::runtime::WorkflowContext;
+
+    #[tokio::test]
+    async fn requests_per_host_are_capped() {
+        let limited = HttpClientConfig {
+            max_concurrent_per_host: Some(1),
+            ..Default::default()
+        };
+        let clients = HttpClients::new(&HttpClientConfig::default())
+            .unwrap()
+            .with_host("API.example.com", &limited)
+            .unwrap();
+        let api = Url::parse("https://api.example.com/orders").unwrap();
+        let other = Url::parse("https://other.example.com/").unwrap();
+
+        let (_, first) = clients.acquire(&api).await;
+        assert!(first.is_some());
+        assert!(clients.acquire(&other).await.1.is_none());
+        let second = tokio::time::timeout(Duration::from_millis(20), clients.acquire(&api)).await;
+        assert!(second.is_err(), "a second request to the host must wait");
+        drop(first);
+        assert!(clients.acquire(&api).await.1.is_some());
+    }
+
+    #[tokio::test]
+    async fn calls_use_the_configured_clients() {
+        let server = serve_once("200 OK", "application/json", r#"{"ok":true}"#);
+        let config = HttpClientConfig {
+            connect_timeout: Some(Duration::from_secs(1)),
+            read_timeout: Some(Duration::from_secs(1)),
+            max_idle_per_host: Some(2),
+            max_concurrent_per_host: Some(4),
+            ..Default::default()
+        };
+        let ctx = WorkflowContext::default().with_http_clients(HttpClients::new(&config).unwrap());
+        let workflow = Workflow::try_from_yaml(&format!(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: fetch
+  version: '1.0.0'
+do:
+- fetch:
+    call: http
+    with:
+      method: get
+      endpoint: {}/status
+",
+            server.url
+        ))
+        .unwrap();
+
+        assert_eq!(workflow.run(&ctx, json!({})).await.unwrap(), json!({ "ok": true }));
+
+        let proxy = HttpClientConfig {
+            proxy: Some("not a proxy url".into()),
+            ..Default::default()
+        };
+        assert!(HttpClients::new(&proxy).unwrap_err().contains("invalid proxy"));
+    }
+}
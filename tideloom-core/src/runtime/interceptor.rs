@@ -0,0 +1,137 @@
+//! Hooks on the outbound requests of HTTP calls, including OpenAPI calls, e.g. to sign them or add tenant
+//! headers.
+
+use std::path::PathBuf;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Request, StatusCode};
+
+use crate::runtime::{StepResult, WorkflowContext};
+
+/// A received, or interceptor-provided, HTTP response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    /// File holding the body instead of `body`, when it was longer than
+    /// [`HttpBodyLimits::spool_above`](crate::runtime::http_client::HttpBodyLimits::spool_above).
+    pub spooled: Option<PathBuf>,
+}
+
+impl HttpResponse {
+    pub fn new(status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+            spooled: None,
+        }
+    }
+}
+
+/// Middleware around every request of `call: http` and `call: openapi` tasks, and around the OAuth2 token requests
+/// of their authentication; registered on the context with
+/// [`WorkflowContext::with_http_interceptor`](crate::runtime::WorkflowContext::with_http_interceptor).
+///
+/// `before_request` hooks run in registration order once the request is authenticated, and `after_response`
+/// hooks in reverse order before the task reads the response. An error fails the task.
+#[async_trait::async_trait]
+pub trait HttpInterceptor: Send + Sync {
+    /// May change the request, or answer it: a returned response is used instead of sending the request, and
+    /// skips the `before_request` of later interceptors.
+    async fn before_request(&self, _ctx: &WorkflowContext, _request: &mut Request) -> StepResult<Option<HttpResponse>> {
+        Ok(None)
+    }
+
+    async fn after_response(
+        &self,
+        _ctx: &WorkflowContext,
+        _request: &Request,
+        _response: &mut HttpResponse,
+    ) -> StepResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::nodes::testing::serve_once;
+
+    /// Adds the tenant header to every request and replaces every response body with its status.
+    struct TenantHeader;
+
+    #[async_trait::async_trait]
+    impl HttpInterceptor for TenantHeader {
+        async fn before_request(&self, ctx: &WorkflowContext, req: &mut Request) -> StepResult<Option<HttpResponse>> {
+            let tenant = HeaderValue::from_str(ctx.tenant.as_deref().unwrap_or_default()).map_err(|e| e.to_string())?;
+            req.headers_mut().insert("x-tenant", tenant);
+            Ok(None)
+        }
+
+        async fn after_response(&self, _: &WorkflowContext, _: &Request, res: &mut HttpResponse) -> StepResult<()> {
+            res.body = format!(r#"{{"status":{}}}"#, res.status.as_u16()).into_bytes();
+            Ok(())
+        }
+    }
+
+    /// Answers every request itself.
+    struct Cached;
+
+    #[async_trait::async_trait]
+    impl HttpInterceptor for Cached {
+        async fn before_request(&self, _: &WorkflowContext, _: &mut Request) -> StepResult<Option<HttpResponse>> {
+            Ok(Some(HttpResponse::new(StatusCode::OK, r#"{"cached":true}"#)))
+        }
+    }
+
+    fn fetch(url: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: fetch
+  version: '1.0.0'
+do:
+- fetch:
+    call: http
+    with:
+      method: get
+      endpoint: {url}/orders
+"
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn interceptors_wrap_requests_and_responses() {
+        let server = serve_once("201 Created", "application/json", "{}");
+        let ctx = WorkflowContext::default()
+            .with_tenant("acme")
+            .with_http_interceptor(Arc::new(TenantHeader));
+
+        let output = fetch(&server.url).run(&ctx, json!({})).await.unwrap();
+
+        assert_eq!(output, json!({ "status": 201 }));
+        assert!(server.next_request().to_lowercase().contains("x-tenant: acme"));
+    }
+
+    #[tokio::test]
+    async fn an_interceptor_can_answer_without_sending() {
+        let ctx = WorkflowContext::default()
+            .with_http_interceptor(Arc::new(Cached))
+            .with_http_interceptor(Arc::new(TenantHeader));
+
+        let output = fetch("http://orders.invalid").run(&ctx, json!({})).await.unwrap();
+
+        assert_eq!(output, json!({ "status": 200 }));
+    }
+}
@@ -0,0 +1,109 @@
+//! Per task kind concurrency caps and in-flight counts, shared by every instance using the same context.
+//!
+//! Task kinds are `http`, `openapi`, `asyncapi` and other `call` types, `run`, `emit` and `listen`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::runtime::StepResult;
+
+#[derive(Debug, Default)]
+struct Slot {
+    semaphore: Option<Arc<Semaphore>>,
+    in_flight: AtomicUsize,
+}
+
+/// Caps how many tasks of each kind run at once, so one slow connector cannot hold every worker.
+///
+/// Kinds without a cap are unlimited but still counted. Clones share the same caps and counts.
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimits {
+    slots: Arc<Mutex<HashMap<String, Arc<Slot>>>>,
+}
+
+impl ConcurrencyLimits {
+    /// Allows at most `max` tasks of `kind` to run at once; further tasks wait for a free slot.
+    pub fn with_cap(self, kind: &str, max: usize) -> Self {
+        let slot = Slot {
+            semaphore: Some(Arc::new(Semaphore::new(max))),
+            in_flight: AtomicUsize::new(0),
+        };
+        self.lock().insert(kind.to_string(), Arc::new(slot));
+        self
+    }
+
+    /// Number of tasks of `kind` currently running, excluding those waiting for a slot.
+    pub fn in_flight(&self, kind: &str) -> usize {
+        self.lock()
+            .get(kind)
+            .map_or(0, |slot| slot.in_flight.load(Ordering::SeqCst))
+    }
+
+    /// In-flight counts of every kind seen so far.
+    pub fn snapshot(&self) -> BTreeMap<String, usize> {
+        self.lock()
+            .iter()
+            .map(|(kind, slot)| (kind.clone(), slot.in_flight.load(Ordering::SeqCst)))
+            .collect()
+    }
+
+    /// Waits for a slot of `kind`; the task counts as in flight until the returned guard is dropped.
+    pub async fn acquire(&self, kind: &str) -> StepResult<InFlight> {
+        let slot = self.lock().entry(kind.to_string()).or_default().clone();
+        let permit = match &slot.semaphore {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| format!("{kind} concurrency limit: {e}"))?,
+            ),
+            None => None,
+        };
+        slot.in_flight.fetch_add(1, Ordering::SeqCst);
+        Ok(InFlight { slot, _permit: permit })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<Slot>>> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A running task counted by [`ConcurrencyLimits`]; releases its slot when dropped.
+#[derive(Debug)]
+pub struct InFlight {
+    slot: Arc<Slot>,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.slot.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn caps_block_until_a_slot_is_released() {
+        let limits = ConcurrencyLimits::default().with_cap("http", 1);
+
+        let first = limits.acquire("http").await.unwrap();
+        let _run = limits.acquire("run").await.unwrap();
+        assert_eq!(limits.in_flight("http"), 1);
+        assert!(tokio::time::timeout(Duration::from_millis(20), limits.acquire("http")).await.is_err());
+
+        drop(first);
+        let _second = limits.acquire("http").await.unwrap();
+        let snapshot = limits.snapshot();
+        assert_eq!(snapshot["http"], 1);
+        assert_eq!(snapshot["run"], 1);
+    }
+}
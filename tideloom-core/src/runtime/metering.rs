@@ -0,0 +1,99 @@
+//! Usage records of effectful tasks, for chargeback on shared platforms.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Identifies the workflow instance a task runs in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstanceInfo {
+    /// Definition key, see [`workflow_key`](crate::runtime::workflow_key).
+    pub workflow: String,
+    /// Unique id of the instance.
+    pub id: String,
+    /// The instance that started this one through a `run: workflow` task, if any.
+    pub parent: Option<Box<InstanceInfo>>,
+}
+
+/// One execution of an effectful task (`call`, `run`, `emit` or `listen`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    /// Task kind, as used by [`ConcurrencyLimits`](crate::runtime::limits::ConcurrencyLimits).
+    pub kind: String,
+    pub instance: InstanceInfo,
+    pub tenant: Option<String>,
+    pub duration: Duration,
+    /// Size of the task input, serialized as JSON.
+    pub bytes_in: usize,
+    /// Size of the task output serialized as JSON; zero when the task failed.
+    pub bytes_out: usize,
+    pub succeeded: bool,
+}
+
+/// Receives a [`Usage`] after every effectful task.
+pub trait Meter: Send + Sync {
+    fn record(&self, usage: &Usage);
+}
+
+/// Summed usage of a group of task executions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageTotals {
+    pub executions: u64,
+    pub failures: u64,
+    pub duration: Duration,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &Usage) {
+        self.executions += 1;
+        self.failures += u64::from(!usage.succeeded);
+        self.duration += usage.duration;
+        self.bytes_in += usage.bytes_in as u64;
+        self.bytes_out += usage.bytes_out as u64;
+    }
+}
+
+/// A [`Meter`] summing usage per instance, per workflow definition and per tenant.
+#[derive(Debug, Default)]
+pub struct UsageAggregator {
+    totals: Mutex<Aggregates>,
+}
+
+#[derive(Debug, Default)]
+struct Aggregates {
+    instances: HashMap<String, UsageTotals>,
+    workflows: HashMap<String, UsageTotals>,
+    tenants: HashMap<String, UsageTotals>,
+}
+
+impl UsageAggregator {
+    pub fn by_instance(&self) -> HashMap<String, UsageTotals> {
+        self.lock().instances.clone()
+    }
+
+    pub fn by_workflow(&self) -> HashMap<String, UsageTotals> {
+        self.lock().workflows.clone()
+    }
+
+    /// Totals of executions that ran for a tenant; others are only counted per instance and workflow.
+    pub fn by_tenant(&self) -> HashMap<String, UsageTotals> {
+        self.lock().tenants.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Aggregates> {
+        self.totals.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Meter for UsageAggregator {
+    fn record(&self, usage: &Usage) {
+        let mut totals = self.lock();
+        totals.instances.entry(usage.instance.id.clone()).or_default().add(usage);
+        totals.workflows.entry(usage.instance.workflow.clone()).or_default().add(usage);
+        if let Some(tenant) = &usage.tenant {
+            totals.tenants.entry(tenant.clone()).or_default().add(usage);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Counters and histograms about workflows and tasks, for operational dashboards.
+//!
+//! Unlike [`metering`](crate::runtime::metering), which records the usage of effectful tasks for chargeback,
+//! these cover every workflow and task and are meant for a monitoring backend such as Prometheus.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Workflow instances started, labelled with `workflow`.
+pub const WORKFLOWS_STARTED: &str = "tideloom_workflows_started_total";
+/// Workflow instances that produced an output, labelled with `workflow`.
+pub const WORKFLOWS_COMPLETED: &str = "tideloom_workflows_completed_total";
+/// Workflow instances that failed or were cancelled, labelled with `workflow`.
+pub const WORKFLOWS_FAULTED: &str = "tideloom_workflows_faulted_total";
+/// Duration of every task in seconds, labelled with `kind` and `task`.
+pub const TASK_DURATION: &str = "tideloom_task_duration_seconds";
+/// Retries of failed steps, labelled with `step`: the task kind for default retries, `try` otherwise.
+pub const TASK_RETRIES: &str = "tideloom_task_retries_total";
+
+pub type Labels = [(&'static str, String)];
+
+/// Backend receiving the runtime's metrics; registered on the context with
+/// [`WorkflowContext::with_metrics`](crate::runtime::WorkflowContext::with_metrics).
+pub trait MetricsRecorder: Send + Sync {
+    fn increment_counter(&self, name: &'static str, labels: &Labels);
+    fn record_histogram(&self, name: &'static str, labels: &Labels, value: f64);
+}
+
+/// A [`MetricsRecorder`] keeping every metric in memory, keyed by name and labels.
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    counters: Mutex<HashMap<String, u64>>,
+    histograms: Mutex<HashMap<String, Vec<f64>>>,
+}
+
+impl InMemoryMetrics {
+    /// Value of counter `name` with exactly `labels`, zero if it was never incremented.
+    pub fn counter(&self, name: &str, labels: &Labels) -> u64 {
+        self.counters.lock().unwrap().get(&key(name, labels)).copied().unwrap_or(0)
+    }
+
+    /// Values recorded into histogram `name` with exactly `labels`.
+    pub fn histogram(&self, name: &str, labels: &Labels) -> Vec<f64> {
+        self.histograms.lock().unwrap().get(&key(name, labels)).cloned().unwrap_or_default()
+    }
+}
+
+impl MetricsRecorder for InMemoryMetrics {
+    fn increment_counter(&self, name: &'static str, labels: &Labels) {
+        *self.counters.lock().unwrap().entry(key(name, labels)).or_default() += 1;
+    }
+
+    fn record_histogram(&self, name: &'static str, labels: &Labels, value: f64) {
+        self.histograms.lock().unwrap().entry(key(name, labels)).or_default().push(value);
+    }
+}
+
+fn key(name: &str, labels: &Labels) -> String {
+    let labels: Vec<_> = labels.iter().map(|(label, value)| format!("{label}={value:?}")).collect();
+    format!("{name}{{{}}}", labels.join(","))
+}
+
+/// A [`MetricsRecorder`] forwarding to the global recorder of the `metrics` crate, e.g. a Prometheus exporter.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsFacade;
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for MetricsFacade {
+    fn increment_counter(&self, name: &'static str, labels: &Labels) {
+        ::metrics::counter!(name, labels).increment(1);
+    }
+
+    fn record_histogram(&self, name: &'static str, labels: &Labels, value: f64) {
+        ::metrics::histogram!(name, labels).record(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::runtime::WorkflowContext;
+    use crate::runtime::retry::{RetryDefaults, RetryPolicy};
+
+    #[tokio::test]
+    async fn workflows_and_tasks_are_counted_and_timed() {
+        let workflow = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: measured
+  version: '1.0.0'
+do:
+- greet:
+    run:
+      shell:
+        command: echo
+        arguments: ['${ .name }']
+- fail:
+    run:
+      shell:
+        command: 'false'
+",
+        )
+        .unwrap();
+        let metrics = Arc::new(InMemoryMetrics::default());
+        let retry = RetryPolicy {
+            delay: Duration::from_millis(1),
+            max_retries: Some(1),
+            ..Default::default()
+        };
+        let ctx = WorkflowContext::default()
+            .with_metrics(metrics.clone())
+            .with_default_retry(RetryDefaults::new(retry));
+
+        workflow.run(&ctx, json!({ "name": "ada" })).await.unwrap_err();
+
+        let workflow = [("workflow", "test/measured:1.0.0".to_string())];
+        assert_eq!(metrics.counter(WORKFLOWS_STARTED, &workflow), 1);
+        assert_eq!(metrics.counter(WORKFLOWS_FAULTED, &workflow), 1);
+        assert_eq!(metrics.counter(WORKFLOWS_COMPLETED, &workflow), 0);
+        assert_eq!(metrics.counter(TASK_RETRIES, &[("step", "run".to_string())]), 1);
+        let greet = metrics.histogram(TASK_DURATION, &[("kind", "run".to_string()), ("task", "greet".to_string())]);
+        assert_eq!(greet.len(), 1);
+        assert!(greet[0] > 0.0);
+    }
+}
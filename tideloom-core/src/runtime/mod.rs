@@ -1,3 +1,21 @@
+pub mod alerts;
+pub mod auth;
+pub mod blocking;
+pub mod expr;
+pub mod flags;
+pub mod http_client;
+pub mod interceptor;
+pub mod limits;
+pub mod memo;
+pub mod metering;
+pub mod metrics;
+pub mod observer;
+pub mod registry;
+pub mod retry;
+pub mod schema;
+pub mod secrets;
 pub mod step;
+pub mod timeout;
 
 pub use step::*;
+pub use tokio_util::sync::CancellationToken;
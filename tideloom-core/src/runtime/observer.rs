@@ -0,0 +1,162 @@
+//! Typed lifecycle events of workflow instances and their tasks, for hosts that track progress.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::WorkflowSummary;
+use crate::runtime::metering::InstanceInfo;
+
+/// A lifecycle event of a workflow instance or one of its tasks.
+///
+/// Inputs, outputs and errors are snapshots with the workflow's secrets redacted. Task positions are JSON
+/// pointers into the workflow document, e.g. `/do/1/pay/try/0/charge`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkflowEvent {
+    WorkflowStarted {
+        instance: InstanceInfo,
+        /// Title, summary, tags and metadata of the instance's definition.
+        definition: Arc<WorkflowSummary>,
+        input: Value,
+        at: DateTime<Utc>,
+    },
+    WorkflowCompleted {
+        instance: InstanceInfo,
+        output: Value,
+        at: DateTime<Utc>,
+        duration: Duration,
+    },
+    /// The instance failed or was cancelled.
+    WorkflowFaulted {
+        instance: InstanceInfo,
+        error: String,
+        at: DateTime<Utc>,
+        duration: Duration,
+    },
+    TaskStarted {
+        instance: InstanceInfo,
+        task: String,
+        position: String,
+        input: Value,
+        at: DateTime<Utc>,
+    },
+    TaskCompleted {
+        instance: InstanceInfo,
+        task: String,
+        position: String,
+        output: Value,
+        at: DateTime<Utc>,
+        duration: Duration,
+    },
+    TaskFaulted {
+        instance: InstanceInfo,
+        task: String,
+        position: String,
+        error: String,
+        at: DateTime<Utc>,
+        duration: Duration,
+    },
+}
+
+impl WorkflowEvent {
+    /// The instance the event is about.
+    pub fn instance(&self) -> &InstanceInfo {
+        match self {
+            Self::WorkflowStarted { instance, .. }
+            | Self::WorkflowCompleted { instance, .. }
+            | Self::WorkflowFaulted { instance, .. }
+            | Self::TaskStarted { instance, .. }
+            | Self::TaskCompleted { instance, .. }
+            | Self::TaskFaulted { instance, .. } => instance,
+        }
+    }
+}
+
+/// Receives the [`WorkflowEvent`]s of every instance run with the context it is registered on, through
+/// [`WorkflowContext::with_observer`](crate::runtime::WorkflowContext::with_observer).
+///
+/// Observers are called inline, so they should hand slow work off rather than block. Any
+/// `Fn(&WorkflowEvent)` closure is an observer.
+pub trait WorkflowObserver: Send + Sync {
+    fn on_event(&self, event: &WorkflowEvent);
+}
+
+impl<F> WorkflowObserver for F
+where
+    F: Fn(&WorkflowEvent) + Send + Sync,
+{
+    fn on_event(&self, event: &WorkflowEvent) {
+        self(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::runtime::WorkflowContext;
+
+    #[tokio::test]
+    async fn observers_receive_typed_lifecycle_events() {
+        let workflow = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: observed
+  version: '1.0.0'
+do:
+- greet:
+    run:
+      shell:
+        command: echo
+        arguments: ['${ .name }']
+- wrap:
+    do:
+    - fail:
+        run:
+          shell:
+            command: 'false'
+",
+        )
+        .unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let faults = Arc::new(Mutex::new(0));
+        let counted = faults.clone();
+        let ctx = WorkflowContext::default()
+            .with_observer(Arc::new(move |event: &WorkflowEvent| recorded.lock().unwrap().push(event.clone())))
+            .with_observer(Arc::new(move |event: &WorkflowEvent| {
+                if matches!(event, WorkflowEvent::TaskFaulted { .. } | WorkflowEvent::WorkflowFaulted { .. }) {
+                    *counted.lock().unwrap() += 1;
+                }
+            }));
+
+        workflow.run(&ctx, json!({ "name": "ada" })).await.unwrap_err();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 8, "{events:?}");
+        let input = json!({ "name": "ada" });
+        assert!(matches!(&events[0], WorkflowEvent::WorkflowStarted { input: started, .. } if started == &input));
+        assert!(matches!(
+            &events[2],
+            WorkflowEvent::TaskCompleted { task, position, output, .. }
+                if task == "greet" && position == "/do/0/greet" && output == &json!("ada")
+        ));
+        let faulted = |at: usize| match &events[at] {
+            WorkflowEvent::TaskFaulted { position, .. } => position.clone(),
+            other => panic!("expected a fault, got {other:?}"),
+        };
+        assert_eq!(faulted(5), "/do/1/wrap/do/0/fail");
+        assert_eq!(faulted(6), "/do/1/wrap");
+        assert!(matches!(&events[7], WorkflowEvent::WorkflowFaulted { error, .. } if error.contains("task 'fail'")));
+        assert!(events.iter().all(|event| event.instance() == events[0].instance()));
+        assert_eq!(*faults.lock().unwrap(), 3);
+    }
+}
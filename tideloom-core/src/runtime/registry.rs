@@ -0,0 +1,140 @@
+//! Custom `call` targets provided by the embedding application, such as `call: sendSlack`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+use serverless_workflow_core::models::schema::SchemaDefinition;
+
+use crate::runtime::schema::Schema;
+use crate::runtime::{SharedTask, StepResult, WorkflowContext};
+use crate::validation::BUILT_IN_CALLS;
+
+/// A task registered under a `call` name, with the schema its arguments must match, if declared.
+#[derive(Clone)]
+pub struct CustomTask {
+    pub task: SharedTask,
+    pub input_schema: Option<Arc<Schema>>,
+}
+
+impl CustomTask {
+    /// Runs the task with `arguments`, once they match the declared schema.
+    pub async fn execute(&self, ctx: &WorkflowContext, arguments: Value) -> StepResult<Value> {
+        if let Some(schema) = &self.input_schema {
+            schema.check(&arguments, "call arguments")?;
+        }
+        self.task.execute(ctx, arguments).await
+    }
+}
+
+/// The custom `call` targets of a context, registered with
+/// [`WorkflowContext::with_task_registry`](crate::runtime::WorkflowContext::with_task_registry).
+///
+/// A call naming a function of the workflow's `use.functions` runs that function; any other call that is not
+/// built into the DSL runs the task registered under its name, with the call's `with` arguments as input.
+/// Clones share the registered tasks.
+#[derive(Clone, Default)]
+pub struct TaskRegistry {
+    tasks: HashMap<String, CustomTask>,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `task` as `call: name`. Built-in call types cannot be replaced.
+    pub fn register(self, name: &str, task: SharedTask) -> StepResult<Self> {
+        self.insert(name, task, None)
+    }
+
+    /// Registers `task` as `call: name`, rejecting arguments that do not match `schema`, both when
+    /// [validating](crate::validation::validate_with) a workflow and when the task runs.
+    pub fn register_with_schema(self, name: &str, task: SharedTask, schema: &SchemaDefinition) -> StepResult<Self> {
+        let schema = Schema::compile(schema).map_err(|e| format!("task '{name}': {e}"))?;
+        self.insert(name, task, Some(Arc::new(schema)))
+    }
+
+    fn insert(mut self, name: &str, task: SharedTask, input_schema: Option<Arc<Schema>>) -> StepResult<Self> {
+        if BUILT_IN_CALLS.iter().any(|built_in| name.eq_ignore_ascii_case(built_in)) {
+            return Err(format!("'{name}' is a built-in call type").into());
+        }
+        self.tasks.insert(name.to_string(), CustomTask { task, input_schema });
+        Ok(self)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomTask> {
+        self.tasks.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tasks.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tasks.keys().map(String::as_str)
+    }
+}
+
+impl std::fmt::Debug for TaskRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::runtime::Task;
+
+    #[derive(Debug)]
+    struct Shout;
+
+    #[async_trait::async_trait]
+    impl Task for Shout {
+        type Input = Value;
+        type Output = Value;
+
+        async fn execute(&self, _ctx: &WorkflowContext, input: Value) -> StepResult<Value> {
+            Ok(json!(input["text"].as_str().unwrap_or_default().to_uppercase()))
+        }
+    }
+
+    fn schema() -> SchemaDefinition {
+        serde_json::from_value(json!({
+            "document": { "type": "object", "required": ["text"], "properties": { "text": { "type": "string" } } }
+        }))
+        .unwrap()
+    }
+
+    fn workflow(with: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            "document:\n  dsl: '1.0.0'\n  namespace: test\n  name: shout\n  version: '1.0.0'\n\
+             do:\n- shout:\n    call: shout\n    with: {with}\n"
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn calls_run_registered_tasks_with_checked_arguments() {
+        let registry = TaskRegistry::new().register_with_schema("shout", Arc::new(Shout), &schema()).unwrap();
+        let ctx = WorkflowContext::default().with_task_registry(registry.clone());
+
+        let output = workflow("{ text: '${ .greeting }' }").run(&ctx, json!({ "greeting": "hi" })).await.unwrap();
+        assert_eq!(output, json!("HI"));
+        let err = workflow("{ text: '${ .missing }' }").run(&ctx, json!({})).await.unwrap_err();
+        assert!(err.contains("call arguments does not match its schema"), "{err}");
+
+        assert!(workflow("{ text: hi }").validate_with(&registry).is_empty());
+        let diagnostics = workflow("{ text: 42 }").validate_with(&registry);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].position, "/do/0/shout/with");
+        assert_eq!(workflow("{ text: hi }").validate().len(), 1);
+        assert!(workflow("{ text: hi }").check_support(&ctx).is_empty());
+
+        assert!(TaskRegistry::new().register("HTTP", Arc::new(Shout)).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Retry policies (`try.catch.retry`) and the delays they compute between attempts.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serverless_workflow_core::models::duration::Duration as DurationDefinition;
+use serverless_workflow_core::models::retry::RetryPolicyDefinition;
+
+use crate::runtime::StepResult;
+
+/// What a failed call tells the retry loops around it, beyond its error message; carried by its
+/// [`StepError`](crate::runtime::StepError).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryHint {
+    /// Retrying cannot fix the failure, such as a rejected HTTP request; it is not retried, whatever the policy.
+    NotRetryable,
+    /// Retry no earlier than this, e.g. as asked by an HTTP `Retry-After` header.
+    After(Duration),
+}
+
+/// How the delay grows between consecutive retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    Constant,
+    /// Adds `increment` to the delay for every further retry.
+    Linear { increment: Duration },
+    /// Doubles the delay for every further retry.
+    Exponential,
+}
+
+/// A resolved retry policy.
+///
+/// `max_retries` counts retries, not the initial attempt. Without any limit a step is retried until it
+/// succeeds, as the DSL specifies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub delay: Duration,
+    pub backoff: Backoff,
+    /// Random extra delay, between the two bounds, added to every computed delay.
+    pub jitter: Option<(Duration, Duration)>,
+    pub max_retries: Option<u32>,
+    /// Total time after which no further retry is attempted.
+    pub max_duration: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_secs(1),
+            backoff: Backoff::Constant,
+            jitter: None,
+            max_retries: None,
+            max_duration: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn try_from_definition(def: &RetryPolicyDefinition) -> StepResult<Self> {
+        if def.when.is_some() || def.except_when.is_some() {
+            return Err("retry `when`/`exceptWhen` conditions are not supported".into());
+        }
+        let delay = def.delay.as_ref().map(to_std).unwrap_or(Duration::ZERO);
+        let backoff = match &def.backoff {
+            None => Backoff::Constant,
+            Some(b) => match (&b.constant, &b.linear, &b.exponential) {
+                (_, None, None) => Backoff::Constant,
+                (None, Some(linear), None) => Backoff::Linear {
+                    increment: linear.increment.as_ref().map(to_std).unwrap_or(delay),
+                },
+                (None, None, Some(_)) => Backoff::Exponential,
+                _ => return Err("retry backoff must define a single strategy".into()),
+            },
+        };
+        let jitter = match &def.jitter {
+            Some(jitter) if to_std(&jitter.from) > to_std(&jitter.to) => {
+                return Err("retry jitter `from` must not exceed `to`".into());
+            }
+            Some(jitter) => Some((to_std(&jitter.from), to_std(&jitter.to))),
+            None => None,
+        };
+        let limit = def.limit.as_ref();
+        Ok(Self {
+            delay,
+            backoff,
+            jitter,
+            max_retries: limit
+                .and_then(|l| l.attempt.as_ref())
+                .and_then(|a| a.count)
+                .map(u32::from),
+            max_duration: limit.and_then(|l| l.duration.as_ref()).map(to_std),
+        })
+    }
+
+    /// Delay before retry number `retry` (1 for the first retry), without jitter.
+    pub fn backoff_delay(&self, retry: u32) -> Duration {
+        let step = retry.saturating_sub(1);
+        match self.backoff {
+            Backoff::Constant => self.delay,
+            Backoff::Linear { increment } => self.delay.saturating_add(increment.saturating_mul(step)),
+            Backoff::Exponential => self.delay.saturating_mul(2u32.saturating_pow(step)),
+        }
+    }
+
+    /// Delay before retry number `retry`, including a random jitter when configured.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self.backoff_delay(retry);
+        match self.jitter {
+            Some((from, to)) if to > from => delay + rand::random_range(from..=to),
+            Some((from, _)) => delay + from,
+            None => delay,
+        }
+    }
+
+    /// Whether another retry is allowed after `retries` retries and `elapsed` time since the first attempt.
+    pub fn allows(&self, retries: u32, elapsed: Duration) -> bool {
+        self.max_retries.is_none_or(|max| retries < max) && self.max_duration.is_none_or(|max| elapsed < max)
+    }
+}
+
+/// Retry policies the runtime applies to effectful tasks that no enclosing `try` retries, keyed by task
+/// kind (`http`, `run`, ...; see [`LimitedNode`](crate::nodes::limited::LimitedNode)) with a fallback for
+/// every other kind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetryDefaults {
+    pub fallback: Option<RetryPolicy>,
+    pub by_kind: HashMap<String, RetryPolicy>,
+}
+
+impl RetryDefaults {
+    /// Applies `policy` to every effectful task.
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            fallback: Some(policy),
+            by_kind: HashMap::new(),
+        }
+    }
+
+    /// Applies `policy` to tasks of `kind` instead of the fallback.
+    pub fn with_kind(mut self, kind: &str, policy: RetryPolicy) -> Self {
+        self.by_kind.insert(kind.to_ascii_lowercase(), policy);
+        self
+    }
+
+    pub fn policy(&self, kind: &str) -> Option<&RetryPolicy> {
+        self.by_kind.get(kind).or(self.fallback.as_ref())
+    }
+}
+
+fn to_std(duration: &DurationDefinition) -> Duration {
+    Duration::from_millis(duration.total_milliseconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(yaml: &str) -> StepResult<RetryPolicy> {
+        RetryPolicy::try_from_definition(&serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn backoff_strategies() {
+        let exponential = policy("delay: { seconds: 1 }\nbackoff: { exponential: {} }").unwrap();
+        let delays: Vec<_> = (1..=4).map(|r| exponential.backoff_delay(r).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8]);
+
+        let linear = policy("delay: { seconds: 2 }\nbackoff: { linear: { increment: { seconds: 3 } } }").unwrap();
+        let delays: Vec<_> = (1..=3).map(|r| linear.backoff_delay(r).as_secs()).collect();
+        assert_eq!(delays, [2, 5, 8]);
+
+        let constant = policy("delay: { milliseconds: 500 }").unwrap();
+        assert_eq!(constant.backoff_delay(7), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_and_limits() {
+        let policy = policy(
+            "delay: { seconds: 1 }\njitter: { from: { milliseconds: 100 }, to: { milliseconds: 200 } }\nlimit:\n  attempt: { count: 2 }\n  duration: { minutes: 1 }",
+        )
+        .unwrap();
+        for _ in 0..20 {
+            let delay = policy.delay(1);
+            assert!((Duration::from_millis(1100)..=Duration::from_millis(1200)).contains(&delay), "{delay:?}");
+        }
+        assert!(policy.allows(1, Duration::from_secs(1)));
+        assert!(!policy.allows(2, Duration::from_secs(1)));
+        assert!(!policy.allows(0, Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,121 @@
+//! JSON Schema validation of workflow and task data (`input.schema` / `output.schema`).
+
+use std::fmt;
+
+use serde_json::Value;
+use serverless_workflow_core::models::schema::SchemaDefinition;
+
+use crate::runtime::StepResult;
+
+/// One violation of a schema by a JSON instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value, empty for the root.
+    pub instance_path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.instance_path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.instance_path, self.message)
+        }
+    }
+}
+
+/// A compiled `json` schema.
+///
+/// Only inline `document` schemas are supported; external `resource` schemas are rejected at compile time.
+pub struct Schema {
+    validator: jsonschema::Validator,
+}
+
+impl Schema {
+    pub fn compile(definition: &SchemaDefinition) -> StepResult<Self> {
+        let format = definition.format.split(':').next().unwrap_or_default();
+        if !format.eq_ignore_ascii_case("json") {
+            return Err(format!("unsupported schema format '{}'", definition.format).into());
+        }
+        let document = definition
+            .document
+            .as_ref()
+            .ok_or_else(|| "only inline schema documents are supported".to_string())?;
+        let validator = jsonschema::validator_for(document).map_err(|e| format!("invalid schema: {e}"))?;
+        Ok(Self { validator })
+    }
+
+    /// Returns every violation of the schema by `instance`.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<SchemaError>> {
+        let errors: Vec<SchemaError> = self
+            .validator
+            .iter_errors(instance)
+            .map(|e| SchemaError {
+                instance_path: e.instance_path().to_string(),
+                message: e.to_string(),
+            })
+            .collect();
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Validates `instance` and flattens the violations into a step error describing `what` was validated.
+    pub fn check(&self, instance: &Value, what: &str) -> StepResult<()> {
+        self.validate(instance).map_err(|errors| {
+            let details: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            format!("{what} does not match its schema: {}", details.join("; ")).into()
+        })
+    }
+}
+
+impl fmt::Debug for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Schema").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema(document: Value) -> StepResult<Schema> {
+        Schema::compile(&SchemaDefinition {
+            format: "json".to_string(),
+            resource: None,
+            document: Some(document),
+        })
+    }
+
+    #[test]
+    fn validate_reports_instance_paths() {
+        let schema = schema(json!({
+            "type": "object",
+            "required": ["order"],
+            "properties": { "order": { "type": "object", "properties": { "qty": { "type": "integer" } } } }
+        }))
+        .unwrap();
+
+        assert!(schema.validate(&json!({ "order": { "qty": 2 } })).is_ok());
+        let errors = schema.validate(&json!({ "order": { "qty": "two" } })).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/order/qty");
+
+        let err = schema.check(&json!({}), "task input").unwrap_err();
+        assert!(err.starts_with("task input does not match its schema: "), "{err}");
+        assert!(err.contains("order"), "{err}");
+    }
+
+    #[test]
+    fn compile_rejects_external_and_unknown_formats() {
+        let err = Schema::compile(&SchemaDefinition {
+            format: "avro".to_string(),
+            resource: None,
+            document: Some(json!({})),
+        })
+        .unwrap_err();
+        assert!(err.contains("avro"), "{err}");
+        assert!(schema(json!({ "type": 5 })).is_err());
+    }
+}
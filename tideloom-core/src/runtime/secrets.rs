@@ -0,0 +1,216 @@
+//! Secrets exposed to expressions as `$secrets.NAME`, limited to those a workflow declares in `use.secrets`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+use crate::runtime::StepResult;
+
+/// Replaces secret values wherever they would leave the engine: errors, traces, observer and alert events, and
+/// emitted events.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Looks up secrets by name; registered on the context with
+/// [`WorkflowContext::with_secrets`](crate::runtime::WorkflowContext::with_secrets).
+pub trait SecretsProvider: Send + Sync {
+    /// Returns the secret named `name`, or `None` when the provider does not know it.
+    fn secret(&self, name: &str) -> StepResult<Option<String>>;
+}
+
+/// Reads secrets from environment variables, optionally prefixed: `db` is read from `{prefix}db`.
+#[derive(Debug, Clone, Default)]
+pub struct EnvSecrets {
+    prefix: String,
+}
+
+impl EnvSecrets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self { prefix: prefix.into() }
+    }
+}
+
+impl SecretsProvider for EnvSecrets {
+    fn secret(&self, name: &str) -> StepResult<Option<String>> {
+        match std::env::var(format!("{}{name}", self.prefix)) {
+            Ok(value) => Ok(Some(value)),
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(e) => Err(format!("secret '{name}': {e}").into()),
+        }
+    }
+}
+
+/// Reads each secret from the file of the same name in a directory, as mounted by Kubernetes or Docker.
+#[derive(Debug, Clone)]
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl SecretsProvider for FileSecrets {
+    fn secret(&self, name: &str) -> StepResult<Option<String>> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(format!("invalid secret name '{name}'").into());
+        }
+        match std::fs::read_to_string(self.dir.join(name)) {
+            Ok(value) => Ok(Some(value.trim_end_matches(['\r', '\n']).to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("secret '{name}': {e}").into()),
+        }
+    }
+}
+
+/// Secrets held in memory, for tests and embedding hosts.
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySecrets {
+    secrets: HashMap<String, String>,
+}
+
+impl InMemorySecrets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_secret(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.secrets.insert(name.into(), value.into());
+        self
+    }
+}
+
+impl SecretsProvider for InMemorySecrets {
+    fn secret(&self, name: &str) -> StepResult<Option<String>> {
+        Ok(self.secrets.get(name).cloned())
+    }
+}
+
+/// Resolves the secrets named in `use.secrets` into the object bound to `$secrets`.
+pub fn load(provider: Option<&dyn SecretsProvider>, names: &[String]) -> StepResult<Map<String, Value>> {
+    if names.is_empty() {
+        return Ok(Map::new());
+    }
+    let provider = provider.ok_or_else(|| "workflow uses secrets but no secrets provider is configured".to_string())?;
+    names
+        .iter()
+        .map(|name| match provider.secret(name)? {
+            Some(value) => Ok((name.clone(), Value::String(value))),
+            None => Err(format!("secret '{name}' not found").into()),
+        })
+        .collect()
+}
+
+/// Replaces every occurrence of a secret value in `text` with [`REDACTED`].
+pub fn redact_str(text: &str, secrets: &Map<String, Value>) -> String {
+    let mut text = text.to_string();
+    for secret in secrets.values().filter_map(Value::as_str).filter(|s| !s.is_empty()) {
+        text = text.replace(secret, REDACTED);
+    }
+    text
+}
+
+/// Redacts secret values in every string, and object key, nested in `value`.
+pub fn redact(value: &Value, secrets: &Map<String, Value>) -> Value {
+    if secrets.is_empty() {
+        return value.clone();
+    }
+    match value {
+        Value::String(s) => Value::String(redact_str(s, secrets)),
+        Value::Array(items) => items.iter().map(|item| redact(item, secrets)).collect(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (redact_str(k, secrets), redact(v, secrets)))
+            .collect(),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+    use crate::Workflow;
+    use crate::messaging::{EventSource, InMemoryEventBus};
+    use crate::runtime::WorkflowContext;
+
+    #[test]
+    fn providers_look_up_secrets() {
+        let dir = std::env::temp_dir().join(format!("tideloom-secrets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("db"), "hunter2\n").unwrap();
+        let files = FileSecrets::new(&dir);
+        assert_eq!(files.secret("db").unwrap().as_deref(), Some("hunter2"));
+        assert_eq!(files.secret("missing").unwrap(), None);
+        assert!(files.secret("../db").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(EnvSecrets::with_prefix("PA").secret("TH").unwrap(), std::env::var("PATH").ok());
+        assert_eq!(InMemorySecrets::new().with_secret("a", "1").secret("a").unwrap().as_deref(), Some("1"));
+
+        let err = load(None, &["db".to_string()]).unwrap_err();
+        assert_eq!(err, "workflow uses secrets but no secrets provider is configured");
+        let err = load(Some(&InMemorySecrets::new()), &["db".to_string()]).unwrap_err();
+        assert_eq!(err, "secret 'db' not found");
+    }
+
+    #[tokio::test]
+    async fn declared_secrets_are_readable_and_redacted() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: secretive
+  version: '1.0.0'
+use:
+  secrets: [apiKey]
+output:
+  as:
+    key: ${ .key }
+    other: ${ .other }
+    exported: ${ $context.key }
+do:
+- announce:
+    emit:
+      event:
+        with:
+          source: test
+          type: test.key
+          data:
+            key: ${ $secrets.apiKey }
+    output:
+      as:
+        key: ${ $secrets.apiKey }
+        other: ${ $secrets.dbPassword }
+    export:
+      as:
+        key: ${ $secrets.apiKey }
+"#,
+        )
+        .unwrap();
+        let bus = Arc::new(InMemoryEventBus::default());
+        let mut events = bus.subscribe().await.unwrap();
+        let secrets = InMemorySecrets::new()
+            .with_secret("apiKey", "k3y")
+            .with_secret("dbPassword", "hunter2");
+        let ctx = WorkflowContext::default()
+            .with_event_sink(bus.clone())
+            .with_secrets(Arc::new(secrets));
+
+        let output = workflow.run(&ctx, json!({})).await.unwrap();
+
+        assert_eq!(output, json!({ "key": "k3y", "other": null, "exported": "k3y" }));
+        assert_eq!(events.next().await.unwrap().data, Some(json!({ "key": REDACTED })));
+    }
+}
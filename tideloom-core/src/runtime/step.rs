@@ -1,13 +1,520 @@
-pub type StepResult<T> = std::result::Result<T, String>;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{Map, Value, json};
+use serverless_workflow_core::models::workflow::WorkflowDefinition;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+use crate::WorkflowSummary;
+use crate::messaging::{EventSink, EventSource, MessageBroker};
+use crate::nodes::switch::SwitchOutcome;
+use crate::runtime::alerts;
+use crate::runtime::auth::{Authentication, TokenCache};
+use crate::runtime::blocking::BlockingPool;
+use crate::runtime::flags::FlagProvider;
+use crate::runtime::http_client::{HttpBodyLimits, HttpClients};
+use crate::runtime::interceptor::HttpInterceptor;
+use crate::runtime::limits::ConcurrencyLimits;
+use crate::runtime::memo::MemoCache;
+use crate::runtime::metering::{InstanceInfo, Meter};
+use crate::runtime::metrics::{Labels, MetricsRecorder, TASK_RETRIES};
+use crate::runtime::observer::{WorkflowEvent, WorkflowObserver};
+use crate::runtime::registry::TaskRegistry;
+use crate::runtime::retry::{RetryDefaults, RetryHint, RetryPolicy};
+use crate::runtime::secrets::{self, SecretsProvider};
+
+pub type StepResult<T> = std::result::Result<T, StepError>;
+
+/// Error of a step: its message, with what the failure tells the retry loops and `try` tasks around it, if
+/// anything.
+///
+/// An error built from a message carries no [`RetryHint`] and no [`ErrorKind`]. Tasks that wrap the error of a
+/// task they run keep both with [`map_message`](Self::map_message); a task that recovers from an error drops
+/// them with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepError {
+    message: String,
+    hint: Option<RetryHint>,
+    kind: Option<ErrorKind>,
+}
+
+/// Kind of failure of a [`StepError`], as `catch.errors.with.type` of a `try` task selects it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A task or workflow did not complete within its `timeout`.
+    Timeout,
+}
+
+impl ErrorKind {
+    /// The error type of the kind, as defined by the DSL.
+    pub fn type_uri(self) -> &'static str {
+        match self {
+            Self::Timeout => "https://serverlessworkflow.io/spec/1.0.0/errors/timeout",
+        }
+    }
+}
+
+impl StepError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            hint: None,
+            kind: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: RetryHint) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    pub fn hint(&self) -> Option<RetryHint> {
+        self.hint
+    }
+
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn kind(&self) -> Option<ErrorKind> {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Rewrites the message, e.g. to say which task failed, keeping the hint and kind.
+    pub fn map_message(self, f: impl FnOnce(String) -> String) -> Self {
+        Self {
+            message: f(self.message),
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Display for StepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StepError {}
+
+impl std::ops::Deref for StepError {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<String> for StepError {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for StepError {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<StepError> for String {
+    fn from(error: StepError) -> Self {
+        error.message
+    }
+}
+
+impl PartialEq<str> for StepError {
+    fn eq(&self, other: &str) -> bool {
+        self.message == other
+    }
+}
+
+impl PartialEq<&str> for StepError {
+    fn eq(&self, other: &&str) -> bool {
+        self.message == *other
+    }
+}
+
+impl PartialEq<String> for StepError {
+    fn eq(&self, other: &String) -> bool {
+        &self.message == other
+    }
+}
+
+/// A compiled task shared between the instances that run it.
+pub type SharedTask = Arc<dyn Task<Input = Value, Output = Value>>;
 
 /// Shared runtime context passed to every step execution.
-#[derive(Debug, Default, Clone)]
+#[derive(Default, Clone)]
 pub struct WorkflowContext {
     pub http_client: reqwest::Client,
+    /// Per-host clients of HTTP and OpenAPI calls, if the host configured them; see [`Self::call_clients`].
+    pub http_clients: Option<HttpClients>,
+    /// Size limits of the response bodies read by HTTP and OpenAPI calls.
+    pub http_body_limits: HttpBodyLimits,
+    /// Hooks run around every request of HTTP and OpenAPI calls, in registration order.
+    pub http_interceptors: Vec<Arc<dyn HttpInterceptor>>,
+    /// Source consumed by `listen` tasks, if the host configured one.
+    pub event_source: Option<Arc<dyn EventSource>>,
+    /// Sink published to by `emit` tasks, if the host configured one.
+    pub event_sink: Option<Arc<dyn EventSink>>,
+    /// Receives operational events such as exhausted retries, if the host configured one; see
+    /// [`alerts`](crate::runtime::alerts).
+    pub alerts: Option<Arc<dyn EventSink>>,
+    /// Backends of `call: asyncapi` tasks, keyed by lowercase server protocol such as `mqtt`.
+    pub brokers: HashMap<String, Arc<dyn MessageBroker>>,
+    /// Environment variables visible to processes started by `run` tasks.
+    pub process_env: ProcessEnvironment,
+    /// Workflows callable from `run: workflow` tasks, keyed by [`workflow_key`].
+    pub workflows: HashMap<String, Arc<WorkflowDefinition>>,
+    /// The instance's `$context`, written by `export.as` and readable from expressions.
+    pub context: ContextData,
+    /// Further variables in scope for expressions, such as the item bound by a `for` loop, keyed with their `$`.
+    pub scope: Map<String, Value>,
+    /// Host-provided entries of `$runtime`, such as the environment, beside its `name` and `version`.
+    pub runtime: Map<String, Value>,
+    /// Runs synchronous or CPU-heavy work of tasks off the async workers; shared with every clone of the context.
+    pub blocking: BlockingPool,
+    /// Concurrency caps per task kind, shared with every clone of the context.
+    pub limits: ConcurrencyLimits,
+    /// Receives the usage of every effectful task, if the host configured one.
+    pub meter: Option<Arc<dyn Meter>>,
+    /// Receives counters and histograms about workflows and tasks, if the host configured one.
+    pub metrics: Option<Arc<dyn MetricsRecorder>>,
+    /// Receive the lifecycle events of workflows and tasks, in registration order.
+    pub observers: Vec<Arc<dyn WorkflowObserver>>,
+    /// Tenant the instance runs for, attached to metered usage.
+    pub tenant: Option<String>,
+    /// The running instance; set when a workflow starts.
+    pub instance: InstanceInfo,
+    /// Cancels every task running with this context, or a clone of it, once triggered.
+    pub cancellation: CancellationToken,
+    /// Set by a task's `end` directive: every block enclosing the task stops once it completes. Each workflow
+    /// instance has its own.
+    pub ended: Arc<AtomicBool>,
+    /// Receives the directive of the `switch` task running with this context, for the `do` block running it.
+    pub switched: Option<SwitchOutcome>,
+    /// Authentication policies of the running workflow's `use.authentications`, by name.
+    pub authentications: HashMap<String, Authentication>,
+    /// Custom functions of the running workflow's `use.functions`, by name.
+    pub functions: HashMap<String, SharedTask>,
+    /// Custom `call` targets registered by the host, run by calls that name no function of `use.functions`.
+    pub tasks: TaskRegistry,
+    /// OAuth2 access tokens, shared with every clone of the context.
+    pub tokens: TokenCache,
+    /// Retry policies of effectful tasks that no enclosing `try` retries.
+    pub default_retry: RetryDefaults,
+    /// Results of memoized call tasks, shared with every clone of the context.
+    pub memo: MemoCache,
+    /// Evaluates the `$flags` read by task `if` conditions, if the host configured one.
+    pub flags: Option<Arc<dyn FlagProvider>>,
+    /// Source of the secrets declared in `use.secrets`, if the host configured one.
+    pub secrets: Option<Arc<dyn SecretsProvider>>,
 }
 impl WorkflowContext {
     pub fn new(http_client: reqwest::Client) -> Self {
-        Self { http_client }
+        Self {
+            http_client,
+            ..Default::default()
+        }
+    }
+
+    /// Sends HTTP and OpenAPI calls through `clients`; their default client also serves every other request.
+    pub fn with_http_clients(mut self, clients: HttpClients) -> Self {
+        self.http_client = clients.client_for_default().clone();
+        self.http_clients = Some(clients);
+        self
+    }
+
+    /// The clients of HTTP and OpenAPI calls: the configured ones, or else clients built from the default
+    /// [`HttpClientConfig`](crate::runtime::http_client::HttpClientConfig).
+    pub fn call_clients(&self) -> &HttpClients {
+        match &self.http_clients {
+            Some(clients) => clients,
+            None => HttpClients::shared(),
+        }
+    }
+
+    pub fn with_http_body_limits(mut self, limits: HttpBodyLimits) -> Self {
+        self.http_body_limits = limits;
+        self
+    }
+
+    pub fn with_task_registry(mut self, tasks: TaskRegistry) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    pub fn with_http_interceptor(mut self, interceptor: Arc<dyn HttpInterceptor>) -> Self {
+        self.http_interceptors.push(interceptor);
+        self
+    }
+
+    pub fn with_event_source(mut self, source: Arc<dyn EventSource>) -> Self {
+        self.event_source = Some(source);
+        self
+    }
+
+    pub fn with_event_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.event_sink = Some(sink);
+        self
+    }
+
+    pub fn with_alerts(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.alerts = Some(sink);
+        self
+    }
+
+    /// Routes `call: asyncapi` operations on servers of `protocol` to `broker`.
+    pub fn with_broker(mut self, protocol: &str, broker: Arc<dyn MessageBroker>) -> Self {
+        self.brokers.insert(protocol.to_lowercase(), broker);
+        self
+    }
+
+    /// Seeds the instance's `$context`, e.g. with tenant configuration or feature flags.
+    pub fn with_context(mut self, context: Value) -> Self {
+        self.context = ContextData::new(context);
+        self
+    }
+
+    /// Adds `key` to `$runtime`, e.g. the environment workflows branch on; a `name` or `version` replaces the
+    /// runtime's own.
+    pub fn with_runtime_value(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.runtime.insert(key.into(), value);
+        self
+    }
+
+    /// Runtime variables visible to expressions: `$context`, `$runtime` and the variables in
+    /// [`scope`](Self::scope).
+    pub fn vars(&self) -> Map<String, Value> {
+        let mut vars = self.scope.clone();
+        vars.insert("$context".to_string(), self.context.get());
+        let mut runtime = Map::new();
+        runtime.insert("name".to_string(), json!("tideloom"));
+        runtime.insert("version".to_string(), json!(env!("CARGO_PKG_VERSION")));
+        runtime.extend(self.runtime.clone());
+        vars.insert("$runtime".to_string(), Value::Object(runtime));
+        vars
+    }
+
+    /// Masks the values of the running workflow's `$secrets` nested in `value`.
+    pub fn redact(&self, value: &Value) -> Value {
+        match self.scope.get("$secrets") {
+            Some(Value::Object(secrets)) => secrets::redact(value, secrets),
+            _ => value.clone(),
+        }
+    }
+
+    /// Like [`redact`](Self::redact), for error messages.
+    pub fn redact_error(&self, error: StepError) -> StepError {
+        match self.scope.get("$secrets") {
+            Some(Value::Object(secrets)) => error.map_message(|message| secrets::redact_str(&message, secrets)),
+            _ => error,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: ConcurrencyLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Retries failed effectful tasks per `retry` unless an enclosing `try` declares its own policy.
+    pub fn with_default_retry(mut self, retry: RetryDefaults) -> Self {
+        self.default_retry = retry;
+        self
+    }
+
+    pub fn with_blocking_pool(mut self, pool: BlockingPool) -> Self {
+        self.blocking = pool;
+        self
+    }
+
+    pub fn with_meter(mut self, meter: Arc<dyn Meter>) -> Self {
+        self.meter = Some(meter);
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsRecorder>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Adds an observer of workflow and task events; see [`WorkflowObserver`].
+    pub fn with_observer(mut self, observer: Arc<dyn WorkflowObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Sends the event built by `event` to every observer. The event is only built when there are observers.
+    pub fn observe(&self, event: impl FnOnce() -> WorkflowEvent) {
+        if self.observers.is_empty() {
+            return;
+        }
+        let event = event();
+        for observer in &self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    /// Increments counter `name` on the context's metrics recorder, if any.
+    pub fn count(&self, name: &'static str, labels: &Labels) {
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_counter(name, labels);
+        }
+    }
+
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Lets the host cancel instances running with this context through `token`.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    pub fn with_flags(mut self, flags: Arc<dyn FlagProvider>) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretsProvider>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    pub fn with_process_env(mut self, process_env: ProcessEnvironment) -> Self {
+        self.process_env = process_env;
+        self
+    }
+
+    /// Registers a workflow so that `run: workflow` tasks can start it.
+    pub fn with_workflow(mut self, definition: WorkflowDefinition) -> Self {
+        let document = &definition.document;
+        let key = workflow_key(&document.namespace, &document.name, &document.version);
+        self.workflows.insert(key, Arc::new(definition));
+        self
+    }
+
+    /// Describes the workflows registered with [`with_workflow`](Self::with_workflow), ordered by key.
+    pub fn registered_workflows(&self) -> Vec<WorkflowSummary> {
+        let mut workflows: Vec<_> = self.workflows.values().map(|w| WorkflowSummary::from_definition(w)).collect();
+        workflows.sort_by(|a, b| a.key.cmp(&b.key));
+        workflows
+    }
+}
+
+/// Shared, mutable `$context` of a workflow instance. Clones share the same data.
+#[derive(Debug, Clone)]
+pub struct ContextData(Arc<Mutex<Value>>);
+
+impl Default for ContextData {
+    fn default() -> Self {
+        Self::new(Value::Object(Default::default()))
+    }
+}
+
+impl ContextData {
+    pub fn new(value: Value) -> Self {
+        Self(Arc::new(Mutex::new(value)))
+    }
+
+    pub fn get(&self) -> Value {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set(&self, value: Value) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = value;
+    }
+}
+
+/// Registry key of a workflow: `namespace/name:version`.
+pub fn workflow_key(namespace: &str, name: &str, version: &str) -> String {
+    format!("{namespace}/{name}:{version}")
+}
+
+/// Controls which host environment variables leak into processes started by `run` tasks.
+///
+/// By default processes start with an empty environment apart from `PATH`; variables declared by the
+/// task itself are always passed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessEnvironment {
+    /// Pass the whole host environment through.
+    pub inherit: bool,
+    /// Host variables passed through when `inherit` is false.
+    pub allow: Vec<String>,
+}
+
+impl Default for ProcessEnvironment {
+    fn default() -> Self {
+        Self {
+            inherit: false,
+            allow: vec!["PATH".to_string()],
+        }
+    }
+}
+
+impl ProcessEnvironment {
+    /// Passes the whole host environment through.
+    pub fn inherit() -> Self {
+        Self {
+            inherit: true,
+            allow: Vec::new(),
+        }
+    }
+
+    /// Host variables to pass through, in addition to those declared by the task.
+    pub fn host_vars(&self) -> Vec<(String, String)> {
+        if self.inherit {
+            return std::env::vars().collect();
+        }
+        self.allow
+            .iter()
+            .filter_map(|name| Some((name.clone(), std::env::var(name).ok()?)))
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for WorkflowContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkflowContext")
+            .field("http_client", &self.http_client)
+            .field("http_clients", &self.http_clients)
+            .field("http_body_limits", &self.http_body_limits)
+            .field("http_interceptors", &self.http_interceptors.len())
+            .field("event_source", &self.event_source.is_some())
+            .field("event_sink", &self.event_sink.is_some())
+            .field("alerts", &self.alerts.is_some())
+            .field("brokers", &self.brokers.keys().collect::<Vec<_>>())
+            .field("process_env", &self.process_env)
+            .field("workflows", &self.workflows.keys().collect::<Vec<_>>())
+            .field("context", &self.context)
+            .field("scope", &self.scope)
+            .field("limits", &self.limits.snapshot())
+            .field("blocking", &self.blocking.size())
+            .field("meter", &self.meter.is_some())
+            .field("metrics", &self.metrics.is_some())
+            .field("observers", &self.observers.len())
+            .field("tenant", &self.tenant)
+            .field("instance", &self.instance)
+            .field("cancelled", &self.cancellation.is_cancelled())
+            .field("authentications", &self.authentications.keys().collect::<Vec<_>>())
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
+            .field("tasks", &self.tasks)
+            .field("default_retry", &self.default_retry)
+            .field("memo", &self.memo.len())
+            .field("flags", &self.flags.is_some())
+            .field("secrets", &self.secrets.is_some())
+            .finish()
     }
 }
 
@@ -19,6 +526,7 @@ pub enum StepStatus {
     Retrying,
     Succeeded,
     Failed,
+    Cancelled,
 }
 
 impl StepStatus {
@@ -32,6 +540,9 @@ impl StepStatus {
                 | (StepStatus::Running, StepStatus::Retrying)
                 | (StepStatus::Failed, StepStatus::Retrying)
                 | (StepStatus::Retrying, StepStatus::Running)
+                | (StepStatus::Pending, StepStatus::Cancelled)
+                | (StepStatus::Running, StepStatus::Cancelled)
+                | (StepStatus::Retrying, StepStatus::Cancelled)
         )
     }
 }
@@ -86,20 +597,41 @@ impl StepInstance {
             Err(format!(
                 "invalid transition for step '{}': {:?} -> {:?}",
                 self.name, self.status, next
-            ))
+            ).into())
         }
     }
 }
 
+/// Error of a step or workflow stopped through its cancellation token.
+///
+/// Only the message is built here: whether a run was cancelled is told by its token, or by a step ending in
+/// [`StepStatus::Cancelled`], never by the error text.
+pub fn cancelled_error(what: &str) -> StepError {
+    StepError::new(format!("{what} was cancelled"))
+}
+
 /// Runs a step by enforcing the lifecycle transitions around its execution.
+///
+/// A step cancelled before or while it runs ends in [`StepStatus::Cancelled`].
 pub async fn run_step<T: Task>(
     step: &mut StepInstance,
     step_type: &T,
     ctx: &WorkflowContext,
     input: T::Input,
 ) -> StepResult<T::Output> {
+    if ctx.cancellation.is_cancelled() {
+        step.transition(StepStatus::Cancelled)?;
+        return Err(cancelled_error(&format!("step '{}'", step.name())));
+    }
     step.transition(StepStatus::Running)?;
-    match step_type.execute(ctx, input).await {
+    let result = tokio::select! {
+        result = step_type.execute(ctx, input) => result,
+        _ = ctx.cancellation.cancelled() => {
+            step.transition(StepStatus::Cancelled)?;
+            return Err(cancelled_error(&format!("step '{}'", step.name())));
+        }
+    };
+    match result {
         Ok(output) => {
             step.transition(StepStatus::Succeeded)?;
             Ok(output)
@@ -110,3 +642,142 @@ pub async fn run_step<T: Task>(
         }
     }
 }
+
+/// Runs a step like [`run_step`], retrying failed attempts as allowed by `policy`.
+///
+/// Between attempts the step goes through [`StepStatus::Retrying`] and waits for the policy's delay, or for
+/// the delay the failed call asks for when longer; see [`RetryHint`]. Once the policy is exhausted, or the
+/// failure is not retryable, the last error is returned, with its hint for any retry loop around this one. A
+/// cancelled step is never retried.
+pub async fn run_step_with_retry<T: Task>(
+    step: &mut StepInstance,
+    step_type: &T,
+    ctx: &WorkflowContext,
+    input: T::Input,
+    policy: &RetryPolicy,
+) -> StepResult<T::Output>
+where
+    T::Input: Clone,
+{
+    let started = std::time::Instant::now();
+    loop {
+        let span = tracing::info_span!("attempt", task = %step.name(), attempt = step.attempts() + 1);
+        let err = match run_step(step, step_type, ctx, input.clone()).instrument(span).await {
+            Ok(output) => return Ok(output),
+            Err(err) => err,
+        };
+        if step.status() == StepStatus::Cancelled || err.hint() == Some(RetryHint::NotRetryable) {
+            return Err(err);
+        }
+        let retries = step.attempts() - 1;
+        if !policy.allows(retries, started.elapsed()) {
+            let data = json!({
+                "task": step.name(),
+                "attempts": step.attempts(),
+                "error": ctx.redact_error(err.clone()).message(),
+            });
+            alerts::publish(ctx, alerts::RETRY_EXHAUSTED, data).await;
+            return Err(err);
+        }
+        let requested = match err.hint() {
+            Some(RetryHint::After(after)) => after,
+            _ => Duration::ZERO,
+        };
+        step.transition(StepStatus::Retrying)?;
+        ctx.count(TASK_RETRIES, &[("step", step.name().to_string())]);
+        tokio::select! {
+            _ = tokio::time::sleep(policy.delay(retries + 1).max(requested)) => {}
+            _ = ctx.cancellation.cancelled() => {
+                step.transition(StepStatus::Cancelled)?;
+                return Err(cancelled_error(&format!("step '{}'", step.name())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    /// Fails with an error that reads like a cancellation until it is cancelled for real.
+    #[derive(Default)]
+    struct Stubborn(AtomicU32);
+
+    #[async_trait::async_trait]
+    impl Task for Stubborn {
+        type Input = ();
+        type Output = ();
+
+        async fn execute(&self, ctx: &WorkflowContext, _: ()) -> StepResult<()> {
+            if self.0.fetch_add(1, Ordering::SeqCst) == 2 {
+                ctx.cancellation.cancel();
+                std::future::pending::<()>().await;
+            }
+            Err("upstream job was cancelled".to_string().into())
+        }
+    }
+
+    /// Fails like a rejected call in a fork branch, with the hint only if `hinted`.
+    struct Rejected {
+        hinted: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Task for Rejected {
+        type Input = ();
+        type Output = ();
+
+        async fn execute(&self, _: &WorkflowContext, _: ()) -> StepResult<()> {
+            let error = StepError::new("status 422");
+            let error = if self.hinted { error.with_hint(RetryHint::NotRetryable) } else { error };
+            Err(error.map_message(|e| format!("fork branch 'charge' failed: {e}")))
+        }
+    }
+
+    #[test]
+    fn runtime_metadata_is_visible_to_expressions() {
+        let ctx = WorkflowContext::default().with_runtime_value("environment", json!("staging"));
+
+        let runtime = &ctx.vars()["$runtime"];
+
+        assert_eq!(runtime["name"], "tideloom");
+        assert_eq!(runtime["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(runtime["environment"], "staging");
+    }
+
+    #[tokio::test]
+    async fn hints_travel_with_their_error() {
+        async fn run(hinted: bool) -> (StepError, u32) {
+            let policy = serde_yaml::from_str("delay: { milliseconds: 1 }\nlimit: { attempt: { count: 2 } }").unwrap();
+            let policy = RetryPolicy::try_from_definition(&policy).unwrap();
+            let mut step = StepInstance::new("charge");
+            let ctx = WorkflowContext::default();
+            let err = run_step_with_retry(&mut step, &Rejected { hinted }, &ctx, (), &policy).await.unwrap_err();
+            (err, step.attempts())
+        }
+
+        // Spawned, as scheduled and non-awaited instances are.
+        let (err, attempts) = tokio::spawn(run(true)).await.unwrap();
+        assert_eq!(err, "fork branch 'charge' failed: status 422");
+        assert_eq!((err.hint(), attempts), (Some(RetryHint::NotRetryable), 1));
+
+        let (err, attempts) = run(false).await;
+        assert_eq!((err.hint(), attempts), (None, 3));
+    }
+
+    #[tokio::test]
+    async fn only_real_cancellations_stop_retries() {
+        let policy = serde_yaml::from_str("delay: { milliseconds: 1 }\nlimit: { attempt: { count: 5 } }").unwrap();
+        let policy = RetryPolicy::try_from_definition(&policy).unwrap();
+        let ctx = WorkflowContext::default().with_cancellation(CancellationToken::new());
+        let mut step = StepInstance::new("stubborn");
+
+        let err = run_step_with_retry(&mut step, &Stubborn::default(), &ctx, (), &policy).await.unwrap_err();
+
+        assert_eq!(err, "step 'stubborn' was cancelled");
+        assert_eq!(step.status(), StepStatus::Cancelled);
+        assert_eq!(step.attempts(), 3);
+    }
+}
@@ -0,0 +1,100 @@
+//! Task and workflow `timeout` blocks.
+
+use std::time::Duration;
+
+use serverless_workflow_core::models::duration::OneOfDurationOrIso8601Expression;
+use serverless_workflow_core::models::timeout::OneOfTimeoutDefinitionOrReference;
+
+use crate::runtime::{ErrorKind, StepError, StepResult};
+
+/// Resolves a `timeout` block. References to `use.timeouts` are not supported yet.
+pub fn timeout_after(timeout: &OneOfTimeoutDefinitionOrReference) -> StepResult<Duration> {
+    match timeout {
+        OneOfTimeoutDefinitionOrReference::Timeout(timeout) => duration_of(&timeout.after),
+        OneOfTimeoutDefinitionOrReference::Reference(name) => {
+            Err(format!("timeout references are not supported: '{name}'").into())
+        }
+    }
+}
+
+/// Resolves a DSL duration, given either as an object (`seconds: 30`) or as an ISO 8601 string.
+pub fn duration_of(duration: &OneOfDurationOrIso8601Expression) -> StepResult<Duration> {
+    match duration {
+        OneOfDurationOrIso8601Expression::Duration(duration) => {
+            Ok(Duration::from_millis(duration.total_milliseconds()))
+        }
+        OneOfDurationOrIso8601Expression::Iso8601Expression(expr) => parse_iso8601_duration(expr),
+    }
+}
+
+/// Error of a task or workflow that did not complete within `after`, of kind [`ErrorKind::Timeout`]; `what` names
+/// it, e.g. `task 'charge'`.
+pub fn timeout_error(what: &str, after: Duration) -> StepError {
+    StepError::new(format!("{what} timed out after {after:?}")).with_kind(ErrorKind::Timeout)
+}
+
+/// Parses an ISO 8601 duration such as `PT1H30M` or `P1DT0.5S`.
+///
+/// Years and months have no fixed length and are rejected.
+pub fn parse_iso8601_duration(expr: &str) -> StepResult<Duration> {
+    let invalid = || format!("invalid ISO 8601 duration '{expr}'");
+    let rest = expr.strip_prefix('P').ok_or_else(invalid)?;
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return Err(invalid().into()),
+        Some((date, time)) => (date, time),
+        None => (rest, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return Err(invalid().into());
+    }
+    let mut seconds = 0.0;
+    // Units of each part in their required order, with their length in seconds; zero for calendar units.
+    let date_units = [('Y', 0.0), ('M', 0.0), ('W', 604_800.0), ('D', 86_400.0)];
+    let time_units = [('H', 3_600.0), ('M', 60.0), ('S', 1.0)];
+    for (part, units) in [(date, &date_units[..]), (time, &time_units[..])] {
+        let mut next_unit = 0;
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+                continue;
+            }
+            let offset = units[next_unit..].iter().position(|(unit, _)| *unit == c).ok_or_else(invalid)?;
+            let (unit, factor) = units[next_unit + offset];
+            if factor == 0.0 {
+                return Err(format!("ISO 8601 duration '{expr}' uses {unit}, which has no fixed length").into());
+            }
+            let value: f64 = number.parse().map_err(|_| invalid())?;
+            seconds += value * factor;
+            number.clear();
+            next_unit += offset + 1;
+        }
+        if !number.is_empty() {
+            return Err(invalid().into());
+        }
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_iso8601_durations() {
+        assert_eq!(parse_iso8601_duration("PT1H30M").unwrap(), Duration::from_secs(5_400));
+        assert_eq!(parse_iso8601_duration("P1W1DT0.5S").unwrap(), Duration::from_millis(691_200_500));
+        for invalid in ["P", "PT", "1H", "PT1X", "PT1M1H", "P1DT"] {
+            assert!(parse_iso8601_duration(invalid).is_err(), "{invalid}");
+        }
+        assert!(parse_iso8601_duration("P1M").unwrap_err().contains("no fixed length"));
+    }
+
+    #[test]
+    fn only_engine_timeouts_are_of_the_timeout_kind() {
+        let error = timeout_error("task 'charge'", Duration::from_millis(5));
+        assert_eq!(error, "task 'charge' timed out after 5ms");
+        assert_eq!(error.kind(), Some(ErrorKind::Timeout));
+        assert_eq!(StepError::from("upstream timed out after 5s").kind(), None);
+    }
+}
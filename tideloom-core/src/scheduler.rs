@@ -0,0 +1,681 @@
+//! Starts workflow instances according to the definition's `schedule` block.
+//!
+//! `cron` expressions use the standard five fields (a leading seconds field is also accepted) and are
+//! evaluated in the scheduler's timezone, UTC by default. `every` starts an instance at a fixed interval,
+//! `after` restarts the workflow once the previous instance completed, and `on` starts an instance each
+//! time the event consumption strategy is satisfied.
+//!
+//! A `cron` or `every` start that comes later than the misfire tolerance, e.g. after the host was suspended,
+//! is skipped and reported as a [`SCHEDULE_MISFIRED`] event to the context's alert sink.
+//!
+//! With [`Scheduler::with_jitter`], each workflow's `cron` and `every` starts are delayed by an offset derived
+//! from the workflow's key, so definitions sharing an expression do not all start at the same instant.
+//!
+//! [`Scheduler::backfill`] replays the `cron` or `every` starts of a past period instead.
+//!
+//! [`Scheduler::pause`] stops a schedule from starting instances until [`Scheduler::resume`], without removing
+//! it; [`Scheduler::schedules`] lists the schedules with their next start.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::{Value, json};
+use serverless_workflow_core::models::task::{ListenTaskDefinition, ListenerDefinition};
+use serverless_workflow_core::models::workflow::WorkflowScheduleDefinition;
+use futures::StreamExt;
+use tokio::sync::{broadcast, watch};
+use tokio::task::{AbortHandle, JoinHandle};
+
+use crate::Workflow;
+use crate::nodes::listen::ListenNode;
+use crate::runtime::alerts::{self, SCHEDULE_MISFIRED};
+use crate::runtime::{ContextData, StepResult, Task, WorkflowContext, workflow_key};
+
+/// When scheduled instances of a workflow start.
+#[derive(Debug, Clone)]
+pub enum Trigger {
+    Cron(Box<cron::Schedule>),
+    Every(Duration),
+    After(Duration),
+    On(ListenNode),
+}
+
+impl Trigger {
+    pub fn try_from_definition(schedule: &WorkflowScheduleDefinition) -> StepResult<Self> {
+        let to_std = |d: &serverless_workflow_core::models::duration::Duration| {
+            Duration::from_millis(d.total_milliseconds())
+        };
+        match (&schedule.cron, &schedule.every, &schedule.after, &schedule.on) {
+            (Some(cron), None, None, None) => Ok(Self::Cron(Box::new(parse_cron(cron)?))),
+            (None, Some(every), None, None) if every.total_milliseconds() > 0 => Ok(Self::Every(to_std(every))),
+            (None, None, Some(after), None) => Ok(Self::After(to_std(after))),
+            (None, None, None, Some(on)) => Ok(Self::On(ListenNode::try_from_listen(&ListenTaskDefinition::new(
+                ListenerDefinition::new(on.clone()),
+            ))?)),
+            (None, Some(_), None, None) => Err("schedule `every` must be a positive duration".into()),
+            (None, None, None, None) => Err("schedule requires one of `cron`, `every`, `after` or `on`".into()),
+            _ => Err("schedule must define exactly one of `cron`, `every`, `after` or `on`".into()),
+        }
+    }
+
+    /// Next time a time-based trigger fires strictly after `after`; `None` for `after` and `on` triggers.
+    pub fn next_fire<Z: TimeZone>(&self, after: &DateTime<Z>) -> Option<DateTime<Z>> {
+        match self {
+            Self::Cron(schedule) => schedule.after(after).next(),
+            Self::Every(every) => Some(after.clone() + chrono::Duration::from_std(*every).ok()?),
+            Self::After(_) | Self::On(_) => None,
+        }
+    }
+}
+
+fn parse_cron(expr: &str) -> StepResult<cron::Schedule> {
+    // The `cron` crate expects a seconds field; the DSL uses standard five-field expressions.
+    let expr = match expr.split_whitespace().count() {
+        5 => format!("0 {expr}"),
+        _ => expr.to_string(),
+    };
+    cron::Schedule::from_str(&expr).map_err(|e| format!("invalid cron expression '{expr}': {e}").into())
+}
+
+/// Outcome of one scheduled instance.
+#[derive(Debug, Clone)]
+pub struct ScheduledRun {
+    /// Name of the workflow, from its `document`.
+    pub workflow: String,
+    pub started_at: DateTime<Utc>,
+    pub result: StepResult<Value>,
+}
+
+/// How far a [`Backfill`] got.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackfillProgress {
+    /// Occurrences in the backfilled period; `None` until the last one has been started.
+    pub total: Option<usize>,
+    /// Instances started so far.
+    pub started: usize,
+    /// Instances that completed successfully.
+    pub completed: usize,
+    pub failed: usize,
+}
+
+impl BackfillProgress {
+    pub fn is_done(&self) -> bool {
+        self.total == Some(self.completed + self.failed)
+    }
+}
+
+/// A backfill started by [`Scheduler::backfill`]; it keeps running if the handle is dropped, until the scheduler
+/// shuts down.
+#[derive(Debug)]
+pub struct Backfill {
+    progress: watch::Receiver<BackfillProgress>,
+    handle: JoinHandle<BackfillProgress>,
+}
+
+impl Backfill {
+    pub fn progress(&self) -> BackfillProgress {
+        *self.progress.borrow()
+    }
+
+    /// Receives the progress each time an instance finishes.
+    pub fn watch(&self) -> watch::Receiver<BackfillProgress> {
+        self.progress.clone()
+    }
+
+    /// Waits until every instance finished, and returns the final progress.
+    pub async fn wait(self) -> StepResult<BackfillProgress> {
+        self.handle.await.map_err(|e| format!("backfill failed: {e}").into())
+    }
+}
+
+/// A schedule registered with [`Scheduler::schedule`], as listed by [`Scheduler::schedules`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleStatus {
+    /// Key of the workflow, as `namespace/name:version`.
+    pub workflow: String,
+    pub paused: bool,
+    /// Next `cron` or `every` start, jitter included, which is skipped if the schedule is still paused by then;
+    /// `None` for `after` and `on` schedules.
+    pub next_fire: Option<DateTime<Utc>>,
+}
+
+/// Scheduler's side of a running schedule.
+struct ScheduleHandle {
+    paused: watch::Sender<bool>,
+    next_fire: watch::Receiver<Option<DateTime<Utc>>>,
+}
+
+/// Schedule's side of its [`ScheduleHandle`].
+struct ScheduleControl {
+    paused: watch::Receiver<bool>,
+    next_fire: watch::Sender<Option<DateTime<Utc>>>,
+}
+
+const DEFAULT_MISFIRE_TOLERANCE: Duration = Duration::from_secs(60);
+
+/// Runs the schedules of registered workflows on the tokio runtime until dropped or shut down.
+pub struct Scheduler {
+    ctx: WorkflowContext,
+    timezone: Tz,
+    misfire_tolerance: Duration,
+    jitter: Duration,
+    runs: broadcast::Sender<ScheduledRun>,
+    /// Schedules by workflow key.
+    schedules: BTreeMap<String, ScheduleHandle>,
+    /// Schedules and backfills, aborted on shutdown.
+    handles: Vec<AbortHandle>,
+}
+
+impl Scheduler {
+    pub fn new(ctx: WorkflowContext) -> Self {
+        Self {
+            ctx,
+            timezone: Tz::UTC,
+            misfire_tolerance: DEFAULT_MISFIRE_TOLERANCE,
+            jitter: Duration::ZERO,
+            runs: broadcast::channel(256).0,
+            schedules: BTreeMap::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    /// Timezone in which `cron` expressions of workflows scheduled afterwards are evaluated.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// How late a `cron` or `every` start may be before it is skipped as a misfire; one minute by default.
+    pub fn with_misfire_tolerance(mut self, tolerance: Duration) -> Self {
+        self.misfire_tolerance = tolerance;
+        self
+    }
+
+    /// Upper bound of the delay added to the `cron` and `every` starts of workflows scheduled afterwards; none by
+    /// default.
+    ///
+    /// Each workflow gets its own delay, derived from its `namespace/name:version` key, so it is the same across
+    /// restarts and hosts.
+    pub fn with_jitter(mut self, max: Duration) -> Self {
+        self.jitter = max;
+        self
+    }
+
+    /// Receives the outcome of every scheduled instance started from now on.
+    pub fn runs(&self) -> broadcast::Receiver<ScheduledRun> {
+        self.runs.subscribe()
+    }
+
+    /// Starts scheduling `workflow` according to its `schedule` block. A workflow can only be scheduled once.
+    pub fn schedule(&mut self, workflow: Workflow) -> StepResult<()> {
+        let definition = workflow.definition();
+        let schedule = definition
+            .schedule
+            .as_ref()
+            .ok_or_else(|| format!("workflow '{}' has no schedule", definition.document.name))?;
+        let trigger = Trigger::try_from_definition(schedule)?;
+        let document = &definition.document;
+        let key = workflow_key(&document.namespace, &document.name, &document.version);
+        if self.schedules.contains_key(&key) {
+            return Err(format!("workflow '{key}' is already scheduled").into());
+        }
+        let splay = splay(&key, self.jitter);
+        let instance = Instance {
+            workflow: Arc::new(workflow),
+            ctx: self.ctx.clone(),
+            runs: self.runs.clone(),
+        };
+        let (paused, paused_receiver) = watch::channel(false);
+        let (next_fire_sender, next_fire) = watch::channel(None);
+        let control = ScheduleControl {
+            paused: paused_receiver,
+            next_fire: next_fire_sender,
+        };
+        let (timezone, tolerance) = (self.timezone, self.misfire_tolerance);
+        self.handles.push(tokio::spawn(instance.drive(trigger, timezone, tolerance, splay, control)).abort_handle());
+        self.schedules.insert(key, ScheduleHandle { paused, next_fire });
+        Ok(())
+    }
+
+    /// Stops the schedule of the workflow `key`, as `namespace/name:version`, from starting instances until it is
+    /// resumed. Starts due meanwhile are skipped rather than made up; instances already running are left to
+    /// complete.
+    pub fn pause(&self, key: &str) -> StepResult<()> {
+        self.handle(key)?.paused.send_replace(true);
+        Ok(())
+    }
+
+    /// Lets a paused schedule start instances again, from its next start on.
+    pub fn resume(&self, key: &str) -> StepResult<()> {
+        self.handle(key)?.paused.send_replace(false);
+        Ok(())
+    }
+
+    /// Lists the schedules, ordered by workflow key.
+    pub fn schedules(&self) -> Vec<ScheduleStatus> {
+        self.schedules
+            .iter()
+            .map(|(key, handle)| ScheduleStatus {
+                workflow: key.clone(),
+                paused: *handle.paused.borrow(),
+                next_fire: *handle.next_fire.borrow(),
+            })
+            .collect()
+    }
+
+    fn handle(&self, key: &str) -> StepResult<&ScheduleHandle> {
+        self.schedules
+            .get(key)
+            .ok_or_else(|| format!("workflow '{key}' is not scheduled").into())
+    }
+
+    /// Starts one instance of `workflow` per `cron` or `every` occurrence of its schedule between `from` and
+    /// `to`, both included, running at most `concurrency` instances at once. Each instance gets the time it was
+    /// scheduled for as input, as `{ "scheduledFor": "<RFC 3339 timestamp>" }`, and reports its outcome like
+    /// any scheduled instance; see [`runs`](Self::runs).
+    ///
+    /// `every` occurrences start at `from`; `cron` ones are evaluated in the scheduler's timezone. Occurrences
+    /// are computed as instances start, so the total is only known once the last one started.
+    ///
+    /// [`shutdown`](Self::shutdown) stops the backfill from starting further instances; instances already running
+    /// are left to complete.
+    pub fn backfill(
+        &mut self,
+        workflow: Workflow,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        concurrency: usize,
+    ) -> StepResult<Backfill> {
+        let definition = workflow.definition();
+        let schedule = definition
+            .schedule
+            .as_ref()
+            .ok_or_else(|| format!("workflow '{}' has no schedule", definition.document.name))?;
+        let trigger = Trigger::try_from_definition(schedule)?;
+        let from = from.with_timezone(&self.timezone);
+        let first = match &trigger {
+            // Occurrences are strictly after the given time, and `from` is included.
+            Trigger::Cron(_) => trigger.next_fire(&(from - chrono::Duration::nanoseconds(1))),
+            Trigger::Every(_) => Some(from),
+            Trigger::After(_) | Trigger::On(_) => {
+                return Err("only `cron` and `every` schedules can be backfilled".into());
+            }
+        };
+        let instance = Instance {
+            workflow: Arc::new(workflow),
+            ctx: self.ctx.clone(),
+            runs: self.runs.clone(),
+        };
+        let (progress, receiver) = watch::channel(BackfillProgress::default());
+        let handle = tokio::spawn(async move {
+            let mut occurrences = std::iter::successors(first, |last| trigger.next_fire(last))
+                .map(|occurrence| occurrence.with_timezone(&Utc))
+                .take_while(|occurrence| *occurrence <= to);
+            let occurrences = std::iter::from_fn(|| {
+                let occurrence = occurrences.next();
+                progress.send_modify(|progress| match occurrence {
+                    Some(_) => progress.started += 1,
+                    None => progress.total = Some(progress.started),
+                });
+                occurrence
+            });
+            // Instances run as their own tasks, so that aborting the backfill does not abort them.
+            let mut runs = futures::stream::iter(occurrences)
+                .map(|occurrence| {
+                    tokio::spawn(instance.clone().start(json!({ "scheduledFor": occurrence.to_rfc3339() })))
+                })
+                .buffer_unordered(concurrency.max(1));
+            while let Some(succeeded) = runs.next().await {
+                progress.send_modify(|progress| match succeeded {
+                    Ok(true) => progress.completed += 1,
+                    Ok(false) | Err(_) => progress.failed += 1,
+                });
+            }
+            *progress.borrow()
+        });
+        self.handles.push(handle.abort_handle());
+        Ok(Backfill {
+            progress: receiver,
+            handle,
+        })
+    }
+
+    /// Stops every schedule. Instances already running are left to complete.
+    pub fn shutdown(&mut self) {
+        self.schedules.clear();
+        for handle in self.handles.drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+/// Delay of at most `max` added to the starts of the workflow `key`, from an FNV-1a hash of the key so that it
+/// does not depend on the process.
+fn splay(key: &str, max: Duration) -> Duration {
+    let max = max.as_millis() as u64;
+    if max == 0 {
+        return Duration::ZERO;
+    }
+    let hash = key
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    Duration::from_millis(hash % (max + 1))
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("timezone", &self.timezone)
+            .field("misfire_tolerance", &self.misfire_tolerance)
+            .field("jitter", &self.jitter)
+            .field("schedules", &self.schedules.len())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+struct Instance {
+    workflow: Arc<Workflow>,
+    ctx: WorkflowContext,
+    runs: broadcast::Sender<ScheduledRun>,
+}
+
+impl Instance {
+    /// Starts instances as `trigger` fires, `splay` after each `cron` or `every` occurrence, unless `control`
+    /// says the schedule is paused.
+    async fn drive(
+        self,
+        trigger: Trigger,
+        timezone: Tz,
+        tolerance: Duration,
+        splay: Duration,
+        control: ScheduleControl,
+    ) {
+        let ScheduleControl { mut paused, next_fire } = control;
+        let splay = chrono::Duration::from_std(splay).unwrap_or_default();
+        match &trigger {
+            Trigger::Cron(_) | Trigger::Every(_) => {
+                let mut last = Utc::now().with_timezone(&timezone);
+                while let Some(next) = trigger.next_fire(&last) {
+                    let due = next.with_timezone(&Utc) + splay;
+                    next_fire.send_replace(Some(due));
+                    tokio::time::sleep((due - Utc::now()).to_std().unwrap_or_default()).await;
+                    if *paused.borrow() {
+                        last = next;
+                        continue;
+                    }
+                    let now = Utc::now();
+                    let late = (now - due).to_std().unwrap_or_default();
+                    if late > tolerance {
+                        self.misfired(due, late).await;
+                        // Resume from now rather than replaying every start missed meanwhile.
+                        last = (now - splay).with_timezone(&timezone);
+                        continue;
+                    }
+                    tokio::spawn(self.clone().start(Value::Object(Default::default())));
+                    last = next;
+                }
+            }
+            Trigger::After(delay) => loop {
+                // The scheduler dropping the sender also ends the schedule.
+                if paused.wait_for(|paused| !paused).await.is_err() {
+                    return;
+                }
+                self.clone().start(Value::Object(Default::default())).await;
+                tokio::time::sleep(*delay).await;
+            },
+            Trigger::On(listen) => loop {
+                match listen.execute(&self.ctx, Value::Object(Default::default())).await {
+                    Ok(events) => {
+                        if !*paused.borrow() {
+                            tokio::spawn(self.clone().start(events));
+                        }
+                    }
+                    Err(e) => {
+                        self.report(Utc::now(), Err(format!("schedule trigger failed: {e}").into()));
+                        return;
+                    }
+                }
+            },
+        }
+    }
+
+    /// Runs one instance with `input` and reports its outcome; returns whether it succeeded.
+    ///
+    /// Each instance gets its own `$context`, seeded from the scheduler's context, and a cancellation token
+    /// cancelled along with the scheduler's.
+    async fn start(self, input: Value) -> bool {
+        let started_at = Utc::now();
+        let ctx = WorkflowContext {
+            context: ContextData::new(self.ctx.context.get()),
+            cancellation: self.ctx.cancellation.child_token(),
+            ..self.ctx.clone()
+        };
+        let result = self.workflow.run(&ctx, input).await;
+        let succeeded = result.is_ok();
+        self.report(started_at, result);
+        succeeded
+    }
+
+    async fn misfired(&self, scheduled_for: DateTime<Utc>, late: Duration) {
+        let document = &self.workflow.definition().document;
+        let mut ctx = self.ctx.clone();
+        ctx.instance.workflow = workflow_key(&document.namespace, &document.name, &document.version);
+        let data = json!({
+            "workflow": document.name,
+            "scheduledFor": scheduled_for.to_rfc3339(),
+            "lateMs": late.as_millis() as u64,
+        });
+        alerts::publish(&ctx, SCHEDULE_MISFIRED, data).await;
+    }
+
+    fn report(&self, started_at: DateTime<Utc>, result: StepResult<Value>) {
+        // Nobody listening for outcomes is fine.
+        let _ = self.runs.send(ScheduledRun {
+            workflow: self.workflow.definition().document.name.clone(),
+            started_at,
+            result,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::messaging::{CloudEvent, EventSource, InMemoryEventBus};
+
+    fn workflow(schedule: &str) -> Workflow {
+        Workflow::try_from_yaml(&format!(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: scheduled
+  version: '1.0.0'
+schedule:
+{schedule}
+do:
+- mark:
+    run:
+      shell:
+        command: 'true'
+"
+        )).unwrap()
+    }
+
+    #[test]
+    fn cron_next_fire_respects_timezone() {
+        let definition = workflow("  cron: '30 9 * * *'");
+        let trigger = Trigger::try_from_definition(definition.definition().schedule.as_ref().unwrap()).unwrap();
+        let paris: Tz = "Europe/Paris".parse().unwrap();
+        let now = paris.with_ymd_and_hms(2025, 1, 15, 10, 0, 0).unwrap();
+
+        let next = trigger.next_fire(&now).unwrap();
+
+        assert_eq!(next, paris.with_ymd_and_hms(2025, 1, 16, 9, 30, 0).unwrap());
+        assert_eq!(next.with_timezone(&Utc).to_rfc3339(), "2025-01-16T08:30:00+00:00");
+    }
+
+    #[test]
+    fn schedule_requires_a_single_trigger() {
+        let definition = workflow("  cron: '* * * * *'\n  after:\n    seconds: 1");
+        let err = Trigger::try_from_definition(definition.definition().schedule.as_ref().unwrap()).unwrap_err();
+        assert!(err.contains("exactly one"), "{err}");
+        assert!(parse_cron("every day").is_err());
+    }
+
+    #[test]
+    fn jitter_is_fixed_per_workflow() {
+        let max = Duration::from_secs(60);
+        let first = splay("test/nightly:1.0.0", max);
+
+        assert_eq!(splay("test/nightly:1.0.0", max), first);
+        assert_ne!(splay("test/weekly:1.0.0", max), first);
+        assert!((0..100).all(|i| splay(&format!("test/report-{i}:1.0.0"), max) <= max));
+        assert_eq!(splay("test/nightly:1.0.0", Duration::ZERO), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn jittered_starts_are_delayed() {
+        let jitter = Duration::from_millis(200);
+        let splay = splay("test/scheduled:1.0.0", jitter);
+        let mut scheduler = Scheduler::new(WorkflowContext::default()).with_jitter(jitter);
+        let mut runs = scheduler.runs();
+        let scheduled = Utc::now();
+        scheduler.schedule(workflow("  every:\n    milliseconds: 20")).unwrap();
+
+        let run = runs.recv().await.unwrap();
+        scheduler.shutdown();
+        assert!(run.started_at - scheduled >= chrono::Duration::from_std(splay).unwrap(), "{splay:?}");
+    }
+
+    #[tokio::test]
+    async fn every_starts_instances_repeatedly() {
+        let mut scheduler = Scheduler::new(WorkflowContext::default());
+        let mut runs = scheduler.runs();
+        scheduler.schedule(workflow("  every:\n    milliseconds: 20")).unwrap();
+
+        for _ in 0..2 {
+            let run = runs.recv().await.unwrap();
+            assert_eq!(run.workflow, "scheduled");
+            assert!(run.result.is_ok(), "{:?}", run.result);
+        }
+        scheduler.shutdown();
+    }
+
+    #[tokio::test]
+    async fn paused_schedules_start_nothing_until_resumed() {
+        let mut scheduler = Scheduler::new(WorkflowContext::default());
+        let mut runs = scheduler.runs();
+        scheduler.schedule(workflow("  every:\n    milliseconds: 20")).unwrap();
+        let key = "test/scheduled:1.0.0";
+
+        scheduler.pause(key).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(runs.try_recv().is_err());
+        let status = &scheduler.schedules()[0];
+        assert_eq!((status.workflow.as_str(), status.paused), (key, true));
+        assert!(status.next_fire.is_some_and(|next| next > Utc::now() - chrono::Duration::milliseconds(20)));
+
+        scheduler.resume(key).unwrap();
+        let run = runs.recv().await.unwrap();
+        assert!(run.result.is_ok(), "{:?}", run.result);
+        assert!(!scheduler.schedules()[0].paused);
+
+        let err = scheduler.schedule(workflow("  every:\n    milliseconds: 20")).unwrap_err();
+        assert!(err.contains("already scheduled"), "{err}");
+        let err = scheduler.pause("test/unknown:1.0.0").unwrap_err();
+        assert_eq!(err, "workflow 'test/unknown:1.0.0' is not scheduled");
+        scheduler.shutdown();
+        assert!(scheduler.schedules().is_empty());
+    }
+
+    #[tokio::test]
+    async fn late_starts_are_skipped_and_reported() {
+        let bus = Arc::new(InMemoryEventBus::default());
+        let mut alerts = bus.subscribe().await.unwrap();
+        let mut scheduler =
+            Scheduler::new(WorkflowContext::default().with_alerts(bus.clone())).with_misfire_tolerance(Duration::ZERO);
+        let mut runs = scheduler.runs();
+        scheduler.schedule(workflow("  every:\n    milliseconds: 20")).unwrap();
+
+        let event = alerts.next().await.unwrap();
+        scheduler.shutdown();
+        assert_eq!(event.type_, SCHEDULE_MISFIRED);
+        assert_eq!(event.source, "/tideloom/test/scheduled:1.0.0");
+        assert_eq!(event.data.unwrap()["workflow"], json!("scheduled"));
+        assert!(runs.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn backfill_starts_an_instance_per_past_occurrence() {
+        let nightly = Workflow::try_from_yaml(
+            "
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: nightly
+  version: '1.0.0'
+schedule:
+  cron: '0 2 * * *'
+do:
+- report:
+    run:
+      shell:
+        command: echo
+        arguments: ['${ .scheduledFor }']
+",
+        )
+        .unwrap();
+        let mut scheduler = Scheduler::new(WorkflowContext::default());
+        let mut runs = scheduler.runs();
+        let from = Utc.with_ymd_and_hms(2025, 3, 1, 2, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 3, 4, 1, 0, 0).unwrap();
+
+        let backfill = scheduler.backfill(nightly, from, to, 2).unwrap();
+        let progress = backfill.wait().await.unwrap();
+        assert_eq!(progress, BackfillProgress { total: Some(3), started: 3, completed: 3, failed: 0 });
+        assert!(progress.is_done());
+
+        let mut days: Vec<Value> = (0..3).map(|_| runs.try_recv().unwrap().result.unwrap()).collect();
+        days.sort_by_key(ToString::to_string);
+        assert_eq!(
+            days,
+            ["2025-03-01T02:00:00+00:00", "2025-03-02T02:00:00+00:00", "2025-03-03T02:00:00+00:00"].map(Value::from)
+        );
+        let after = scheduler.backfill(workflow("  after:\n    seconds: 1"), from, to, 1);
+        assert!(after.unwrap_err().contains("can be backfilled"));
+    }
+
+    #[tokio::test]
+    async fn on_starts_an_instance_per_matching_event() {
+        let bus = Arc::new(InMemoryEventBus::default());
+        let ctx = WorkflowContext::default().with_event_source(bus.clone());
+        let mut scheduler = Scheduler::new(ctx);
+        let mut runs = scheduler.runs();
+        scheduler
+            .schedule(workflow("  on:\n    one:\n      with:\n        type: com.example.tick"))
+            .unwrap();
+
+        // Keep publishing until the scheduler's listener has subscribed.
+        let run = loop {
+            bus.publish(CloudEvent::new("tick-1", "test", "com.example.tick").with_data(json!(1)));
+            if let Ok(Ok(run)) = tokio::time::timeout(Duration::from_millis(20), runs.recv()).await {
+                break run;
+            }
+        };
+        assert!(run.result.is_ok(), "{:?}", run.result);
+    }
+}
@@ -0,0 +1,35 @@
+//! OTLP export of the `workflow`, `task` and `attempt` spans, e.g. to Jaeger or Tempo.
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::runtime::StepResult;
+
+/// Builds a tracing layer exporting spans over OTLP/HTTP to `endpoint`, such as
+/// `http://localhost:4318/v1/traces`, under `service_name`.
+///
+/// Add the layer to the host's subscriber. Keep the returned provider alive and call its `shutdown` before
+/// exiting, so that batched spans are flushed.
+pub fn otlp_layer<S>(
+    endpoint: &str,
+    service_name: &str,
+) -> StepResult<(OpenTelemetryLayer<S, Tracer>, SdkTracerProvider)>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| format!("OTLP exporter: {e}"))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name.to_string()).build())
+        .build();
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("tideloom"));
+    Ok((layer, provider))
+}
@@ -0,0 +1,624 @@
+//! Static checks of a workflow definition, run before any instance starts.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde_json::Value;
+use serverless_workflow_core::models::duration::OneOfDurationOrIso8601Expression;
+use serverless_workflow_core::models::map::Map;
+use serverless_workflow_core::models::task::TaskDefinition;
+use serverless_workflow_core::models::workflow::WorkflowDefinition;
+
+use crate::extensions::{self, NAMESPACE};
+use crate::nodes::doing::{task_fields, task_kind};
+use crate::nodes::run::RunNode;
+use crate::nodes::switch::FlowDirective;
+use crate::runtime::expr::{is_expression, parse_path};
+use crate::runtime::registry::TaskRegistry;
+use crate::runtime::timeout::parse_iso8601_duration;
+use crate::runtime::{WorkflowContext, workflow_key};
+use crate::scheduler::Trigger;
+
+/// `call` types built into the DSL; any other call must name a function from `use.functions` or a catalog.
+pub(crate) const BUILT_IN_CALLS: [&str; 4] = ["asyncapi", "grpc", "http", "openapi"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The workflow cannot run as written.
+    Error,
+    /// The workflow runs, but probably not as intended.
+    Warning,
+}
+
+/// One finding of [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// JSON pointer to the offending node, such as `/do/1/checkout/fork/branches/0/pay`.
+    pub position: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity} at {}: {}", self.position, self.message)
+    }
+}
+
+/// Checks `definition` for problems that would otherwise only surface while an instance runs.
+///
+/// Reports unreachable tasks, flow directives to unknown tasks, unknown `call` types and functions,
+/// invalid schedules, malformed ISO 8601 durations in `wait` tasks, unsupported expressions and misplaced
+/// [engine extensions](crate::extensions).
+pub fn validate(definition: &WorkflowDefinition) -> Vec<Diagnostic> {
+    validate_with(definition, &TaskRegistry::default())
+}
+
+/// Like [`validate`], also accepting the `call` targets of `tasks`, and checking the arguments of such calls
+/// against their declared schema unless they hold expressions.
+pub fn validate_with(definition: &WorkflowDefinition, tasks: &TaskRegistry) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        definition,
+        tasks,
+        diagnostics: Vec::new(),
+    };
+    if let Some(schedule) = &definition.schedule
+        && let Err(message) = Trigger::try_from_definition(schedule)
+    {
+        validator.error("/schedule", message);
+    }
+    if let Some(functions) = definition.use_.as_ref().and_then(|use_| use_.functions.as_ref()) {
+        let mut functions: Vec<_> = functions.iter().collect();
+        functions.sort_by_key(|(name, _)| *name);
+        for (name, task) in functions {
+            validator.extensions(task, &format!("/use/functions/{name}"), true);
+        }
+    }
+    validator.tasks(&definition.do_, "/do");
+    validator.diagnostics
+}
+
+/// Checks that every task of `definition` can be executed with `ctx`, so that a deployment fails fast
+/// instead of an instance failing mid-run.
+///
+/// Reports task kinds and call types this runtime has no executor for (`grpc` calls, functions not declared in
+/// `use.functions`, `raise`, `set` and `wait` tasks), processes it cannot start, sub-workflows missing from the
+/// context, and `emit`/`listen` tasks without an event sink/source. The tasks of called functions are checked
+/// once, at their declaration.
+pub fn check_support(definition: &WorkflowDefinition, ctx: &WorkflowContext) -> Vec<Diagnostic> {
+    let mut checker = SupportChecker {
+        ctx,
+        functions: definition.use_.as_ref().and_then(|use_| use_.functions.as_ref()),
+        checked: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+    checker.tasks(&definition.do_, "/do");
+    checker.diagnostics
+}
+
+struct Validator<'a> {
+    definition: &'a WorkflowDefinition,
+    tasks: &'a TaskRegistry,
+    diagnostics: Vec<Diagnostic>,
+}
+
+struct SupportChecker<'a> {
+    ctx: &'a WorkflowContext,
+    functions: Option<&'a HashMap<String, TaskDefinition>>,
+    /// Functions whose task was already checked.
+    checked: HashSet<&'a str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl SupportChecker<'_> {
+    fn unsupported(&mut self, position: &str, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            position: position.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn tasks(&mut self, tasks: &Map<String, TaskDefinition>, pointer: &str) {
+        let tasks = tasks.entries.iter().flat_map(|entry| entry.iter());
+        for (index, (name, task)) in tasks.enumerate() {
+            self.task(task, &format!("{pointer}/{index}/{name}"));
+        }
+    }
+
+    fn task(&mut self, task: &TaskDefinition, position: &str) {
+        match task {
+            TaskDefinition::Call(call) => {
+                let kind = call.call.to_ascii_lowercase();
+                let function = self.functions.and_then(|functions| functions.get_key_value(&call.call));
+                if let Some((name, task)) = function.filter(|_| !BUILT_IN_CALLS.contains(&kind.as_str())) {
+                    if self.checked.insert(name) {
+                        self.task(task, &format!("/use/functions/{name}"));
+                    }
+                } else if !["asyncapi", "http", "openapi"].contains(&kind.as_str())
+                    && !self.ctx.tasks.contains(&call.call)
+                {
+                    let what = match kind.as_str() {
+                        "grpc" => "`call: grpc`".to_string(),
+                        _ => format!("function '{}'", call.call),
+                    };
+                    self.unsupported(&format!("{position}/call"), format!("no executor for {what}"));
+                }
+            }
+            TaskDefinition::Do(def) => self.tasks(&def.do_, &format!("{position}/do")),
+            TaskDefinition::Emit(_) if self.ctx.event_sink.is_none() => {
+                self.unsupported(position, "`emit` requires an event sink in the workflow context");
+            }
+            TaskDefinition::For(def) => self.tasks(&def.do_, &format!("{position}/do")),
+            TaskDefinition::Fork(def) => {
+                for (index, entry) in def.fork.branches.entries.iter().enumerate() {
+                    for (name, branch) in entry {
+                        self.task(branch, &format!("{position}/fork/branches/{index}/{name}"));
+                    }
+                }
+            }
+            TaskDefinition::Listen(_) if self.ctx.event_source.is_none() => {
+                self.unsupported(position, "`listen` requires an event source in the workflow context");
+            }
+            TaskDefinition::Raise(_) | TaskDefinition::Set(_) | TaskDefinition::Wait(_) => {
+                self.unsupported(position, format!("no executor for `{}` tasks", task_kind(task)));
+            }
+            TaskDefinition::Run(def) => match (RunNode::try_from_run(def), &def.run.workflow) {
+                (Err(message), _) => self.unsupported(&format!("{position}/run"), message),
+                (Ok(_), Some(workflow)) => {
+                    let key = workflow_key(&workflow.namespace, &workflow.name, &workflow.version);
+                    if !self.ctx.workflows.contains_key(&key) {
+                        let message = format!("workflow '{key}' is not registered in the workflow context");
+                        self.unsupported(&format!("{position}/run/workflow"), message);
+                    }
+                }
+                (Ok(_), None) => {}
+            },
+            TaskDefinition::Try(def) => {
+                self.tasks(&def.try_, &format!("{position}/try"));
+                if let Some(handler) = &def.catch.do_ {
+                    self.tasks(handler, &format!("{position}/catch/do"));
+                }
+            }
+            TaskDefinition::Emit(_) | TaskDefinition::Listen(_) | TaskDefinition::Switch(_) => {}
+        }
+    }
+}
+
+impl Validator<'_> {
+    fn error(&mut self, position: &str, message: impl Into<String>) {
+        self.push(Severity::Error, position, message);
+    }
+
+    fn push(&mut self, severity: Severity, position: &str, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            position: position.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Checks a task list and the flow between its tasks.
+    fn tasks(&mut self, tasks: &Map<String, TaskDefinition>, pointer: &str) {
+        let tasks: Vec<(String, &TaskDefinition)> = tasks
+            .entries
+            .iter()
+            .flat_map(|entry| entry.iter().map(|(name, task)| (name.clone(), task)))
+            .collect();
+        let positions: Vec<String> = tasks
+            .iter()
+            .enumerate()
+            .map(|(index, (name, _))| format!("{pointer}/{index}/{name}"))
+            .collect();
+
+        let mut successors = Vec::new();
+        for (index, (_, task)) in tasks.iter().enumerate() {
+            let position = &positions[index];
+            let mut next = Vec::new();
+            for (directive, at) in self.directives(task, position) {
+                match directive {
+                    FlowDirective::Continue => next.push(index + 1),
+                    FlowDirective::Exit | FlowDirective::End => {}
+                    FlowDirective::Goto(target) => match tasks.iter().position(|(name, _)| *name == target) {
+                        Some(target) => next.push(target),
+                        None => self.error(&at, format!("flows to unknown task '{target}'")),
+                    },
+                }
+            }
+            successors.push(next);
+            self.task(task, position);
+        }
+
+        let mut reachable = vec![false; tasks.len()];
+        let mut pending = vec![0];
+        while let Some(index) = pending.pop() {
+            if index < tasks.len() && !reachable[index] {
+                reachable[index] = true;
+                pending.extend(&successors[index]);
+            }
+        }
+        for (index, position) in positions.iter().enumerate() {
+            if !reachable[index] {
+                self.push(Severity::Warning, position, "task is unreachable");
+            }
+        }
+    }
+
+    /// Flow directives a task can take, with the position each one is declared at.
+    fn directives(&mut self, task: &TaskDefinition, position: &str) -> Vec<(FlowDirective, String)> {
+        let parse = |then: &str| then.parse::<FlowDirective>();
+        match task {
+            TaskDefinition::Switch(switch) => {
+                let mut directives = Vec::new();
+                for (index, entry) in switch.switch.entries.iter().enumerate() {
+                    for (name, case) in entry {
+                        let at = format!("{position}/switch/{index}/{name}");
+                        if let Some(when) = &case.when {
+                            self.path(when, &format!("{at}/when"));
+                        }
+                        match case.then.as_deref().map(parse) {
+                            Some(Ok(directive)) => directives.push((directive, format!("{at}/then"))),
+                            Some(Err(message)) => self.error(&format!("{at}/then"), message),
+                            None => self.error(&at, "switch case requires `then`"),
+                        }
+                    }
+                }
+                // Without a default case, unmatched input fails the task instead of continuing.
+                directives
+            }
+            _ => match task_fields(task).then.as_deref().map(parse) {
+                None => vec![(FlowDirective::Continue, position.to_string())],
+                Some(Ok(directive)) => vec![(directive, format!("{position}/then"))],
+                Some(Err(message)) => {
+                    self.error(&format!("{position}/then"), message);
+                    vec![(FlowDirective::Continue, position.to_string())]
+                }
+            },
+        }
+    }
+
+    /// Checks a single task and recurses into the tasks it contains.
+    fn task(&mut self, task: &TaskDefinition, position: &str) {
+        self.extensions(task, position, false);
+        let fields = task_fields(task);
+        if let Some(if_) = &fields.if_ {
+            self.path(if_, &format!("{position}/if"));
+        }
+        let data = [
+            ("input", to_value(&fields.input)),
+            ("output", to_value(&fields.output)),
+            ("export", to_value(&fields.export)),
+        ];
+        for (name, value) in data {
+            self.expressions(&value, &format!("{position}/{name}"));
+        }
+        match task {
+            TaskDefinition::Call(call) => {
+                self.call(&call.call, call.with.as_ref(), position);
+                self.expressions(&to_value(&call.with), &format!("{position}/with"));
+            }
+            TaskDefinition::Do(def) => self.tasks(&def.do_, &format!("{position}/do")),
+            TaskDefinition::Emit(def) => self.expressions(&to_value(&def.emit), &format!("{position}/emit")),
+            TaskDefinition::For(def) => {
+                self.path(&def.for_.in_, &format!("{position}/for/in"));
+                if let Some(while_) = &def.while_ {
+                    self.path(while_, &format!("{position}/while"));
+                }
+                self.tasks(&def.do_, &format!("{position}/do"));
+            }
+            TaskDefinition::Fork(def) => {
+                for (index, entry) in def.fork.branches.entries.iter().enumerate() {
+                    for (name, branch) in entry {
+                        self.task(branch, &format!("{position}/fork/branches/{index}/{name}"));
+                    }
+                }
+            }
+            TaskDefinition::Listen(def) => self.expressions(&to_value(&def.listen), &format!("{position}/listen")),
+            TaskDefinition::Raise(def) => self.expressions(&to_value(&def.raise), &format!("{position}/raise")),
+            TaskDefinition::Run(def) => self.expressions(&to_value(&def.run), &format!("{position}/run")),
+            TaskDefinition::Set(def) => self.expressions(&to_value(&def.set), &format!("{position}/set")),
+            TaskDefinition::Switch(_) => {}
+            TaskDefinition::Try(def) => {
+                self.tasks(&def.try_, &format!("{position}/try"));
+                if let Some(handler) = &def.catch.do_ {
+                    self.tasks(handler, &format!("{position}/catch/do"));
+                }
+            }
+            TaskDefinition::Wait(def) => {
+                if let OneOfDurationOrIso8601Expression::Iso8601Expression(duration) = &def.duration
+                    && !is_expression(duration)
+                    && let Err(message) = parse_iso8601_duration(duration)
+                {
+                    self.error(&format!("{position}/wait"), message);
+                }
+            }
+        }
+    }
+
+    /// Checks the engine extensions declared by a task, a function of `use.functions` if `function`.
+    fn extensions(&mut self, task: &TaskDefinition, position: &str, function: bool) {
+        for (pointer, message) in extensions::check(task, function) {
+            self.error(&format!("{position}/metadata/{NAMESPACE}{pointer}"), message);
+        }
+    }
+
+    fn call(&mut self, call: &str, with: Option<&HashMap<String, Value>>, position: &str) {
+        if BUILT_IN_CALLS.iter().any(|known| call.eq_ignore_ascii_case(known)) {
+            return;
+        }
+        if let Some(custom) = self.tasks.get(call) {
+            // Without arguments, the task receives the input of the call, only known at run time.
+            let arguments = to_value(&with);
+            if let (Some(schema), Some(_)) = (&custom.input_schema, with)
+                && !has_expression(&arguments)
+                && let Err(errors) = schema.validate(&arguments)
+            {
+                for error in errors {
+                    let message = format!("call arguments do not match the schema of '{call}': {error}");
+                    self.error(&format!("{position}/with"), message);
+                }
+            }
+            return;
+        }
+        let components = self.definition.use_.as_ref();
+        let known = match call.split_once('@') {
+            // `function:version@catalog`
+            Some((_, catalog)) => components
+                .and_then(|c| c.catalogs.as_ref())
+                .is_some_and(|catalogs| catalogs.contains_key(catalog)),
+            None => components
+                .and_then(|c| c.functions.as_ref())
+                .is_some_and(|functions| functions.contains_key(call)),
+        };
+        if !known {
+            self.error(
+                &format!("{position}/call"),
+                format!("unknown call type '{call}': not a built-in call nor a function declared in `use`"),
+            );
+        }
+    }
+
+    /// Checks a bare path such as a `when` or `for.in`, which may also be written as `${ ... }`.
+    fn path(&mut self, expr: &str, position: &str) {
+        if let Err(message) = parse_path(expr) {
+            self.error(position, message);
+        }
+    }
+
+    /// Checks every `${ ... }` string nested in `value`.
+    fn expressions(&mut self, value: &Value, position: &str) {
+        match value {
+            Value::String(s) if is_expression(s) => self.path(s, position),
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.expressions(item, &format!("{position}/{index}"));
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map {
+                    self.expressions(item, &format!("{position}/{key}"));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether `value` holds a runtime expression at any depth.
+fn has_expression(value: &Value) -> bool {
+    match value {
+        Value::String(s) => is_expression(s),
+        Value::Array(items) => items.iter().any(has_expression),
+        Value::Object(map) => map.values().any(has_expression),
+        _ => false,
+    }
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use serverless_workflow_core::models::task::{TaskDefinitionFields, WaitTaskDefinition};
+
+    use super::*;
+    use crate::Workflow;
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn reports_structured_diagnostics() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: lint
+  version: '1.0.0'
+use:
+  functions:
+    notify:
+      call: http
+      with:
+        method: post
+        endpoint: https://example.com/notify
+schedule:
+  cron: 'every monday'
+do:
+  - route:
+      switch:
+        - vip:
+            when: .vip
+            then: greet
+        - other:
+            then: missing
+  - greet:
+      call: notify
+      with:
+        name: ${ .name | ascii_upcase }
+      then: end
+  - orphan:
+      call: sendEmail
+"#,
+        )
+        .unwrap();
+
+        let diagnostics = validate(workflow.definition());
+
+        let messages = messages(&diagnostics);
+        assert!(messages[0].starts_with("error at /schedule: invalid cron expression"), "{}", messages[0]);
+        assert_eq!(
+            messages[1..],
+            [
+                "error at /do/0/route/switch/1/other/then: flows to unknown task 'missing'",
+                "error at /do/1/greet/with/name: unsupported expression '${ .name | ascii_upcase }': expected a path like `.data.id`",
+                "error at /do/2/orphan/call: unknown call type 'sendEmail': not a built-in call nor a function declared in `use`",
+                "warning at /do/2/orphan: task is unreachable",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_engine_extensions() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document: { dsl: '1.0.0', namespace: test, name: extensions, version: '1.0.0' }
+use:
+  functions:
+    notify:
+      call: http
+      metadata: { tideloom: { defaults: { channel: email } } }
+      with: { method: post, endpoint: https://example.com/notify }
+do:
+  - fetch:
+      call: http
+      metadata: { owner: billing, memoize: ignored, tideloom: { memoize: { ttl: PT1M }, retries: 3 } }
+      with: { method: get, endpoint: https://example.com/orders }
+  - both:
+      fork:
+        branches:
+          - left: { call: notify, metadata: { tideloom: { defaults: {} } } }
+        onError: continue
+  - child:
+      run: { shell: { command: ls } }
+      metadata: { tideloom: { fanOut: { in: .items } } }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            messages(&validate(workflow.definition())),
+            [
+                "error at /do/0/fetch/metadata/tideloom/retries: unknown engine extension `retries`",
+                "error at /do/1/both/fork/branches/0/left/metadata/tideloom/defaults: `defaults` only applies to \
+                 functions of `use.functions`",
+                "error at /do/2/child/metadata/tideloom/fanOut: `fanOut` only applies to `run: workflow` tasks",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_wait_durations() {
+        let mut definition = Workflow::try_from_yaml(
+            "document:\n  dsl: '1.0.0'\n  namespace: test\n  name: wait\n  version: '1.0.0'\ndo: []\n",
+        )
+        .unwrap()
+        .definition()
+        .clone();
+        for (name, duration) in [("ok", "PT1H30M"), ("bad", "PT1X"), ("empty", "PT")] {
+            let wait = WaitTaskDefinition {
+                duration: OneOfDurationOrIso8601Expression::Iso8601Expression(duration.to_string()),
+                common: TaskDefinitionFields::new(),
+            };
+            definition.do_.add(name.to_string(), TaskDefinition::Wait(wait));
+        }
+
+        let positions: Vec<_> = validate(&definition).into_iter().map(|d| d.position).collect();
+
+        assert_eq!(positions, ["/do/1/bad/wait", "/do/2/empty/wait"]);
+    }
+
+    #[test]
+    fn reports_tasks_the_context_cannot_execute() {
+        let workflow = Workflow::try_from_yaml(
+            r#"
+document:
+  dsl: '1.0.0'
+  namespace: test
+  name: coverage
+  version: '1.0.0'
+use:
+  functions:
+    notify:
+      call: grpc
+      with:
+        proto:
+          endpoint: file://app/notify.proto
+        service:
+          name: Notifier
+          host: localhost
+        method: Notify
+do:
+- lookup:
+    call: grpc
+    with:
+      proto:
+        endpoint: file://app/greet.proto
+      service:
+        name: GreeterApi.Greeter
+        host: localhost
+      method: SayHello
+- stubbed:
+    call: grpc
+    metadata:
+      mock: {}
+    with:
+      proto:
+        endpoint: file://app/greet.proto
+      service:
+        name: GreeterApi.Greeter
+        host: localhost
+      method: SayHello
+- guarded:
+    try:
+    - alert:
+        call: notify
+    catch:
+      do:
+      - announce:
+          emit:
+            event:
+              with:
+                source: test
+                type: com.example.failed
+- child:
+    run:
+      workflow:
+        namespace: test
+        name: child
+        version: '1.0.0'
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            messages(&workflow.check_support(&WorkflowContext::default())),
+            [
+                "error at /do/0/lookup/call: no executor for `call: grpc`",
+                "error at /do/1/stubbed/call: no executor for `call: grpc`",
+                "error at /use/functions/notify/call: no executor for `call: grpc`",
+                "error at /do/2/guarded/catch/do/0/announce: `emit` requires an event sink in the workflow context",
+                "error at /do/3/child/run/workflow: workflow 'test/child:1.0.0' is not registered in the workflow context",
+            ]
+        );
+        let sink = std::sync::Arc::new(crate::messaging::InMemoryEventBus::default());
+        assert_eq!(workflow.check_support(&WorkflowContext::default().with_event_sink(sink)).len(), 4);
+    }
+}